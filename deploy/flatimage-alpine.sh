@@ -39,6 +39,9 @@ docker build . -t gameimage-wizard-build -f deploy/Dockerfile.wizard.build
 docker run --rm -v "$(pwd)":/workdir gameimage-wizard-build cp /dist/wizard /workdir
 cp -vf ./wizard "$BIN_DIR"/gameimage-wizard
 rm -vf ./wizard
+# Data files the wizard reads at runtime
+mkdir -p "$BUILD_DIR"/app/share/gameimage
+cp -vf "$SRC_DIR"/gui/wizard/data/winetricks.json "$BUILD_DIR"/app/share/gameimage
 
 # Launcher does not need to be static since it runs inside the arch container
 docker build . -t gameimage-launcher-build -f deploy/Dockerfile.launcher.build
@@ -148,7 +151,7 @@ chmod +x "$IMAGE"
 "$IMAGE" fim-env set 'PATH=/opt/gameimage/bin:"$PATH"' 'GIMG_BACKEND="/opt/gameimage/bin/gameimage-cli"'
 
 # Set boot command
-"$IMAGE" fim-boot sh -c '/opt/gameimage/bin/gameimage-wizard'
+"$IMAGE" fim-boot sh -c '/opt/gameimage/bin/gameimage-wizard "$@"' --
 
 # Copy binaries
 "$IMAGE" fim-exec cp -r "$BUILD_DIR"/app /opt/gameimage
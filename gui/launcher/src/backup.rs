@@ -0,0 +1,371 @@
+// Backups of the save and configuration data of the games. The package keeps the changes to its
+// files in the overlay of the configuration directory, a backup of a game compresses the changes
+// to its directory, to the save directories of its platform and to the paths listed for it
+use std::io::prelude::*;
+use std::path::{Path,PathBuf};
+use std::collections::{BTreeMap,HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use anyhow::anyhow as ah;
+
+use shared::std::PathBufExt;
+
+use crate::common;
+
+// Settings in the configuration directory
+const FILE: &str = "gameimage.backup.json";
+// Directory of the changes made to the package
+const DIR_UPPER: &str = "overlays/upperdir";
+// Directory of the games in the package, relative to its root
+const DIR_GAMES: &str = "opt/gameimage-games";
+// Directory of the backups, with a sub-directory for each game
+const DIR_BACKUPS: &str = "backups";
+const EXTENSION: &str = ".tar.gz";
+
+// pub struct Settings {{{
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings
+{
+  // Create a backup after each session
+  #[serde(default)]
+  pub auto : bool,
+  // Backups to keep of each game, zero keeps every backup
+  #[serde(default = "default_keep")]
+  pub keep : usize,
+  // Extra save paths of each game, absolute or relative to the home directory with '~/'
+  #[serde(default)]
+  pub paths : BTreeMap<String, Vec<String>>,
+} // struct Settings }}}
+
+fn default_keep() -> usize { 5 }
+
+// impl Default for Settings {{{
+impl Default for Settings
+{
+  fn default() -> Settings
+  {
+    Settings{ auto: false, keep: default_keep(), paths: BTreeMap::new() }
+  } // fn: default
+} // impl Default for Settings }}}
+
+// pub struct Target {{{
+// Game to back up, its platform and home directory locate the save data outside of its directory
+pub struct Target
+{
+  pub game : String,
+  pub platform : Option<common::Platform>,
+  pub path_dir_home : PathBuf,
+} // struct Target }}}
+
+// impl Target {{{
+impl Target
+{
+  // The game selected in the launcher
+  pub fn selected() -> Target
+  {
+    Target
+    {
+      game: PathBuf::from(std::env::var("GIMG_LAUNCHER_ROOT").unwrap_or_default()).file_name_string(),
+      platform: std::env::var("GIMG_PLATFORM").ok().and_then(|e| common::Platform::from_str(&e).ok()),
+      path_dir_home: PathBuf::from(std::env::var("HOME").unwrap_or_default()),
+    }
+  } // fn: selected
+} // impl Target }}}
+
+// pub fn read() {{{
+pub fn read(path_dir_config : &Path) -> anyhow::Result<Settings>
+{
+  let path_file_db = path_dir_config.join(FILE);
+  if ! path_file_db.exists() { return Ok(Settings::default()); } // if
+  Ok(serde_json::from_reader(std::fs::File::open(path_file_db)?)?)
+} // fn: read }}}
+
+// pub fn write() {{{
+pub fn write(path_dir_config : &Path, settings : &Settings) -> anyhow::Result<()>
+{
+  std::fs::create_dir_all(path_dir_config)?;
+  write!(std::fs::File::create(path_dir_config.join(FILE))?, "{}", serde_json::to_string(settings)?)?;
+  Ok(())
+} // fn: write }}}
+
+// pub fn format_timestamp() {{{
+// UTC date of the seconds since the unix epoch as 'YYYY-MM-DD HH:MM:SS'
+pub fn format_timestamp(seconds : u64) -> String
+{
+  let (days, time) = ((seconds / 86400) as i64, seconds % 86400);
+  // Civil date from the days since the epoch
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, time / 3600, (time % 3600) / 60, time % 60)
+} // fn: format_timestamp }}}
+
+// fn parse() {{{
+// Seconds since the epoch and counter of the backups created in the same second, from the name
+fn parse(path_file_backup : &Path) -> (u64, u64)
+{
+  let name = path_file_backup.to_path_buf().file_name_string();
+  let stem = name.trim_end_matches(EXTENSION);
+  let (seconds, count) = stem.split_once('-').unwrap_or((stem, "0"));
+  (seconds.parse().unwrap_or(0), count.parse().unwrap_or(0))
+} // fn: parse }}}
+
+// pub fn label() {{{
+// Date and size of the backup
+pub fn label(path_file_backup : &Path) -> String
+{
+  let (seconds, _) = parse(path_file_backup);
+  let size = std::fs::metadata(path_file_backup).map(|e| e.len()).unwrap_or(0);
+  format!("{} UTC, {:.1} MiB", format_timestamp(seconds), size as f64 / 1048576.0)
+} // fn: label }}}
+
+// fn get_path_dir_backups() {{{
+fn get_path_dir_backups(path_dir_config : &Path, game : &str) -> PathBuf
+{
+  path_dir_config.join(DIR_BACKUPS).join(game)
+} // fn: get_path_dir_backups }}}
+
+// fn saves() {{{
+// Save directories of the emulators relative to the home directory, the games of the platform share
+// them. Linux games have no common location and wine keeps the saves in the prefix of the game
+fn saves(platform : &common::Platform) -> &'static [&'static str]
+{
+  match platform
+  {
+    common::Platform::RETROARCH => &[".config/retroarch/saves", ".config/retroarch/states"],
+    common::Platform::PCSX2 => &[".config/PCSX2/memcards", ".config/PCSX2/sstates"],
+    common::Platform::RPCS3 => &[".config/rpcs3/dev_hdd0/home"],
+    common::Platform::LINUX | common::Platform::WINE => &[],
+  } // match
+} // fn: saves }}}
+
+// fn sources() {{{
+// Directories of the game in the overlay, relative to it. The directory of the game, the save
+// directories of its platform and the paths listed for it in the settings
+fn sources(path_dir_config : &Path, target : &Target) -> anyhow::Result<Vec<PathBuf>>
+{
+  let settings = read(path_dir_config)?;
+  let path_dir_upper = path_dir_config.join(DIR_UPPER);
+  let path_dir_home = target.path_dir_home.strip_prefix("/").unwrap_or(&target.path_dir_home);
+  let mut sources = vec![PathBuf::from(DIR_GAMES).join(&target.game)];
+  sources.extend(target.platform.iter().flat_map(saves).map(|e| path_dir_home.join(e)));
+  sources.extend(settings.paths.get(&target.game).into_iter().flatten().map(|e| match e.strip_prefix("~/")
+  {
+    Some(path_relative) => path_dir_home.join(path_relative),
+    None => PathBuf::from(e.trim_start_matches('/')),
+  }));
+  let mut unique = HashSet::new();
+  Ok(sources
+    .into_iter()
+    .filter(|e| unique.insert(e.clone()) && path_dir_upper.join(e).exists())
+    .collect())
+} // fn: sources }}}
+
+// fn tar() {{{
+// Runs tar and returns its standard output
+fn tar(args : &[&std::ffi::OsStr]) -> anyhow::Result<String>
+{
+  let output = std::process::Command::new("tar")
+    .args(args)
+    .stdin(std::process::Stdio::null())
+    .output()
+    .map_err(|e| ah!("Could not run tar: {}", e))?;
+  if output.status.success() { return Ok(String::from_utf8_lossy(&output.stdout).into_owned()); } // if
+  Err(ah!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+} // fn: tar }}}
+
+// pub fn list() {{{
+// Backups of the game, the newest first
+pub fn list(path_dir_config : &Path, game : &str) -> Vec<PathBuf>
+{
+  let mut backups : Vec<PathBuf> = match std::fs::read_dir(get_path_dir_backups(path_dir_config, game))
+  {
+    Ok(entries) => entries
+      .filter_map(|e| e.ok())
+      .map(|e| e.path())
+      .filter(|e| e.file_name_string().ends_with(EXTENSION))
+      .collect(),
+    Err(_) => vec![],
+  }; // match
+  // Names are the seconds since the epoch and a counter, compare them as numbers
+  backups.sort_by_key(|e| std::cmp::Reverse(parse(e)));
+  backups
+} // fn: list }}}
+
+// pub fn create() {{{
+// Backups created in the same second are named '<seconds>-<count>'
+pub fn create(path_dir_config : &Path, target : &Target, seconds : u64) -> anyhow::Result<PathBuf>
+{
+  let sources = sources(path_dir_config, target)?;
+  if sources.is_empty() { return Err(ah!("No save data found for '{}'", target.game)); } // if
+  let path_dir_backups = get_path_dir_backups(path_dir_config, &target.game);
+  std::fs::create_dir_all(&path_dir_backups)?;
+  // Reserve the first free name
+  let mut count = 0;
+  let path_file_backup = loop
+  {
+    let name = if count == 0 { format!("{}{}", seconds, EXTENSION) } else { format!("{}-{}{}", seconds, count, EXTENSION) };
+    let path_file_backup = path_dir_backups.join(name);
+    match std::fs::OpenOptions::new().write(true).create_new(true).open(&path_file_backup)
+    {
+      Ok(_) => break path_file_backup,
+      Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => count += 1,
+      Err(e) => return Err(ah!("Could not create backup '{}': {}", path_file_backup.string(), e)),
+    }; // match
+  }; // loop
+  let path_dir_upper = path_dir_config.join(DIR_UPPER);
+  let mut args = vec!["-czf".as_ref(), path_file_backup.as_os_str(), "-C".as_ref(), path_dir_upper.as_os_str()];
+  args.extend(sources.iter().map(|e| e.as_os_str()));
+  if let Err(e) = tar(&args).map(|_| ())
+  {
+    let _ = std::fs::remove_file(&path_file_backup);
+    return Err(e);
+  } // if
+  Ok(path_file_backup)
+} // fn: create }}}
+
+// pub fn prune() {{{
+// Erases the oldest backups of the game past the amount to keep
+pub fn prune(path_dir_config : &Path, game : &str, keep : usize) -> anyhow::Result<()>
+{
+  if keep == 0 { return Ok(()); } // if
+  for path_file_backup in list(path_dir_config, game).into_iter().skip(keep)
+  {
+    std::fs::remove_file(path_file_backup)?;
+  } // for
+  Ok(())
+} // fn: prune }}}
+
+// pub fn restore() {{{
+// Rolls the directories of the backup back through the mounted root of the package, the overlay is
+// not written directly while it is mounted. The changes in the overlay missing from the backup are
+// erased first, which also erases the files of the package changed for the first time after it
+pub fn restore(path_dir_config : &Path, path_dir_root : &Path, path_file_backup : &Path) -> anyhow::Result<()>
+{
+  let path_dir_games = path_dir_root.join(DIR_GAMES);
+  if ! path_dir_games.is_dir() { return Err(ah!("Games directory '{}' is not mounted", path_dir_games.string())); } // if
+  let entries : HashSet<PathBuf> = tar(&["-tzf".as_ref(), path_file_backup.as_os_str()])?
+    .lines()
+    .map(|e| PathBuf::from(e.trim_end_matches('/')))
+    .collect();
+  // Directories given to tar, the entries without a parent in the backup
+  let path_dir_upper = path_dir_config.join(DIR_UPPER);
+  for path_relative in entries.iter().filter(|e| e.parent().is_none_or(|e| ! entries.contains(e)))
+  {
+    // Children first, so directories created after the backup are empty when erased
+    for entry in walkdir::WalkDir::new(path_dir_upper.join(path_relative)).contents_first(true).into_iter().filter_map(|e| e.ok())
+    {
+      let path_entry = entry.path().strip_prefix(&path_dir_upper)?;
+      if entries.contains(path_entry) { continue; } // if
+      let path_target = path_dir_root.join(path_entry);
+      if entry.file_type().is_dir()
+      {
+        // Directories of the package keep their original files
+        let _ = std::fs::remove_dir(&path_target);
+        continue;
+      } // if
+      match std::fs::remove_file(&path_target)
+      {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound =>
+          return Err(ah!("Could not erase '{}': {}", path_target.string(), e)),
+        _ => (),
+      }; // match
+    } // for
+  } // for
+  tar(&["-xzf".as_ref(), path_file_backup.as_os_str(), "-C".as_ref(), path_dir_root.as_os_str()]).map(|_| ())
+} // fn: restore }}}
+
+// pub fn after_session() {{{
+// Backs up the game after a session if automatic backups are enabled
+pub fn after_session(target : &Target) -> anyhow::Result<()>
+{
+  let path_dir_config = common::dir_config()?;
+  let settings = read(&path_dir_config)?;
+  if ! settings.auto { return Ok(()); } // if
+  let path_file_backup = create(&path_dir_config, target, crate::db::playtime::now())?;
+  eprintln!("Created backup '{}'", path_file_backup.string());
+  prune(&path_dir_config, &target.game, settings.keep)
+} // fn: after_session }}}
+
+#[cfg(test)]
+mod tests
+{
+  use std::path::PathBuf;
+  use super::{create, list, prune, restore, read, write, format_timestamp, Settings, Target};
+
+  #[test]
+  fn backup_restore_and_prune()
+  {
+    let dir = shared::testing::TempDir::new();
+    let path_dir_config = dir.path.clone();
+    let path_dir_upper = path_dir_config.join("overlays/upperdir");
+    let path_dir_save = path_dir_upper.join("opt/gameimage-games/doom/saves");
+    std::fs::create_dir_all(&path_dir_save).unwrap();
+    std::fs::write(path_dir_save.join("slot1.sav"), "level 1").unwrap();
+    // Saves of the emulator and a path listed by the user in the home directory
+    let path_dir_home = path_dir_upper.join("home/gameimage");
+    std::fs::create_dir_all(path_dir_home.join(".config/retroarch/saves")).unwrap();
+    std::fs::write(path_dir_home.join(".config/retroarch/saves/doom.srm"), "srm 1").unwrap();
+    std::fs::create_dir_all(path_dir_home.join("doom")).unwrap();
+    std::fs::write(path_dir_home.join("doom/doom.cfg"), "cfg 1").unwrap();
+    // Other data of the home directory is not backed up
+    std::fs::write(path_dir_home.join("other.cfg"), "other").unwrap();
+    assert_eq!(read(&path_dir_config).unwrap(), Settings::default());
+    let settings = Settings{ auto: true, keep: 2, paths: [("doom".to_string(), vec!["~/doom".to_string()])].into() };
+    write(&path_dir_config, &settings).unwrap();
+    assert_eq!(read(&path_dir_config).unwrap(), settings);
+    let target = |game : &str| Target
+    {
+      game: game.to_string(),
+      platform: Some(crate::common::Platform::RETROARCH),
+      path_dir_home: PathBuf::from("/home/gameimage"),
+    };
+    // Games without data in the overlay have nothing to back up
+    let zelda = Target{ platform: None, ..target("zelda") };
+    assert!(create(&path_dir_config, &zelda, 1).is_err());
+    let path_file_first = create(&path_dir_config, &target("doom"), 1_700_000_000).unwrap();
+    create(&path_dir_config, &target("doom"), 1_700_000_100).unwrap();
+    // Backups in the same second get distinct names
+    create(&path_dir_config, &target("doom"), 1_700_000_200).unwrap();
+    create(&path_dir_config, &target("doom"), 1_700_000_200).unwrap();
+    assert_eq!(list(&path_dir_config, "doom").len(), 4);
+    // Changes after the first backup, made through the mount and seen in the overlay
+    let path_dir_root = path_dir_config.join("mount");
+    assert!(restore(&path_dir_config, &path_dir_root, &path_file_first).is_err());
+    for path_dir in [&path_dir_upper, &path_dir_root]
+    {
+      std::fs::create_dir_all(path_dir.join("opt/gameimage-games/doom/saves/new")).unwrap();
+      std::fs::create_dir_all(path_dir.join("home/gameimage/doom")).unwrap();
+      std::fs::write(path_dir.join("opt/gameimage-games/doom/saves/slot1.sav"), "level 2").unwrap();
+      std::fs::write(path_dir.join("opt/gameimage-games/doom/saves/new/slot2.sav"), "level 3").unwrap();
+      std::fs::write(path_dir.join("home/gameimage/doom/doom.cfg"), "cfg 2").unwrap();
+      std::fs::write(path_dir.join("home/gameimage/other.cfg"), "other 2").unwrap();
+    } // for
+    // Files of the package outside of the overlay are kept
+    std::fs::write(path_dir_root.join("opt/gameimage-games/doom/game.bin"), "game").unwrap();
+    restore(&path_dir_config, &path_dir_root, &path_file_first).unwrap();
+    let read_root = |path : &str| std::fs::read_to_string(path_dir_root.join(path)).unwrap();
+    assert_eq!(read_root("opt/gameimage-games/doom/saves/slot1.sav"), "level 1");
+    assert!(! path_dir_root.join("opt/gameimage-games/doom/saves/new").exists());
+    assert_eq!(read_root("opt/gameimage-games/doom/game.bin"), "game");
+    assert_eq!(read_root("home/gameimage/.config/retroarch/saves/doom.srm"), "srm 1");
+    assert_eq!(read_root("home/gameimage/doom/doom.cfg"), "cfg 1");
+    assert_eq!(read_root("home/gameimage/other.cfg"), "other 2");
+    // The overlay is not written directly
+    assert_eq!(std::fs::read_to_string(path_dir_save.join("slot1.sav")).unwrap(), "level 2");
+    // Newest first, the oldest are pruned
+    prune(&path_dir_config, "doom", 2).unwrap();
+    let names : Vec<_> = list(&path_dir_config, "doom").iter().map(|e| e.file_name().unwrap().to_owned()).collect();
+    assert_eq!(names, ["1700000200-1.tar.gz", "1700000200.tar.gz"]);
+    assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13:20");
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
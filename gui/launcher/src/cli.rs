@@ -0,0 +1,216 @@
+// Command line interface for scripts and other frontends
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use anyhow::anyhow as ah;
+
+use shared::std::PathBufExt;
+
+use crate::games;
+
+// Overrides of the environment, boot applies them after the databases of the game
+const ENV_OVERRIDES: &str = "GIMG_LAUNCHER_ENV";
+// Variables the launcher sets when it selects a game
+const ENV_SELECTION: [&str; 6] = ["GIMG_PLATFORM", "GIMG_LAUNCHER_BOOT", "GIMG_LAUNCHER_ROOT"
+  , "GIMG_LAUNCHER_IMG", "GIMG_LAUNCHER_IMG_GRAYSCALE", "GIMG_LAUNCHER_EXECUTABLE"];
+
+// pub struct Executable {{{
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Executable
+{
+  pub path  : String,
+  pub alias : Option<String>,
+} // struct Executable }}}
+
+// pub struct Entry {{{
+#[derive(Debug, Serialize)]
+pub struct Entry
+{
+  pub index       : usize,
+  pub name        : String,
+  pub platform    : String,
+  pub path        : String,
+  pub executables : Vec<Executable>,
+} // struct Entry }}}
+
+// pub struct DryRun {{{
+#[derive(Debug, Serialize)]
+pub struct DryRun
+{
+  // Boot script and the arguments it receives
+  pub command    : Vec<String>,
+  // Executable boot runs and its arguments, for the platforms with executables
+  pub executable : Option<String>,
+  pub arguments  : Vec<String>,
+  pub env        : BTreeMap<String,String>,
+} // struct DryRun }}}
+
+// pub fn list() {{{
+pub fn list(games : &[games::Game]) -> Vec<Entry>
+{
+  games.iter().enumerate().map(|(index, game)|
+  {
+    let (executables, aliases) = games::executables(&game.path_root).unwrap_or_default();
+    Entry
+    {
+      index,
+      name: game.path_root.file_name_string(),
+      platform: game.platform.as_str().to_string(),
+      path: game.path_root.string(),
+      executables: executables.into_iter()
+        .map(|e| Executable{ alias: aliases.get(&e).cloned(), path: e })
+        .collect(),
+    }
+  }).collect()
+} // fn: list }}}
+
+// pub fn parse_env() {{{
+// Parses a KEY=VAL override, boot reads the overrides as lines so they cannot contain a newline
+pub fn parse_env(arg : &str) -> anyhow::Result<(String,String)>
+{
+  if arg.contains('\n') { return Err(ah!("Environment override '{}' contains a newline", arg.escape_debug())); } // if
+  match arg.split_once('=')
+  {
+    Some((key, value)) if ! key.is_empty() => Ok((key.to_string(), value.to_string())),
+    _ => Err(ah!("Expected KEY=VAL, got '{}'", arg)),
+  } // match
+} // fn: parse_env }}}
+
+// pub fn set_env() {{{
+// Sets the overrides for the hooks and passes them to boot
+pub fn set_env(overrides : &[(String,String)])
+{
+  for (key, value) in overrides
+  {
+    std::env::set_var(key, value);
+  } // for
+  let lines : Vec<String> = overrides.iter().map(|(k,v)| format!("{}={}", k, v)).collect();
+  std::env::set_var(ENV_OVERRIDES, lines.join("\n"));
+} // fn: set_env }}}
+
+// fn has_executables() {{{
+// Platforms which boot an executable of the game with stored arguments
+fn has_executables(platform : &str) -> bool
+{
+  platform == "linux" || platform == "wine"
+} // fn: has_executables }}}
+
+// pub fn environment() {{{
+// Environment boot runs the executable with, in the order it applies it
+pub fn environment(path_dir_root : &Path
+  , executable : Option<&str>
+  , overrides : &[(String,String)]) -> BTreeMap<String,String>
+{
+  let mut env : BTreeMap<String,String> = ENV_SELECTION.iter()
+    .filter_map(|e| std::env::var(e).ok().map(|value| (e.to_string(), value)))
+    .collect();
+  // Environment of the game
+  env.extend(shared::db::kv::read(&path_dir_root.join("gameimage.env.json")).unwrap_or_default());
+  // Environment of the executable, the dll overrides append to the ones of the game
+  if let Some(executable) = executable
+  && let Ok(file) = std::fs::File::open(path_dir_root.join("gameimage.env.executable.json"))
+  && let Ok(db) = serde_json::from_reader::<_, BTreeMap<String,BTreeMap<String,String>>>(file)
+  && let Some(db_executable) = db.get(executable)
+  {
+    for (key, value) in db_executable
+    {
+      let value = match env.get(key)
+      {
+        Some(current) if key == "WINEDLLOVERRIDES" && ! current.is_empty() => format!("{};{}", current, value),
+        _ => value.clone(),
+      }; // match
+      env.insert(key.clone(), value);
+    } // for
+  } // if
+  env.extend(overrides.iter().cloned());
+  env
+} // fn: environment }}}
+
+// pub fn dry_run() {{{
+// Boot command of the selected game and the environment it runs with
+pub fn dry_run(game : &games::Game, args : &[String], overrides : &[(String,String)]) -> anyhow::Result<DryRun>
+{
+  let platform = game.platform.as_str();
+  let executable = match std::env::var("GIMG_LAUNCHER_EXECUTABLE")
+  {
+    Ok(executable) => Some(executable),
+    Err(_) if has_executables(platform) => crate::db::project::read(&game.path_root.join("gameimage.json"))?
+      .path_file_rom
+      .map(|e| e.string()),
+    Err(_) => None,
+  }; // match
+  // Stored arguments of the executable, split as boot splits them
+  let mut arguments : Vec<String> = match &executable
+  {
+    Some(executable) if has_executables(platform) =>
+      shared::db::kv::read(&game.path_root.join("gameimage.args.json"))
+        .unwrap_or_default()
+        .get(executable)
+        .map(|e| e.split(' ').map(|e| e.to_string()).collect())
+        .unwrap_or_default(),
+    _ => vec![],
+  }; // match
+  arguments.extend(args.iter().cloned());
+  Ok(DryRun
+  {
+    command: [game.path_boot.string()].into_iter().chain(args.iter().cloned()).collect(),
+    executable: executable.clone().filter(|_| has_executables(platform)),
+    arguments,
+    env: environment(&game.path_root, executable.as_deref(), overrides),
+  })
+} // fn: dry_run }}}
+
+#[cfg(test)]
+mod tests
+{
+  #[test]
+  fn resolve_executables_arguments_and_environment()
+  {
+    // Game with an enabled executable, an alias and stored arguments and environment
+    let dir = shared::testing::TempDir::new();
+    let path_dir_games = dir.path.clone();
+    let path_dir_root = path_dir_games.join("doom");
+    std::fs::create_dir_all(path_dir_root.join("icon")).unwrap();
+    let f_write = |name: &str, contents: &str| std::fs::write(path_dir_root.join(name), contents).unwrap();
+    f_write("gameimage.json", r#"{ "project": "doom", "platform": "WINE", "path_file_rom": "rom/doom.exe" }"#);
+    f_write("boot", "");
+    f_write("icon/icon.png", "");
+    f_write("gameimage.executable.json", r#"{ "rom/setup.exe": "1" }"#);
+    f_write("gameimage.alias.json", r#"{ "rom/setup.exe": "Setup" }"#);
+    f_write("gameimage.args.json", r#"{ "rom/setup.exe": "-lang en" }"#);
+    f_write("gameimage.env.json", r#"{ "WINEDLLOVERRIDES": "d3d9=n", "DXVK_HUD": "0" }"#);
+    f_write("gameimage.env.executable.json", r#"{ "rom/setup.exe": { "WINEDLLOVERRIDES": "dinput8=n" } }"#);
+    let games = crate::games::games_in(&path_dir_games).unwrap();
+    assert_eq!(crate::games::find(&games, "doom").unwrap(), 0);
+    assert!(crate::games::find(&games, "zelda").is_err());
+    // Listing, sorted by alias or path
+    let entries = super::list(&games);
+    assert_eq!(entries[0].platform, "wine");
+    assert_eq!(entries[0].executables, [
+      super::Executable{ path: "rom/setup.exe".into(), alias: Some("Setup".into()) },
+      super::Executable{ path: "rom/doom.exe".into(), alias: None },
+    ]);
+    // Executables by path or alias
+    assert_eq!(crate::games::find_executable(&path_dir_root, "Setup").unwrap(), "rom/setup.exe");
+    assert_eq!(crate::games::find_executable(&path_dir_root, "rom/doom.exe").unwrap(), "rom/doom.exe");
+    assert!(crate::games::find_executable(&path_dir_root, "rom/missing.exe").is_err());
+    // Overrides
+    assert_eq!(super::parse_env("DXVK_HUD=fps=1").unwrap(), ("DXVK_HUD".into(), "fps=1".into()));
+    assert!(super::parse_env("DXVK_HUD").is_err());
+    assert!(super::parse_env("DXVK_HUD=fps\nWINEDEBUG=-all").is_err());
+    let overrides = vec![("DXVK_HUD".to_string(), "1".to_string())];
+    let env = super::environment(&path_dir_root, Some("rom/setup.exe"), &overrides);
+    assert_eq!(env["WINEDLLOVERRIDES"], "d3d9=n;dinput8=n");
+    assert_eq!(env["DXVK_HUD"], "1");
+    // Dry run of the default executable
+    let dry_run = super::dry_run(&games[0], &["-skill".into(), "4".into()], &[]).unwrap();
+    assert_eq!(dry_run.command, [path_dir_root.join("boot").to_str().unwrap(), "-skill", "4"]);
+    assert_eq!(dry_run.executable.as_deref(), Some("rom/doom.exe"));
+    assert_eq!(dry_run.arguments, ["-skill", "4"]);
+    assert_eq!(dry_run.env["WINEDLLOVERRIDES"], "d3d9=n");
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use anyhow::anyhow as ah;
 
+use shared::std::PathBufExt;
+
 #[derive(Debug, Clone, Copy)]
 #[allow(dead_code)]
 pub enum Msg
@@ -9,7 +13,10 @@ pub enum Msg
   DrawSelectorExecutable,
   DrawEnv,
   DrawEnablerExecutable,
+  DrawBackup,
   DrawMenu,
+  Input(crate::input::Action),
+  Fullscreen,
   WindActivate,
   WindDeactivate,
   Quit,
@@ -55,6 +62,20 @@ impl Platform
   } // as_str
 } // impl Platform }}}
 
+// pub fn dir_config() {{{
+// The '.<package>.config' directory next to the package, it keeps the changes made to the package
+pub fn dir_config() -> anyhow::Result<PathBuf>
+{
+  if let Ok(path_dir_config) = std::env::var("FIM_DIR_CONFIG")
+  {
+    return Ok(PathBuf::from(path_dir_config));
+  } // if
+  let path_file_binary = PathBuf::from(std::env::var("FIM_FILE_BINARY")
+    .map_err(|_| ah!("Could not find the configuration directory of the package"))?);
+  let path_dir_binary = path_file_binary.parent().ok_or(ah!("Could not get the parent of the package"))?;
+  Ok(path_dir_binary.join(format!(".{}.config", path_file_binary.file_name_string())))
+} // fn: dir_config }}}
+
 #[macro_export]
 macro_rules! assign_to_arc_mutex
 {
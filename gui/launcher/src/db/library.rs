@@ -0,0 +1,71 @@
+// Favorite and hidden games of the package, kept in the configuration directory of the package
+use std::path::PathBuf;
+use std::io::prelude::*;
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+const FILE: &str = "gameimage.library.json";
+
+// pub struct Library {{{
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Library
+{
+  #[serde(default)]
+  pub favorites : BTreeSet<String>,
+  #[serde(default)]
+  pub hidden    : BTreeSet<String>,
+} // struct Library }}}
+
+// pub fn get_path_file_db() {{{
+pub fn get_path_file_db() -> anyhow::Result<PathBuf>
+{
+  Ok(crate::common::dir_config()?.join(FILE))
+} // fn: get_path_file_db }}}
+
+// pub fn read() {{{
+pub fn read(path_file_db : &PathBuf) -> anyhow::Result<Library>
+{
+  if ! path_file_db.exists() { return Ok(Library::default()); } // if
+  Ok(serde_json::from_reader(std::fs::File::open(path_file_db)?)?)
+} // fn: read }}}
+
+// pub fn write() {{{
+pub fn write(path_file_db : &PathBuf, library : &Library) -> anyhow::Result<()>
+{
+  if let Some(path_dir_parent) = path_file_db.parent() { std::fs::create_dir_all(path_dir_parent)?; } // if
+  write!(std::fs::File::create(path_file_db)?, "{}", serde_json::to_string(library)?)?;
+  Ok(())
+} // fn: write }}}
+
+// pub fn update() {{{
+// Applies f to the library of the package and saves it
+pub fn update(f: impl FnOnce(&mut Library)) -> anyhow::Result<()>
+{
+  let path_file_db = get_path_file_db()?;
+  let mut library = read(&path_file_db)?;
+  f(&mut library);
+  write(&path_file_db, &library)
+} // fn: update }}}
+
+// impl Library {{{
+impl Library
+{
+  pub fn is_favorite(&self, game : &str) -> bool { self.favorites.contains(game) }
+
+  pub fn is_hidden(&self, game : &str) -> bool { self.hidden.contains(game) }
+
+  // pub fn toggle_favorite() {{{
+  pub fn toggle_favorite(&mut self, game : &str)
+  {
+    if ! self.favorites.remove(game) { self.favorites.insert(game.to_string()); } // if
+  } // fn: toggle_favorite }}}
+
+  // pub fn toggle_hidden() {{{
+  pub fn toggle_hidden(&mut self, game : &str)
+  {
+    if ! self.hidden.remove(game) { self.hidden.insert(game.to_string()); } // if
+  } // fn: toggle_hidden }}}
+} // impl Library }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
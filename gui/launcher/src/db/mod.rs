@@ -1 +1,3 @@
+pub mod library;
+pub mod playtime;
 pub mod project;
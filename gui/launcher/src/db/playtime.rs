@@ -0,0 +1,169 @@
+// Play sessions of the games in the package, kept in the configuration directory of the package
+// since the directories of the games are read-only
+use std::path::PathBuf;
+use std::io::prelude::*;
+use std::time::{SystemTime,UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+const FILE: &str = "gameimage.playtime.json";
+
+// pub struct Session {{{
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session
+{
+  pub game       : String,
+  // Executable selected in the launcher, none for the default one
+  pub executable : Option<String>,
+  // Seconds since the unix epoch
+  pub start      : u64,
+  pub end        : u64,
+} // struct Session }}}
+
+// pub struct Playtime {{{
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Playtime
+{
+  #[serde(default)]
+  pub sessions : Vec<Session>,
+} // struct Playtime }}}
+
+// pub fn now() {{{
+pub fn now() -> u64
+{
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|e| e.as_secs()).unwrap_or(0)
+} // fn: now }}}
+
+// pub fn get_path_file_db() {{{
+// Database in the '.<package>.config' directory next to the package
+pub fn get_path_file_db() -> anyhow::Result<PathBuf>
+{
+  Ok(crate::common::dir_config()?.join(FILE))
+} // fn: get_path_file_db }}}
+
+// pub fn read() {{{
+pub fn read(path_file_db : &PathBuf) -> anyhow::Result<Playtime>
+{
+  if ! path_file_db.exists() { return Ok(Playtime::default()); } // if
+  Ok(serde_json::from_reader(std::fs::File::open(path_file_db)?)?)
+} // fn: read }}}
+
+// pub fn write() {{{
+pub fn write(path_file_db : &PathBuf, playtime : &Playtime) -> anyhow::Result<()>
+{
+  if let Some(path_dir_parent) = path_file_db.parent() { std::fs::create_dir_all(path_dir_parent)?; } // if
+  write!(std::fs::File::create(path_file_db)?, "{}", serde_json::to_string(playtime)?)?;
+  Ok(())
+} // fn: write }}}
+
+// pub fn record() {{{
+pub fn record(path_file_db : &PathBuf, session : Session) -> anyhow::Result<()>
+{
+  let mut playtime = read(path_file_db)?;
+  playtime.sessions.push(session);
+  write(path_file_db, &playtime)
+} // fn: record }}}
+
+// impl Session {{{
+impl Session
+{
+  pub fn duration(&self) -> u64
+  {
+    self.end.saturating_sub(self.start)
+  } // fn: duration
+} // impl Session }}}
+
+// impl Playtime {{{
+impl Playtime
+{
+  // pub fn total() {{{
+  // Seconds played of the game
+  pub fn total(&self, game : &str) -> u64
+  {
+    self.sessions.iter().filter(|e| e.game == game).map(|e| e.duration()).sum()
+  } // fn: total }}}
+
+  // pub fn total_executable() {{{
+  // Seconds played of the executable of the game, none for the default executable
+  pub fn total_executable(&self, game : &str, executable : Option<&str>) -> u64
+  {
+    self.sessions.iter()
+      .filter(|e| e.game == game && e.executable.as_deref() == executable)
+      .map(|e| e.duration())
+      .sum()
+  } // fn: total_executable }}}
+
+  // pub fn last_played() {{{
+  // End of the last session of the game
+  pub fn last_played(&self, game : &str) -> Option<u64>
+  {
+    self.sessions.iter().filter(|e| e.game == game).map(|e| e.end).max()
+  } // fn: last_played }}}
+} // impl Playtime }}}
+
+// pub fn format_duration() {{{
+pub fn format_duration(seconds : u64) -> String
+{
+  let (hours, minutes) = (seconds / 3600, (seconds % 3600) / 60);
+  match (hours, minutes)
+  {
+    (0, 0) => "less than a minute".to_string(),
+    (0, m) => format!("{}m", m),
+    (h, m) => format!("{}h {}m", h, m),
+  } // match
+} // fn: format_duration }}}
+
+// pub fn format_last_played() {{{
+pub fn format_last_played(timestamp : u64, now : u64) -> String
+{
+  match now.saturating_sub(timestamp) / 86400
+  {
+    0 => "today".to_string(),
+    1 => "yesterday".to_string(),
+    days => format!("{} days ago", days),
+  } // match
+} // fn: format_last_played }}}
+
+// pub fn summary() {{{
+// Total playtime and last session of the game, none if it was never played
+pub fn summary(playtime : &Playtime, game : &str) -> Option<String>
+{
+  let last_played = playtime.last_played(game)?;
+  Some(format!("Played {}, last played {}"
+    , format_duration(playtime.total(game))
+    , format_last_played(last_played, now())
+  ))
+} // fn: summary }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{read, record, format_duration, format_last_played, Session, Playtime};
+
+  #[test]
+  fn record_sessions_per_game_and_executable()
+  {
+    let dir = shared::testing::TempDir::new();
+    let path_file_db = dir.path.join("gameimage.playtime.json");
+    assert_eq!(read(&path_file_db).unwrap(), Playtime::default());
+    let session = |game: &str, executable: Option<&str>, start, end| Session
+    {
+      game: game.into(), executable: executable.map(|e| e.into()), start, end
+    };
+    record(&path_file_db, session("doom", None, 100, 3700)).unwrap();
+    record(&path_file_db, session("doom", Some("setup.exe"), 5000, 5060)).unwrap();
+    record(&path_file_db, session("zelda", None, 200, 300)).unwrap();
+    let playtime = read(&path_file_db).unwrap();
+    assert_eq!(playtime.sessions.len(), 3);
+    assert_eq!(playtime.total("doom"), 3660);
+    assert_eq!(playtime.total_executable("doom", None), 3600);
+    assert_eq!(playtime.total_executable("doom", Some("setup.exe")), 60);
+    assert_eq!(playtime.last_played("doom"), Some(5060));
+    assert_eq!(playtime.last_played("dos"), None);
+    assert_eq!(format_duration(playtime.total("doom")), "1h 1m");
+    assert_eq!(format_duration(30), "less than a minute");
+    assert_eq!(format_last_played(5060, 5060 + 86400 * 3), "3 days ago");
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -1,4 +1,5 @@
 use std::env;
+use std::sync::{Mutex,LazyLock};
 
 use fltk::prelude::*;
 use fltk::{
@@ -11,12 +12,41 @@ use fltk::{
 use shared::dimm;
 use shared::fltk::WidgetExtExtra;
 use shared::fltk::SenderExt;
+use shared::std::PathBufExt;
 use shared::{hover_blink,column,row,fixed};
 
 use crate::common;
 use crate::games;
+use crate::db;
+use crate::nav;
 use common::Msg;
 
+// Failures of the hooks of the last launch
+static FAILURES: LazyLock<Mutex<Vec<String>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+// fn: playtime_label() {{{
+// Playtime of the selected game, and of the selected executable if it is not the default one
+fn playtime_label() -> String
+{
+  let game = std::path::PathBuf::from(env::var("GIMG_LAUNCHER_ROOT").unwrap_or_default()).file_name_string();
+  let playtime = match db::playtime::get_path_file_db().and_then(|e| db::playtime::read(&e))
+  {
+    Ok(playtime) => playtime,
+    Err(e) => { eprintln!("Could not read playtime: {}", e); return String::new(); },
+  }; // match
+  let mut label = match db::playtime::summary(&playtime, &game)
+  {
+    Some(summary) => summary,
+    None => return "Never played".to_string(),
+  }; // match
+  if let Ok(executable) = env::var("GIMG_LAUNCHER_EXECUTABLE")
+  {
+    let seconds = playtime.total_executable(&game, Some(&executable));
+    label.push_str(&format!(", {} with this executable", db::playtime::format_duration(seconds)));
+  } // if
+  label
+} // fn: playtime_label }}}
+
 // fn: new {{{
 pub fn new(tx : Sender<Msg>)
 {
@@ -34,9 +64,23 @@ pub fn new(tx : Sender<Msg>)
   {
     println!("Failed to set launcher image");
   } // else
-  // Buttons
+  // Buttons in navigation order
+  let mut focusable : Vec<fltk::widget::Widget> = vec![];
   column!(col,
-    col.add(&Frame::default());
+    // Failures of the hooks over the cover
+    let mut frame_failures = Frame::default()
+      .with_align(enums::Align::Inside | enums::Align::Top | enums::Align::Left | enums::Align::Wrap);
+    frame_failures.set_label(&FAILURES.lock().unwrap().join("\n"));
+    frame_failures.set_label_color(enums::Color::Red);
+    frame_failures.set_frame(enums::FrameType::NoBox);
+    col.add(&frame_failures);
+    // Playtime of the selected game
+    let mut frame_playtime = Frame::default()
+      .with_align(enums::Align::Inside | enums::Align::Left);
+    frame_playtime.set_label(&playtime_label());
+    frame_playtime.set_label_color(enums::Color::White);
+    frame_playtime.set_frame(enums::FrameType::NoBox);
+    col.fixed(&frame_playtime, dimm::height_text() + dimm::border_half());
     row!(row,
       row.set_margin(dimm::border_half());
       fixed!(row, btn_menu, shared::fltk::button::rect::list(), dimm::width_button_rec());
@@ -46,6 +90,7 @@ pub fn new(tx : Sender<Msg>)
         fixed!(row, btn_game, shared::fltk::button::rect::joystick(), dimm::width_button_rec());
         btn_game.clone().emit(tx, Msg::DrawSelectorGame);
         hover_blink!(btn_game);
+        focusable.push(btn_game.as_base_widget());
       } // if
       if show_btn_executable
       {
@@ -53,6 +98,7 @@ pub fn new(tx : Sender<Msg>)
         fixed!(row, btn_executable, shared::fltk::button::rect::switch(), dimm::width_button_rec());
         btn_executable.clone().emit(tx, Msg::DrawSelectorExecutable);
         hover_blink!(btn_executable);
+        focusable.push(btn_executable.as_base_widget());
       } // if
       row.add(&Frame::default());
       fixed!(row, btn_play, shared::fltk::button::rect::play().with_color(enums::Color::Blue), dimm::width_button_rec());
@@ -60,12 +106,13 @@ pub fn new(tx : Sender<Msg>)
     col.fixed(&row, dimm::height_button_rec() + dimm::border());
   );
 
-  let mut fb: Vec<u8> = vec![0u8; (dimm::width_launcher() * dimm::height_launcher() * 4) as usize];
-  // Fill with required color
-  for (_, pixel) in fb.chunks_exact_mut(4).enumerate() {
-    pixel.copy_from_slice(&[0, 0, 0, 96]);
-  }
-  let image = fltk::image::RgbImage::new(&fb, dimm::width_launcher(), dimm::height_button_rec() + dimm::border(), enums::ColorDepth::Rgba8).unwrap();
+  // Translucent black, sized to the width of the row since the window is resizable in couch mode
+  let f_image = |w: i32|
+  {
+    let fb : Vec<u8> = [0u8, 0, 0, 96].repeat((w * (dimm::height_button_rec() + dimm::border())) as usize);
+    fltk::image::RgbImage::new(&fb, w, dimm::height_button_rec() + dimm::border(), enums::ColorDepth::Rgba8).unwrap()
+  };
+  let image = f_image(dimm::width_launcher());
   // Bottom background
   let mut row = row.clone();
   row.set_align(enums::Align::Inside | enums::Align::Center);
@@ -73,14 +120,18 @@ pub fn new(tx : Sender<Msg>)
   row.set_image(Some(image));
   row.resize_callback(move |s,_,_,w,_|
   {
-    let image = fltk::image::RgbImage::new(&fb, w, dimm::height_button_rec() + dimm::border(), enums::ColorDepth::Rgba8).unwrap();
-    s.set_image(Some(image));
+    s.set_image(Some(f_image(w)));
   });
 
   // Button left aligned
   btn_menu.clone().emit(tx, Msg::DrawMenu);
   hover_blink!(btn_menu);
   hover_blink!(btn_play);
+  // Navigation
+  focusable.insert(0, btn_menu.as_base_widget());
+  focusable.push(btn_play.as_base_widget());
+  nav::set(focusable, None);
+  nav::set_launch(btn_play.as_base_widget());
 
   // Button right aligned
   let clone_tx = tx.clone();
@@ -104,7 +155,7 @@ pub fn new(tx : Sender<Msg>)
     std::thread::spawn(move ||
     {
       // Launch game
-      games::launch();
+      *FAILURES.lock().unwrap() = games::launch(&[]);
       // Redraw
       clone_tx.send_activate(Msg::DrawCover);
     });
@@ -13,7 +13,9 @@ use shared::{fixed,hover_blink,hseparator_fixed,column,row,rescope};
 use fltk::prelude::ButtonExt;
 
 use crate::common;
+use crate::nav;
 
+pub mod backup;
 pub mod enabler_executable;
 pub mod environment;
 
@@ -44,6 +46,7 @@ pub fn new(tx : Sender<Msg>)
   btn_back.emit(tx, Msg::DrawCover);
   hover_blink!(btn_back);
   // Entries
+  let mut focusable = vec![];
   rescope!(col_content,
     let f_make_entry = #[clown] move |label : &str|
     {
@@ -59,15 +62,29 @@ pub fn new(tx : Sender<Msg>)
       entry
     };
     // Environment
-    f_make_entry("Environment").emit(tx, Msg::DrawEnv);
+    let mut entry = f_make_entry("Environment");
+    entry.emit(tx, Msg::DrawEnv);
+    focusable.push(entry.as_base_widget());
     // Executables
     if let Ok(str_platform) = std::env::var("GIMG_PLATFORM")
     && let Ok(platform) = common::Platform::from_str(&str_platform)
     && platform == common::Platform::WINE
     {
-      f_make_entry("Executable Configuration").emit(tx, Msg::DrawEnablerExecutable);
+      let mut entry = f_make_entry("Executable Configuration");
+      entry.emit(tx, Msg::DrawEnablerExecutable);
+      focusable.push(entry.as_base_widget());
     }
+    // Save data
+    let mut entry = f_make_entry("Save Backups");
+    entry.emit(tx, Msg::DrawBackup);
+    focusable.push(entry.as_base_widget());
+    // Couch mode
+    let mut entry = f_make_entry("Toggle Fullscreen");
+    entry.emit(tx, Msg::Fullscreen);
+    focusable.push(entry.as_base_widget());
   );
+  focusable.push(btn_back.as_base_widget());
+  nav::set(focusable, Some(Msg::DrawCover));
 } // fn: new }}}
 
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
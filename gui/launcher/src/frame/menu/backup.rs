@@ -0,0 +1,230 @@
+use std::path::{Path,PathBuf};
+use std::sync::{Mutex,LazyLock};
+
+use fltk::prelude::*;
+use fltk::{
+  output::Output,
+  app::Sender,
+  enums::{Align,Color},
+  frame::Frame,
+  misc::Spinner,
+  group,
+};
+
+use shared::dimm;
+use shared::fltk::SenderExt;
+use shared::{fixed,row,column,hpack,scroll,hover_blink,hseparator,hseparator_fixed,rescope};
+
+use crate::common;
+use crate::common::Msg;
+use crate::backup;
+use crate::nav;
+
+// Result of the last backup, restore or erase
+static STATUS: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new(String::new()));
+
+// fn run() {{{
+// Runs f in a thread with the window deactivated and shows the result
+fn run(tx: Sender<Msg>, f: impl FnOnce() -> anyhow::Result<String> + Send + 'static)
+{
+  tx.send_awake(Msg::WindDeactivate);
+  std::thread::spawn(move ||
+  {
+    *STATUS.lock().unwrap() = match f()
+    {
+      Ok(status) => status,
+      Err(e) => { eprintln!("{}", e); e.to_string() },
+    }; // match
+    tx.send_activate(Msg::DrawBackup);
+  });
+} // fn: run }}}
+
+// fn new_entry() {{{
+fn new_entry(tx: Sender<Msg>, mut col: group::Pack, path_file_backup: PathBuf, focusable: &mut Vec<fltk::widget::Widget>)
+{
+  row!(row,
+    let mut output = Output::default();
+    output.set_value(&backup::label(&path_file_backup));
+    row.add(&output);
+    fixed!(row, btn_restore, shared::fltk::button::rect::refresh(), dimm::width_button_rec());
+    fixed!(row, btn_del, shared::fltk::button::rect::del(), dimm::width_button_rec());
+    row.resize(row.x(), row.y(), row.w(), dimm::height_button_wide());
+  );
+  col.add(&row);
+  hseparator!(col, row.w());
+  // Restore
+  let mut btn_restore = btn_restore.clone();
+  btn_restore.set_color(Color::Blue);
+  btn_restore.set_tooltip("Restore this backup");
+  hover_blink!(btn_restore);
+  let clone_path_file_backup = path_file_backup.clone();
+  btn_restore.set_callback(move |_|
+  {
+    let label = backup::label(&clone_path_file_backup);
+    if fltk::dialog::choice2_default(&format!("Restore the backup from {}?", label), "Cancel", "Restore", "") != Some(1)
+    {
+      return;
+    } // if
+    let path_file_backup = clone_path_file_backup.clone();
+    run(tx, move ||
+    {
+      backup::restore(&common::dir_config()?, Path::new("/"), &path_file_backup)?;
+      Ok(format!("Restored the backup from {}", label))
+    });
+  });
+  // Erase
+  let mut btn_del = btn_del.clone();
+  btn_del.set_color(Color::Red);
+  btn_del.set_tooltip("Erase this backup");
+  hover_blink!(btn_del);
+  btn_del.set_callback(move |_|
+  {
+    *STATUS.lock().unwrap() = match std::fs::remove_file(&path_file_backup)
+    {
+      Ok(()) => "Erased the backup".to_string(),
+      Err(e) => format!("Could not erase the backup: {}", e),
+    }; // match
+    tx.send(Msg::DrawBackup);
+  });
+  focusable.push(btn_restore.as_base_widget());
+  focusable.push(btn_del.as_base_widget());
+} // fn: new_entry }}}
+
+// fn: new {{{
+pub fn new(tx : Sender<Msg>)
+{
+  let game = backup::Target::selected().game;
+  let path_dir_config = common::dir_config().map_err(|e| eprintln!("{}", e)).ok();
+  let settings = path_dir_config.as_ref().and_then(|e| backup::read(e).ok()).unwrap_or_default();
+  // Layout
+  column!(col,
+    col.set_margin(dimm::border_half());
+    fixed!(col, frame_title, Frame::default(), dimm::height_text());
+    hseparator_fixed!(col, col.w() - dimm::border()*2, dimm::border_half());
+    // Settings
+    row!(row_settings,
+      row_settings.add(&Frame::default().with_label("Back up after each session").with_align(Align::Inside | Align::Left));
+      fixed!(row_settings, btn_auto, shared::fltk::button::rect::checkbutton(), dimm::width_button_rec());
+      row_settings.fixed(&Frame::default().with_label("Keep"), dimm::width_button_wide());
+      fixed!(row_settings, spinner_keep, Spinner::default(), dimm::width_button_wide());
+    );
+    col.fixed(&row_settings, dimm::height_button_rec());
+    row!(row_paths,
+      row_paths.fixed(&Frame::default().with_label("Save paths").with_align(Align::Inside | Align::Left), dimm::width_button_wide());
+      let input_paths : fltk_evented::Listener<_> = fltk::input::Input::default().into();
+      row_paths.add(&input_paths.as_base_widget());
+    );
+    col.fixed(&row_paths, dimm::height_button_rec());
+    fixed!(col, frame_status, Frame::default().with_align(Align::Inside | Align::Left), dimm::height_text());
+    // Content
+    scroll!(scroll,
+      hpack!(col_scroll,);
+      col_scroll.set_spacing(dimm::border());
+      col_scroll.set_size(0,0);
+    );
+    hseparator_fixed!(col, col.w() - dimm::border()*2, dimm::border_half());
+    column!(col_bottom,
+      row!(row_bottom,
+        fixed!(row_bottom, btn_back, &shared::fltk::button::rect::back(), dimm::width_button_rec());
+        row_bottom.add(&Frame::default());
+        fixed!(row_bottom, btn_home, &shared::fltk::button::rect::home(), dimm::width_button_rec());
+        row_bottom.add(&Frame::default());
+        fixed!(row_bottom, btn_add, &shared::fltk::button::rect::save(), dimm::width_button_rec());
+      );
+      col_bottom.fixed(&row_bottom, dimm::height_button_rec());
+    );
+    col.fixed(&col_bottom, dimm::height_button_rec());
+  );
+
+  // Title
+  let mut frame_title = frame_title.clone();
+  frame_title.set_label(&format!("Save Backups of '{}'", game));
+  // Shown once
+  frame_status.clone().set_label(&std::mem::take(&mut *STATUS.lock().unwrap()));
+
+  // Settings
+  let mut btn_auto = btn_auto.clone();
+  btn_auto.set_value(settings.auto);
+  btn_auto.set_tooltip("Create a backup after the game exits");
+  let mut spinner_keep = spinner_keep.clone();
+  spinner_keep.set_range(0.0, 100.0);
+  spinner_keep.set_step(1.0);
+  spinner_keep.set_value(settings.keep as f64);
+  spinner_keep.set_tooltip("Backups to keep of each game, the oldest are erased, zero keeps all of them");
+  let f_save = move |f: &dyn Fn(&mut backup::Settings)|
+  {
+    let result = common::dir_config().and_then(|path_dir_config|
+    {
+      let mut settings = backup::read(&path_dir_config)?;
+      f(&mut settings);
+      backup::write(&path_dir_config, &settings)
+    });
+    if let Err(e) = result
+    {
+      eprintln!("Could not save backup settings: {}", e);
+    } // if
+  };
+  btn_auto.set_callback(move |e| { let auto = e.is_checked(); f_save(&|settings| settings.auto = auto) });
+  spinner_keep.set_callback(move |e| { let keep = e.value() as usize; f_save(&|settings| settings.keep = keep) });
+  // Extra save paths of the game, separated by ':'
+  let mut input_paths = input_paths.clone();
+  input_paths.set_tooltip("Extra save paths of the game separated by ':', absolute or starting with '~/'");
+  let _ = input_paths.insert(&settings.paths.get(&game).cloned().unwrap_or_default().join(":"));
+  input_paths.on_keyup({ let game = game.clone(); move |e|
+  {
+    let paths : Vec<String> = e.value().split(':').map(|e| e.trim().to_string()).filter(|e| ! e.is_empty()).collect();
+    f_save(&|settings| { settings.paths.insert(game.clone(), paths.clone()); });
+  }});
+
+  // Scroll resize callback
+  scroll.resize_callback({let mut c = col_scroll.clone(); move |_,_,_,w,_|
+  {
+    c.resize(c.x(),c.y(),w-dimm::border_half()*3,c.h());
+  }});
+  scroll.set_type(group::ScrollType::VerticalAlways);
+
+  // Configure buttons
+  let mut btn_back = btn_back.clone();
+  btn_back.emit(tx, Msg::DrawMenu);
+  hover_blink!(btn_back);
+  let mut btn_home = btn_home.clone();
+  btn_home.set_color(Color::Blue);
+  btn_home.emit(tx, Msg::DrawCover);
+  hover_blink!(btn_home);
+  let mut btn_add = btn_add.clone();
+  btn_add.set_color(Color::Green);
+  btn_add.set_tooltip("Back up the save data now");
+  hover_blink!(btn_add);
+  btn_add.set_callback(move |_|
+  {
+    run(tx, move ||
+    {
+      let path_dir_config = common::dir_config()?;
+      let target = backup::Target::selected();
+      let path_file_backup = backup::create(&path_dir_config, &target, crate::db::playtime::now())?;
+      backup::prune(&path_dir_config, &target.game, backup::read(&path_dir_config)?.keep)?;
+      Ok(format!("Created the backup from {}", backup::label(&path_file_backup)))
+    });
+  });
+
+  // Entries
+  let mut focusable = vec![];
+  rescope!(col_scroll,
+    let backups = path_dir_config.as_ref().map(|e| backup::list(e, &game)).unwrap_or_default();
+    if backups.is_empty()
+    {
+      col_scroll.add(&Frame::default().with_size(0, dimm::height_button_wide()).with_label("No backups yet"));
+    } // if
+    for path_file_backup in backups
+    {
+      new_entry(tx, col_scroll.clone(), path_file_backup, &mut focusable);
+    } // for
+  );
+
+  // Navigation
+  focusable.extend([btn_back.as_base_widget(), btn_home.as_base_widget(), btn_add.as_base_widget()]);
+  nav::set(focusable, Some(Msg::DrawMenu));
+  nav::set_scroll(scroll.clone());
+} // fn: new }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
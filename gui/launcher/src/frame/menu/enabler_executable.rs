@@ -16,6 +16,7 @@ use shared::std::OsStrExt;
 use shared::{fixed,row,column,hpack,scroll,hover_blink,rescope};
 
 use crate::common::Msg;
+use crate::nav;
 
 // fn find_executables() {{{
 fn find_executables() -> anyhow::Result<Vec<std::path::PathBuf>>
@@ -265,6 +266,8 @@ pub fn new(tx : Sender<Msg>)
   let mut btn_back = btn_back.clone();
   btn_back.emit(tx, Msg::DrawMenu);
   hover_blink!(btn_back);
+  // Navigation, the entries are edited with the mouse
+  nav::set(vec![btn_back.as_base_widget(), btn_home.as_base_widget()], Some(Msg::DrawMenu));
 } // fn: new }}}
 
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -11,6 +11,7 @@ use shared::dimm;
 use shared::{add,fixed,row,column,hpack,scroll,hover_blink,hseparator,hseparator_fixed,rescope};
 
 use crate::common::Msg;
+use crate::nav;
 
 // get_path_db_env() {{{
 fn get_path_db_env() -> anyhow::Result<std::path::PathBuf>
@@ -133,6 +134,8 @@ pub fn new(tx : Sender<Msg>)
   btn_add.set_color(Color::Green);
   btn_add.set_callback(move |_| new_dialog(tx));
   hover_blink!(btn_add);
+  // Navigation, the entries are edited with the mouse
+  nav::set(vec![btn_back.as_base_widget(), btn_home.as_base_widget(), btn_add.as_base_widget()], Some(Msg::DrawMenu));
 
   let path_file_db = match get_path_db_env()
   {
@@ -8,60 +8,21 @@ use fltk::{
   group,
 };
 
-use anyhow::anyhow as ah;
-
 use shared::dimm;
 use shared::fltk::WidgetExtExtra;
-use shared::std::PathBufExt;
 use shared::{fixed,hover_blink,hseparator_fixed,column,hpack,scroll,row,rescope};
 
 use common::Msg;
 
 use crate::common;
-use crate::db;
-
-// fn: get_path_db_executable() {{{
-fn get_path_db_executable() -> anyhow::Result<std::path::PathBuf>
-{
-  let mut path_db : std::path::PathBuf = std::env::var("GIMG_LAUNCHER_ROOT")?.into();
-  path_db.push("gameimage.executable.json");
-
-  Ok(path_db)
-} // fn: get_path_db_executable() }}}
-
-// fn: get_path_db_alias() {{{
-fn get_path_db_alias() -> anyhow::Result<std::path::PathBuf>
-{
-  let mut path_db : std::path::PathBuf = std::env::var("GIMG_LAUNCHER_ROOT")?.into();
-  path_db.push("gameimage.alias.json");
-  Ok(path_db)
-} // fn: get_path_db_alias() }}}
-
-// fn: get_default_executable() {{{
-fn get_default_executable() -> anyhow::Result<std::path::PathBuf>
-{
-  let path_file_db = std::path::PathBuf::from(std::env::var("GIMG_LAUNCHER_ROOT")? + "/gameimage.json");
-  let db_project = db::project::read(&path_file_db)?;
-  Ok(db_project.path_file_rom.ok_or(ah!("Could not read path_file_rom"))?.into())
-} // fn: get_default_executable()}}}
+use crate::games;
+use crate::nav;
 
 // fn: get_menu_entries() {{{
+// Executables of the selected game and their aliases
 pub fn get_menu_entries() -> anyhow::Result<(Vec<String>,HashMap<String,String>)>
 {
-  // Read executables from database
-  let db_executables = shared::db::kv::read(&get_path_db_executable()?).unwrap_or_default();
-  // Read executables from database
-  let db_alias = shared::db::kv::read(&get_path_db_alias()?).unwrap_or_default();
-  // Gather executables in a vector
-  let mut executables: Vec<String> = db_executables.keys().cloned().into_iter().collect();
-  // Avoid duplicate of default executable in the list
-  let default_executable = get_default_executable()?.string();
-  if ! executables.contains(&default_executable)
-  {
-    executables.push(default_executable);
-  } // if
-  executables.sort_by_key(|e| db_alias.get(&e.clone()).unwrap_or(&e.clone()).clone());
-  Ok((executables, db_alias))
+  games::executables(std::path::Path::new(&std::env::var("GIMG_LAUNCHER_ROOT")?))
 } // fn: new_menu_entries() }}}
 
 // fn: new {{{
@@ -99,6 +60,7 @@ pub fn new(tx : Sender<Msg>)
   btn_back.emit(tx, Msg::DrawCover);
   hover_blink!(btn_back);
   // Entries
+  let mut focusable = vec![];
   rescope!(col_content,
     let (executables, aliases) = get_menu_entries().unwrap_or_default();
     for entry in executables
@@ -142,10 +104,14 @@ pub fn new(tx : Sender<Msg>)
         });
         hover_blink!(btn);
         row_entry.add(&btn);
+        focusable.push(btn.as_base_widget());
       );
       row_entry.resize(row_entry.x(), row_entry.y(), row_entry.w(), dimm::height_button_wide() + dimm::border_half());
     } // for
   );
+  focusable.push(btn_back.as_base_widget());
+  nav::set(focusable, Some(Msg::DrawCover));
+  nav::set_scroll(scroll_content.clone());
 } // fn: new }}}
 
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -1,3 +1,5 @@
+use std::sync::{Mutex,LazyLock};
+
 use fltk::prelude::*;
 use fltk::{
   group,
@@ -5,29 +7,116 @@ use fltk::{
   app::Sender,
   button::Button,
   frame::Frame,
+  image::SharedImage,
+  menu::MenuButton,
 };
 
 use shared::dimm;
 use shared::fltk::WidgetExtExtra;
 use shared::fltk::SenderExt;
+use shared::std::PathBufExt;
 
 use crate::games;
 use crate::common;
+use crate::db;
+use crate::nav;
 use common::Msg;
 use shared::{fixed,row,column,hpack,scroll,hover_blink,hseparator_fixed,rescope};
 
+// Filter, order and visibility of the hidden games, kept across redraws
+static QUERY: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new(String::new()));
+static SORT: LazyLock<Mutex<games::Sort>> = LazyLock::new(|| Mutex::new(games::Sort::Name));
+static SHOW_HIDDEN: LazyLock<Mutex<bool>> = LazyLock::new(|| Mutex::new(false));
+
+// Width of a tile of the grid, the cover is square
+fn width_tile() -> i32 { dimm::width_button_wide()*2 }
+
+// fn toggle() {{{
+// Toggles a flag of the game in the library and redraws the grid
+fn toggle(tx: Sender<Msg>, game: String, f: fn(&mut db::library::Library, &str))
+{
+  if let Err(e) = db::library::update(|library| f(library, &game))
+  {
+    eprintln!("Could not update the library: {}", e);
+  } // if
+  tx.send_awake(Msg::DrawSelectorGame);
+} // fn: toggle }}}
+
+// fn new_tile() {{{
+// Cover, name and playtime of the game, with the favorite and hidden toggles
+fn new_tile(tx: Sender<Msg>
+  , game: games::Game
+  , library: &db::library::Library
+  , playtime: &db::playtime::Playtime) -> Button
+{
+  let name = game.path_root.file_name_string();
+  let mut btn_cover = Button::default()
+    .with_frame(enums::FrameType::FlatBox)
+    .with_color(enums::Color::BackGround)
+    .with_color_selected(enums::Color::BackGround.lighter());
+  column!(col,
+    col.fixed(&btn_cover, width_tile());
+    let mut frame_label = Frame::default()
+      .with_align(enums::Align::Inside | enums::Align::Top | enums::Align::Left | enums::Align::Clip);
+    frame_label.set_label(&format!("{}\n{}", name
+      , db::playtime::summary(playtime, &name).unwrap_or("Never played".to_string())
+    ));
+    col.fixed(&frame_label, dimm::height_text()*3);
+    row!(row,
+      row.add(&Frame::default());
+      fixed!(row, btn_favorite, shared::fltk::button::rect::star(), dimm::width_button_rec());
+      fixed!(row, btn_hidden, shared::fltk::button::rect::eye_slash(), dimm::width_button_rec());
+    );
+    col.fixed(&row, dimm::height_button_rec());
+  );
+  // Cover of the game, scaled to the tile
+  match SharedImage::load(&game.path_icon)
+  {
+    Ok(shared_image) =>
+    {
+      btn_cover.set_image_scaled(Some(shared_image.clone()));
+      btn_cover.resize_callback(move |s,_,_,_,_| { s.set_image_scaled(Some(shared_image.clone())); });
+    },
+    Err(e) => { eprintln!("Could not load icon of '{}': {}", name, e); btn_cover.set_label(&name); },
+  } // match
+  btn_cover.set_callback(move |_| { games::select(&game); tx.send_awake(Msg::DrawCover); });
+  hover_blink!(btn_cover);
+  // Favorite
+  let mut btn_favorite = btn_favorite.clone();
+  btn_favorite.set_color(if library.is_favorite(&name) { enums::Color::DarkYellow } else { enums::Color::BackGround });
+  btn_favorite.set_tooltip("Show this game first");
+  btn_favorite.set_callback({ let name = name.clone(); move |_| toggle(tx, name.clone(), db::library::Library::toggle_favorite) });
+  hover_blink!(btn_favorite);
+  // Hidden
+  let mut btn_hidden = btn_hidden.clone();
+  btn_hidden.set_color(if library.is_hidden(&name) { enums::Color::Red } else { enums::Color::BackGround });
+  btn_hidden.set_tooltip("Hide this game from the library");
+  btn_hidden.set_callback(move |_| toggle(tx, name.clone(), db::library::Library::toggle_hidden));
+  hover_blink!(btn_hidden);
+  btn_cover
+} // fn: new_tile }}}
+
 // fn: new {{{
 pub fn new(tx : Sender<Msg>)
 {
+  let sort = *SORT.lock().unwrap();
+  let show_hidden = *SHOW_HIDDEN.lock().unwrap();
   // Layout
   column!(col,
     col.set_margin(dimm::border_half());
     fixed!(col, frame_title, Frame::default(), dimm::height_text());
+    row!(row_filter,
+      let mut input_query : fltk_evented::Listener<_> = fltk::input::Input::default().into();
+      row_filter.add(&input_query.as_base_widget());
+      fixed!(row_filter, menu_sort, MenuButton::default(), dimm::width_button_wide()*2);
+      fixed!(row_filter, btn_show_hidden, shared::fltk::button::rect::eye_slash(), dimm::width_button_rec());
+    );
+    col.fixed(&row_filter, dimm::height_button_wide());
     hseparator_fixed!(col, col.w() - dimm::border()*2, dimm::border_half());
     // Content
     scroll!(scroll,
       hpack!(col_scroll,);
-      col_scroll.set_spacing(0);
+      col_scroll.set_spacing(dimm::border());
       col_scroll.set_size(0,0);
     );
     hseparator_fixed!(col, col.w() - dimm::border()*2, dimm::border_half());
@@ -44,7 +133,51 @@ pub fn new(tx : Sender<Msg>)
 
   // Title
   let mut frame_title = frame_title.clone();
-  frame_title.set_label("Switch Game");
+  frame_title.set_label("Library");
+  // Filter the games by name or platform on enter, or clear the filter once the input is emptied.
+  // The value only includes the last key once it is released
+  input_query.set_value(&QUERY.lock().unwrap().clone());
+  input_query.set_tooltip("Search by name or platform");
+  input_query.on_keyup(move |e|
+  {
+    let mut query = QUERY.lock().unwrap();
+    let key = fltk::app::event_key();
+    if key == fltk::enums::Key::Enter || e.value().is_empty()
+    {
+      if key != fltk::enums::Key::Enter && query.is_empty() { return; }
+      *query = e.value();
+      tx.send_awake(Msg::DrawSelectorGame);
+    } // if
+  });
+  // Sort by name, platform or the last session
+  let mut menu_sort = menu_sort.clone();
+  menu_sort.add_choice("Name|Platform|Recently played");
+  menu_sort.set_label(match sort
+  {
+    games::Sort::Name => "Name",
+    games::Sort::Platform => "Platform",
+    games::Sort::Recent => "Recently played",
+  });
+  menu_sort.set_callback(move |e|
+  {
+    *SORT.lock().unwrap() = match e.value()
+    {
+      1 => games::Sort::Platform,
+      2 => games::Sort::Recent,
+      _ => games::Sort::Name,
+    }; // match
+    tx.send_awake(Msg::DrawSelectorGame);
+  });
+  // Show or hide the hidden games
+  let mut btn_show_hidden = btn_show_hidden.clone();
+  btn_show_hidden.set_color(if show_hidden { enums::Color::Red } else { enums::Color::BackGround });
+  btn_show_hidden.set_tooltip(if show_hidden { "Hide the hidden games" } else { "Show the hidden games" });
+  btn_show_hidden.set_callback(move |_|
+  {
+    *SHOW_HIDDEN.lock().unwrap() = ! show_hidden;
+    tx.send_awake(Msg::DrawSelectorGame);
+  });
+  hover_blink!(btn_show_hidden);
   // Scroll resize callback
   scroll.resize_callback({let mut c = col_scroll.clone(); move |_,_,_,w,_|
   {
@@ -57,26 +190,42 @@ pub fn new(tx : Sender<Msg>)
   btn_home.emit(tx, Msg::DrawCover);
   hover_blink!(btn_home);
 
-  // Create entries
+  // Create the grid, as many tiles in a row as the width fits
+  let columns = ((col.w() - dimm::border()*2) / (width_tile() + dimm::border())).max(1) as usize;
+  let mut focusable = vec![];
   rescope!(col_scroll,
-    for game in games::games().unwrap_or_default()
+    let playtime = db::playtime::get_path_file_db().and_then(|e| db::playtime::read(&e)).unwrap_or_default();
+    let library = db::library::get_path_file_db().and_then(|e| db::library::read(&e)).unwrap_or_default();
+    let games = games::arrange(games::games().unwrap_or_default()
+      , &QUERY.lock().unwrap()
+      , sort
+      , show_hidden
+      , &library
+      , &playtime
+    );
+    if games.is_empty()
     {
-      // Set entry name
-      let osstr_name_file = game.path_root.file_name().unwrap_or_default();
-      let str_name_file = osstr_name_file.to_str().unwrap_or_default();
-      let entry = Button::default()
-        .with_size(0, dimm::height_button_wide() + dimm::border_half())
-        .with_label(&str_name_file)
-        .with_frame(enums::FrameType::FlatBox)
-        .with_color(enums::Color::BackGround)
-        .with_color_selected(enums::Color::BackGround.lighter())
-        .with_align(enums::Align::Left | enums::Align::Inside)
-        .with_callback(move |_| { games::select(&game); tx.send_awake(Msg::DrawCover); });
-      hover_blink!(entry);
-      col_scroll.add(&entry);
+      col_scroll.add(&Frame::default().with_size(0, dimm::height_button_wide()).with_label("No games found"));
+    } // if
+    let mut games = games.into_iter().peekable();
+    while games.peek().is_some()
+    {
+      row!(row,
+        row.set_spacing(dimm::border());
+        for game in games.by_ref().take(columns)
+        {
+          focusable.push(new_tile(tx, game, &library, &playtime).as_base_widget());
+        } // for
+        // Keep the width of the tiles in the last row
+        for _ in row.children() as usize..columns { row.add(&Frame::default()); } // for
+      );
+      row.resize(row.x(), row.y(), row.w(), width_tile() + dimm::height_text()*3 + dimm::height_button_rec());
     } // for
   );
-
+  focusable.push(btn_home.as_base_widget());
+  nav::set(focusable, Some(Msg::DrawCover));
+  nav::set_scroll(scroll.clone());
+  nav::set_columns(columns);
 } // fn: new }}}
 
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
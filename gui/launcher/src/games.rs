@@ -1,6 +1,7 @@
 use std::fs;
+use std::collections::HashMap;
 use std::fs::DirEntry;
-use std::path::PathBuf;
+use std::path::{Path,PathBuf};
 
 use shared::std::PathBufExt;
 
@@ -9,6 +10,9 @@ use anyhow::anyhow as ah;
 use crate::common;
 use crate::db;
 
+// Directory where the packaged projects are mounted
+pub const PATH_DIR_GAMES: &str = "/opt/gameimage-games";
+
 pub struct Game
 {
   pub platform : common::Platform,
@@ -19,15 +23,89 @@ pub struct Game
 } // Game
 
 // pub fn launch() {{{
-pub fn launch()
+// Runs the selected game between its hooks with the extra arguments, returns the failures of the
+// hooks. The game does not start if a pre-launch hook fails
+pub fn launch(args: &[String]) -> Vec<String>
 {
+  let path_dir_root = PathBuf::from(std::env::var("GIMG_LAUNCHER_ROOT").unwrap_or_default());
+  let hooks = match shared::db::hooks::read(&path_dir_root.join(shared::db::hooks::FILE))
+  {
+    Ok(hooks) => hooks,
+    Err(e) => return vec![format!("Could not read hooks: {}", e)],
+  }; // match
+  let mut failures = hooks.run(shared::db::hooks::Stage::Pre, &path_dir_root);
+  if ! failures.is_empty() { return failures; } // if
+  let start = db::playtime::now();
   let _ =  std::process::Command::new("sh")
-    .args(["-c", "$GIMG_LAUNCHER_BOOT"])
+    .args(["-c", "\"$GIMG_LAUNCHER_BOOT\" \"$@\"", "sh"])
+    .args(args)
     .stdout(std::process::Stdio::inherit())
     .stderr(std::process::Stdio::inherit())
     .output();
+  // Record the session
+  let session = db::playtime::Session
+  {
+    game: path_dir_root.file_name_string(),
+    executable: std::env::var("GIMG_LAUNCHER_EXECUTABLE").ok(),
+    start,
+    end: db::playtime::now(),
+  };
+  if let Err(e) = db::playtime::get_path_file_db().and_then(|e| db::playtime::record(&e, session))
+  {
+    eprintln!("Could not record the play session: {}", e);
+  } // if
+  failures.extend(hooks.run(shared::db::hooks::Stage::Post, &path_dir_root));
+  // Back up after the hooks, which may write save data
+  if let Err(e) = crate::backup::after_session(&crate::backup::Target::selected())
+  {
+    failures.push(format!("Could not back up the save data: {}", e));
+  } // if
+  failures
 } // fn: launch }}}
 
+// pub fn sort_by_recent() {{{
+// Sorts the most recently played games first, the games never played keep their order at the end
+pub fn sort_by_recent(games: &mut [Game], playtime: &db::playtime::Playtime)
+{
+  games.sort_by_key(|e| std::cmp::Reverse(playtime.last_played(&e.path_root.file_name_string())));
+} // fn: sort_by_recent }}}
+
+// pub enum Sort {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort
+{
+  Name,
+  Platform,
+  Recent,
+} // enum Sort }}}
+
+// pub fn arrange() {{{
+// Games of the library view, filtered by the words of the query in the name or platform and sorted
+// with the favorites first. Hidden games are removed unless show_hidden is set
+pub fn arrange(mut games: Vec<Game>
+  , query: &str
+  , sort: Sort
+  , show_hidden: bool
+  , library: &db::library::Library
+  , playtime: &db::playtime::Playtime) -> Vec<Game>
+{
+  let query = query.to_lowercase();
+  games.retain(|e|
+  {
+    let name = e.path_root.file_name_string();
+    (show_hidden || ! library.is_hidden(&name))
+      && query.split_whitespace().all(|word| name.to_lowercase().contains(word) || e.platform.as_str().contains(word))
+  });
+  match sort
+  {
+    Sort::Name => games.sort_by_key(|e| e.path_root.file_name_string().to_lowercase()),
+    Sort::Platform => games.sort_by_key(|e| (e.platform.as_str(), e.path_root.file_name_string().to_lowercase())),
+    Sort::Recent => sort_by_recent(&mut games, playtime),
+  } // match
+  games.sort_by_key(|e| ! library.is_favorite(&e.path_root.file_name_string()));
+  games
+} // fn: arrange }}}
+
 // pub fn select() {{{
 pub fn select(game: &Game)
 {
@@ -38,12 +116,45 @@ pub fn select(game: &Game)
   std::env::set_var("GIMG_LAUNCHER_IMG_GRAYSCALE", game.path_icon_grayscale.to_str().unwrap_or(""));
 } // fn: select }}}
 
-// pub fn select_by_index() {{{
-pub fn select_by_index(index: usize) -> anyhow::Result<()>
+// pub fn executables() {{{
+// Executables enabled in the game with the default one, sorted by their aliases
+pub fn executables(path_dir_root : &Path) -> anyhow::Result<(Vec<String>,HashMap<String,String>)>
+{
+  // Read executables from database
+  let db_executables = shared::db::kv::read(&path_dir_root.join("gameimage.executable.json")).unwrap_or_default();
+  // Read aliases from database
+  let db_alias = shared::db::kv::read(&path_dir_root.join("gameimage.alias.json")).unwrap_or_default();
+  // Gather executables in a vector
+  let mut executables: Vec<String> = db_executables.keys().cloned().collect();
+  // Avoid duplicate of default executable in the list
+  let db_project = db::project::read(&path_dir_root.join("gameimage.json"))?;
+  let default_executable = db_project.path_file_rom.ok_or(ah!("Could not read path_file_rom"))?.string();
+  if ! executables.contains(&default_executable)
+  {
+    executables.push(default_executable);
+  } // if
+  executables.sort_by_key(|e| db_alias.get(e).unwrap_or(e).clone());
+  Ok((executables, db_alias))
+} // fn: executables }}}
+
+// pub fn find_executable() {{{
+// Executable of the game with the path or alias
+pub fn find_executable(path_dir_root : &Path, query : &str) -> anyhow::Result<String>
 {
-  select(games()?.get(index).ok_or(ah!("Index out of bounds"))?);
-  Ok(())
-} // fn: select_by_index }}}
+  let (executables, aliases) = executables(path_dir_root)?;
+  executables.into_iter()
+    .find(|e| e == query || aliases.get(e).is_some_and(|alias| alias == query))
+    .ok_or(ah!("No executable with path or alias '{}'", query))
+} // fn: find_executable }}}
+
+// pub fn find() {{{
+// Index of the game with the project name
+pub fn find(games : &[Game], name : &str) -> anyhow::Result<usize>
+{
+  games.iter()
+    .position(|e| e.path_root.file_name_string() == name)
+    .ok_or(ah!("No game named '{}'", name))
+} // fn: find }}}
 
 // fn game() {{{
 fn game(path_root : PathBuf) -> anyhow::Result<Game>
@@ -60,10 +171,11 @@ fn game(path_root : PathBuf) -> anyhow::Result<Game>
   Err(ah!("Could not include project from '{}'", path_root.string()))
 } // fn game() }}}
 
-// pub fn games() {{{
-pub fn games() -> anyhow::Result<Vec<Game>>
+// fn games_in() {{{
+// Games in the sub-directories of path_dir, invalid entries are skipped
+pub fn games_in(path_dir : &Path) -> anyhow::Result<Vec<Game>>
 {
-  let vec_entries : Vec<DirEntry> = fs::read_dir("/opt/gameimage-games")?
+  let vec_entries : Vec<DirEntry> = fs::read_dir(path_dir)?
     .filter_map(|e| { e.ok() })
     .filter(|e|{ e.path().is_dir() })
     .collect();
@@ -83,6 +195,83 @@ pub fn games() -> anyhow::Result<Vec<Game>>
   vec_data.sort_by(|a, b| return a.path_root.string().partial_cmp(&b.path_root.string()).unwrap());
 
   Ok(vec_data)
+} // games_in() }}}
+
+// pub fn games() {{{
+pub fn games() -> anyhow::Result<Vec<Game>>
+{
+  games_in(Path::new(PATH_DIR_GAMES))
 } // games() }}}
 
+#[cfg(test)]
+mod tests
+{
+  use std::path::Path;
+
+  #[test]
+  fn games_in_skips_invalid_entries()
+  {
+    let path_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/games");
+    let games = super::games_in(&path_dir).unwrap();
+    let names : Vec<_> = games.iter().map(|e| e.path_root.file_name().unwrap().to_owned()).collect();
+    assert_eq!(names, ["doom", "zelda"]);
+    assert_eq!(games[0].platform.as_str(), "wine");
+    assert_eq!(games[0].path_boot, path_dir.join("doom/boot"));
+    assert_eq!(games[1].path_icon, path_dir.join("zelda/icon/icon.png"));
+    assert_eq!(games[1].path_icon_grayscale, path_dir.join("zelda/icon/icon.grayscale.png"));
+  }
+
+  #[test]
+  fn sort_by_recent_puts_never_played_last()
+  {
+    let path_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/games");
+    let mut games = super::games_in(&path_dir).unwrap();
+    let session = |game: &str, end| crate::db::playtime::Session{ game: game.into(), executable: None, start: 0, end };
+    let playtime = crate::db::playtime::Playtime{ sessions: vec![session("zelda", 10), session("doom", 5)] };
+    super::sort_by_recent(&mut games, &playtime);
+    let names : Vec<_> = games.iter().map(|e| e.path_root.file_name().unwrap().to_owned()).collect();
+    assert_eq!(names, ["zelda", "doom"]);
+    let playtime = crate::db::playtime::Playtime{ sessions: vec![session("doom", 1)] };
+    super::sort_by_recent(&mut games, &playtime);
+    let names : Vec<_> = games.iter().map(|e| e.path_root.file_name().unwrap().to_owned()).collect();
+    assert_eq!(names, ["doom", "zelda"]);
+  }
+
+  #[test]
+  fn arrange_filters_sorts_and_pins_favorites()
+  {
+    let path_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/games");
+    let session = |game: &str, end| crate::db::playtime::Session{ game: game.into(), executable: None, start: 0, end };
+    let playtime = crate::db::playtime::Playtime{ sessions: vec![session("zelda", 10)] };
+    let mut library = crate::db::library::Library::default();
+    let f_names = |query, sort, show_hidden, library: &crate::db::library::Library| -> Vec<String>
+    {
+      super::arrange(super::games_in(&path_dir).unwrap(), query, sort, show_hidden, library, &playtime)
+        .iter()
+        .map(|e| e.path_root.file_name().unwrap().to_str().unwrap().to_owned())
+        .collect()
+    };
+    assert_eq!(f_names("", super::Sort::Name, false, &library), ["doom", "zelda"]);
+    assert_eq!(f_names("", super::Sort::Platform, false, &library), ["zelda", "doom"]);
+    assert_eq!(f_names("", super::Sort::Recent, false, &library), ["zelda", "doom"]);
+    // Every word matches the name or the platform
+    assert_eq!(f_names("ZEL", super::Sort::Name, false, &library), ["zelda"]);
+    assert_eq!(f_names("wine doom", super::Sort::Name, false, &library), ["doom"]);
+    assert!(f_names("wine zelda", super::Sort::Name, false, &library).is_empty());
+    // Favorites first, hidden games only when shown
+    library.toggle_favorite("zelda");
+    library.toggle_hidden("doom");
+    assert_eq!(f_names("", super::Sort::Name, false, &library), ["zelda"]);
+    assert_eq!(f_names("", super::Sort::Name, true, &library), ["zelda", "doom"]);
+    library.toggle_favorite("zelda");
+    assert_eq!(f_names("", super::Sort::Name, true, &library), ["doom", "zelda"]);
+  }
+
+  #[test]
+  fn games_in_fails_without_directory()
+  {
+    assert!(super::games_in(Path::new("/nonexistent/gameimage-games")).is_err());
+  }
+}
+
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,273 @@
+// Maps the keyboard and the gamepads, read with the linux joystick api, to navigation actions
+use std::io::Read;
+use std::path::PathBuf;
+
+use fltk::enums::Key;
+use fltk::app::Sender;
+
+use crate::common::Msg;
+
+// Joystick api event types, the init flag marks the synthetic events sent with the current state
+// when the device is opened
+const JS_EVENT_BUTTON: u8 = 0x01;
+const JS_EVENT_AXIS: u8 = 0x02;
+const JS_EVENT_INIT: u8 = 0x80;
+// Size of struct js_event
+const JS_EVENT_SIZE: usize = 8;
+// Axis deflection to count as a direction
+const AXIS_THRESHOLD: i16 = 16384;
+
+// pub enum Action {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action
+{
+  Up,
+  Down,
+  Left,
+  Right,
+  Select,
+  Back,
+  Launch,
+  Menu,
+} // enum Action }}}
+
+// pub struct Event {{{
+// Event of the linux joystick api
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event
+{
+  pub time   : u32,
+  pub value  : i16,
+  pub kind   : u8,
+  pub number : u8,
+} // struct Event }}}
+
+// impl Event {{{
+impl Event
+{
+  pub fn from_bytes(bytes: &[u8; JS_EVENT_SIZE]) -> Event
+  {
+    Event
+    {
+      time: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+      value: i16::from_ne_bytes([bytes[4], bytes[5]]),
+      kind: bytes[6],
+      number: bytes[7],
+    }
+  } // fn: from_bytes
+} // impl Event }}}
+
+// pub trait Source {{{
+// Stream of joystick events, none when the stream ends
+pub trait Source
+{
+  fn next(&mut self) -> Option<Event>;
+} // trait Source }}}
+
+// pub struct Device {{{
+// Joystick device, e.g., /dev/input/js0
+pub struct Device
+{
+  file : std::fs::File,
+} // struct Device }}}
+
+// impl Device {{{
+impl Device
+{
+  pub fn open(path_file_device: &PathBuf) -> anyhow::Result<Device>
+  {
+    Ok(Device{ file: std::fs::File::open(path_file_device)? })
+  } // fn: open
+} // impl Device }}}
+
+// impl Source for Device {{{
+impl Source for Device
+{
+  fn next(&mut self) -> Option<Event>
+  {
+    let mut bytes = [0u8; JS_EVENT_SIZE];
+    self.file.read_exact(&mut bytes).ok()?;
+    Some(Event::from_bytes(&bytes))
+  } // fn: next
+} // impl Source for Device }}}
+
+// pub struct Recorded {{{
+// Events recorded from a device, e.g., with 'cat /dev/input/js0 > events.bin'
+#[cfg(test)]
+pub struct Recorded
+{
+  events : std::collections::VecDeque<Event>,
+} // struct Recorded }}}
+
+// impl Recorded {{{
+#[cfg(test)]
+impl Recorded
+{
+  pub fn from_bytes(bytes: &[u8]) -> Recorded
+  {
+    let events = bytes.chunks_exact(JS_EVENT_SIZE)
+      .map(|e| Event::from_bytes(e.try_into().unwrap()))
+      .collect();
+    Recorded{ events }
+  } // fn: from_bytes
+} // impl Recorded }}}
+
+// impl Source for Recorded {{{
+#[cfg(test)]
+impl Source for Recorded
+{
+  fn next(&mut self) -> Option<Event>
+  {
+    self.events.pop_front()
+  } // fn: next
+} // impl Source for Recorded }}}
+
+// pub struct Mapper {{{
+// Converts joystick events to actions, an axis triggers once when it leaves the center and
+// again only after it returns
+#[derive(Default)]
+pub struct Mapper
+{
+  axes : std::collections::HashMap<u8, i8>,
+} // struct Mapper }}}
+
+// impl Mapper {{{
+impl Mapper
+{
+  // pub fn map() {{{
+  pub fn map(&mut self, event: Event) -> Option<Action>
+  {
+    if event.kind & JS_EVENT_INIT != 0 { return None; } // if
+    match event.kind
+    {
+      // Xbox layout: A, B, back and start
+      JS_EVENT_BUTTON if event.value == 1 => match event.number
+      {
+        0 => Some(Action::Select),
+        1 => Some(Action::Back),
+        6 => Some(Action::Menu),
+        7 => Some(Action::Launch),
+        _ => None,
+      }, // match
+      JS_EVENT_AXIS =>
+      {
+        let direction : i8 = if event.value <= -AXIS_THRESHOLD { -1 }
+          else if event.value >= AXIS_THRESHOLD { 1 }
+          else { 0 };
+        let previous = self.axes.insert(event.number, direction).unwrap_or(0);
+        if direction == previous { return None; } // if
+        // Left stick and directional pad
+        match (event.number, direction)
+        {
+          (0 | 6, -1) => Some(Action::Left),
+          (0 | 6, 1) => Some(Action::Right),
+          (1 | 7, -1) => Some(Action::Up),
+          (1 | 7, 1) => Some(Action::Down),
+          _ => None,
+        } // match
+      },
+      _ => None,
+    } // match
+  } // fn: map }}}
+} // impl Mapper }}}
+
+// pub fn actions() {{{
+// Actions of every event in the source
+#[cfg(test)]
+pub fn actions(source: &mut impl Source) -> Vec<Action>
+{
+  let mut mapper = Mapper::default();
+  std::iter::from_fn(|| source.next()).filter_map(|e| mapper.map(e)).collect()
+} // fn: actions }}}
+
+// pub fn key() {{{
+pub fn key(key: Key) -> Option<Action>
+{
+  if key == Key::Up { Some(Action::Up) }
+  else if key == Key::Down { Some(Action::Down) }
+  else if key == Key::Left { Some(Action::Left) }
+  else if key == Key::Right { Some(Action::Right) }
+  else if key == Key::Enter || key == Key::KPEnter || key == Key::from_char(' ') { Some(Action::Select) }
+  else if key == Key::Escape || key == Key::BackSpace { Some(Action::Back) }
+  else if key == Key::F5 { Some(Action::Launch) }
+  else if key == Key::from_char('m') { Some(Action::Menu) }
+  else { None }
+} // fn: key }}}
+
+// fn get_path_file_device() {{{
+// Device set in GIMG_LAUNCHER_JOYSTICK, or the first joystick found
+fn get_path_file_device() -> Option<PathBuf>
+{
+  if let Ok(device) = std::env::var("GIMG_LAUNCHER_JOYSTICK") { return Some(PathBuf::from(device)); } // if
+  (0..4).map(|e| PathBuf::from(format!("/dev/input/js{}", e))).find(|e| e.exists())
+} // fn: get_path_file_device }}}
+
+// pub fn spawn() {{{
+// Sends the actions of the gamepad to the event loop, waits for a gamepad to be plugged in and
+// reopens it after a disconnect
+pub fn spawn(tx: Sender<Msg>)
+{
+  std::thread::spawn(move ||
+  {
+    loop
+    {
+      if let Some(path_file_device) = get_path_file_device()
+      && let Ok(mut device) = Device::open(&path_file_device)
+      {
+        println!("Reading gamepad '{}'", path_file_device.display());
+        let mut mapper = Mapper::default();
+        while let Some(event) = device.next()
+        {
+          if let Some(action) = mapper.map(event)
+          {
+            tx.send(Msg::Input(action));
+            fltk::app::awake();
+          } // if
+        } // while
+      } // if
+      std::thread::sleep(std::time::Duration::from_secs(2));
+    } // loop
+  });
+} // fn: spawn }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{actions, Action, Recorded, JS_EVENT_AXIS, JS_EVENT_BUTTON, JS_EVENT_INIT};
+
+  fn event(value: i16, kind: u8, number: u8) -> Vec<u8>
+  {
+    [0u32.to_ne_bytes().as_slice(), value.to_ne_bytes().as_slice(), &[kind, number]].concat()
+  }
+
+  #[test]
+  fn map_recorded_events_to_actions()
+  {
+    let bytes = [
+      // State of the device when opened
+      event(1, JS_EVENT_BUTTON | JS_EVENT_INIT, 0),
+      event(0, JS_EVENT_AXIS | JS_EVENT_INIT, 1),
+      // Stick held down triggers once
+      event(20000, JS_EVENT_AXIS, 1),
+      event(32767, JS_EVENT_AXIS, 1),
+      event(0, JS_EVENT_AXIS, 1),
+      event(20000, JS_EVENT_AXIS, 1),
+      // Directional pad to the left and back to the center
+      event(-32767, JS_EVENT_AXIS, 6),
+      event(0, JS_EVENT_AXIS, 6),
+      // Button press and release
+      event(1, JS_EVENT_BUTTON, 0),
+      event(0, JS_EVENT_BUTTON, 0),
+      event(1, JS_EVENT_BUTTON, 1),
+      event(1, JS_EVENT_BUTTON, 7),
+      // Unmapped button and a truncated event
+      event(1, JS_EVENT_BUTTON, 3),
+      vec![0, 0, 0],
+    ].concat();
+    assert_eq!(actions(&mut Recorded::from_bytes(&bytes))
+      , [Action::Down, Action::Down, Action::Left, Action::Select, Action::Back, Action::Launch]
+    );
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -11,6 +11,10 @@ use fltk::{
 use shared::svg;
 
 mod games;
+mod cli;
+mod backup;
+mod input;
+mod nav;
 mod frame;
 mod common;
 mod db;
@@ -36,16 +40,18 @@ impl Gui
 {
 
 // fn: new {{{
-pub fn new() -> Self
+// Couch mode fills the screen with larger text for TVs and handhelds
+pub fn new(couch: bool) -> Self
 {
   let app =  app::App::default().with_scheme(app::Scheme::Gtk);
   app::set_frame_type(FrameType::BorderBox);
-  app::set_font_size(dimm::height_text());
+  app::set_font_size(if couch { dimm::height_text()*2 } else { dimm::height_text() });
   let mut wind = Window::default()
     .with_label("GameImage")
     .with_size(dimm::width_launcher(), dimm::height_launcher())
     .center_screen();
-  wind.make_resizable(false);
+  wind.make_resizable(couch);
+  wind.fullscreen(couch);
 
   shared::fltk::theme();
 
@@ -61,6 +67,19 @@ pub fn new() -> Self
 
   let (tx, rx) = fltk::app::channel();
 
+  // Keyboard navigation, keys the focused widget does not handle reach the window
+  wind.handle(move |_, ev|
+  {
+    if ev != fltk::enums::Event::KeyDown { return false; } // if
+    match input::key(app::event_key())
+    {
+      Some(action) => { tx.send(Msg::Input(action)); true },
+      None => false,
+    } // match
+  });
+  // Gamepad navigation
+  input::spawn(tx);
+
   Gui
   {
     app,
@@ -83,6 +102,7 @@ fn redraw(&mut self, msg: Msg)
     Some(Msg::DrawSelectorExecutable) => frame::selector_executable::new(self.tx),
     Some(Msg::DrawEnablerExecutable) => frame::menu::enabler_executable::new(self.tx),
     Some(Msg::DrawEnv) => frame::menu::environment::new(self.tx),
+    Some(Msg::DrawBackup) => frame::menu::backup::new(self.tx),
     Some(Msg::DrawMenu) => frame::menu::new(self.tx),
     _ => (),
   }
@@ -129,6 +149,14 @@ fn init(&mut self)
         shared::fltk::set_active(self.wind.clone(), false);
         app::awake();
       }
+      // Navigation is disabled while the game runs
+      Some(Msg::Input(action)) => if self.wind.active() { nav::apply(self.tx, action); },
+      Some(Msg::Fullscreen) =>
+      {
+        let fullscreen = ! self.wind.fullscreen_active();
+        self.wind.make_resizable(fullscreen);
+        self.wind.fullscreen(fullscreen);
+      }
       Some(Msg::Quit) =>
       {
         app::quit();
@@ -147,10 +175,31 @@ fn init(&mut self)
 #[command(version, about, long_about = None)]
 struct Cli
 {
-  #[arg(long, value_name = "INDEX")]
-  select_index: Option<i32>,
+  #[arg(long, value_name = "INDEX", conflicts_with = "select_name")]
+  select_index: Option<usize>,
+  /// Select the game by its project name
+  #[arg(long, value_name = "NAME")]
+  select_name: Option<String>,
   #[arg(long)]
   select_list: bool,
+  /// List the games, their platforms and executables as JSON
+  #[arg(long)]
+  select_list_json: bool,
+  /// Executable of the selected game, by its path or alias
+  #[arg(long, value_name = "PATH_OR_ALIAS")]
+  executable: Option<String>,
+  /// Override an environment variable for this launch
+  #[arg(long, value_name = "KEY=VAL", value_parser = cli::parse_env)]
+  env: Vec<(String,String)>,
+  /// Print the boot command and environment as JSON instead of launching
+  #[arg(long)]
+  dry_run: bool,
+  /// Start in fullscreen for gamepad and keyboard navigation
+  #[arg(long)]
+  couch: bool,
+  /// Extra arguments for the game
+  #[arg(last = true)]
+  args: Vec<String>,
 } // struct Cli }}}
 
 // fn: main {{{
@@ -158,27 +207,53 @@ fn main() -> anyhow::Result<()>
 {
   let args = Cli::parse();
 
-  // Launch game directly
-  if let Some(index) = args.select_index
+  if args.select_list
   {
-    match games::select_by_index(index as usize)
+    for (index, game) in games::games()?.into_iter().enumerate()
     {
-      Ok(()) => games::launch(),
-      Err(e) => { eprintln!("Could not select index '{}': '{}'", index, e); }
-    } // match
+      println!("{}: {}", index, game.path_root.file_name_string());
+    } // for
     return Ok(());
   } // if
-  else if args.select_list
+  else if args.select_list_json
   {
-    for (index, game) in games::games()?.into_iter().enumerate()
+    println!("{}", serde_json::to_string_pretty(&cli::list(&games::games()?))?);
+    return Ok(());
+  } // else if
+
+  // Launch game directly
+  let index = match (args.select_index, &args.select_name)
+  {
+    (Some(index), _) => Some(index),
+    (None, Some(name)) => Some(games::find(&games::games()?, name)?),
+    (None, None) => None,
+  }; // match
+  if let Some(index) = index
+  {
+    let games = games::games()?;
+    let game = games.get(index).ok_or(anyhow::anyhow!("Could not select index '{}'", index))?;
+    games::select(game);
+    if let Some(executable) = &args.executable
     {
-      println!("{}: {}", index, game.path_root.file_name_string());
-    } // for
+      std::env::set_var("GIMG_LAUNCHER_EXECUTABLE", games::find_executable(&game.path_root, executable)?);
+    } // if
+    if args.dry_run
+    {
+      println!("{}", serde_json::to_string_pretty(&cli::dry_run(game, &args.args, &args.env)?)?);
+      return Ok(());
+    } // if
+    cli::set_env(&args.env);
+    for failure in games::launch(&args.args) { eprintln!("{}", failure); }
     return Ok(());
+  } // if
+  else if args.executable.is_some() || args.dry_run || ! args.env.is_empty() || ! args.args.is_empty()
+  {
+    return Err(anyhow::anyhow!("Select a game with --select-index or --select-name"));
   } // else if
 
   // Start GUI
-  Gui::new().init();
+  let couch = args.couch || std::env::var("GIMG_LAUNCHER_COUCH").is_ok_and(|e| e == "1");
+  Gui::new(couch).init();
 
   Ok(())
 } // }}}
@@ -0,0 +1,200 @@
+// Focus of the current frame for the keyboard and gamepad navigation, each frame registers the
+// widgets it can focus in the order the directions traverse them
+use std::sync::{Mutex,LazyLock};
+
+use fltk::prelude::*;
+use fltk::{
+  app::Sender,
+  widget::Widget,
+  group::Scroll,
+  enums::Color,
+};
+
+use crate::common::Msg;
+use crate::input::Action;
+
+// pub enum Command {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command
+{
+  Focus(usize),
+  Activate(usize),
+  Back,
+  Launch,
+  Menu,
+  None,
+} // enum Command }}}
+
+// pub struct Focus {{{
+// Focused entry out of len entries, none until the first direction is pressed. Entries in a grid of
+// columns move by a row with up and down, zero columns is a list
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Focus
+{
+  pub len     : usize,
+  pub index   : Option<usize>,
+  pub columns : usize,
+} // struct Focus }}}
+
+// impl Focus {{{
+impl Focus
+{
+  // pub fn apply() {{{
+  pub fn apply(&mut self, action: Action) -> Command
+  {
+    match action
+    {
+      Action::Up | Action::Left | Action::Down | Action::Right if self.len > 0 =>
+      {
+        let forward = matches!(action, Action::Down | Action::Right);
+        let step = if matches!(action, Action::Up | Action::Down) { self.columns.max(1) } else { 1 };
+        let index = match (self.index, forward)
+        {
+          (None, _) => 0,
+          (Some(index), true) => (index + step).min(self.len - 1),
+          (Some(index), false) => index.saturating_sub(step),
+        }; // match
+        self.index = Some(index);
+        Command::Focus(index)
+      },
+      Action::Select => match self.index { Some(index) => Command::Activate(index), None => Command::None },
+      Action::Back => Command::Back,
+      Action::Launch => Command::Launch,
+      Action::Menu => Command::Menu,
+      _ => Command::None,
+    } // match
+  } // fn: apply }}}
+} // impl Focus }}}
+
+// struct Nav {{{
+#[derive(Default)]
+struct Nav
+{
+  focus   : Focus,
+  widgets : Vec<(Widget, Color)>,
+  back    : Option<Msg>,
+  launch  : Option<Widget>,
+  scroll  : Option<Scroll>,
+} // struct Nav }}}
+
+static NAV: LazyLock<Mutex<Nav>> = LazyLock::new(|| Mutex::new(Nav::default()));
+
+// pub fn set() {{{
+// Widgets of the frame being drawn and the frame to return to
+pub fn set(widgets: Vec<Widget>, back: Option<Msg>)
+{
+  let mut nav = NAV.lock().unwrap();
+  nav.focus = Focus{ len: widgets.len(), index: None, columns: 0 };
+  nav.widgets = widgets.into_iter().map(|e| { let color = e.color(); (e, color) }).collect();
+  nav.back = back;
+  nav.launch = None;
+  nav.scroll = None;
+} // fn: set }}}
+
+// pub fn set_columns() {{{
+// Columns of the grid the focusable entries of the frame are laid out in
+pub fn set_columns(columns: usize)
+{
+  NAV.lock().unwrap().focus.columns = columns;
+} // fn: set_columns }}}
+
+// pub fn set_launch() {{{
+// Widget activated by the launch action in the frame
+pub fn set_launch(widget: Widget)
+{
+  NAV.lock().unwrap().launch = Some(widget);
+} // fn: set_launch }}}
+
+// pub fn set_scroll() {{{
+// Scroll which contains the focusable entries of the frame, it follows the focus
+pub fn set_scroll(scroll: Scroll)
+{
+  NAV.lock().unwrap().scroll = Some(scroll);
+} // fn: set_scroll }}}
+
+// fn scroll_to() {{{
+// Scrolls the widget into view if it is inside the scroll
+fn scroll_to(scroll: &mut Scroll, widget: &Widget)
+{
+  if ! widget.inside(scroll) { return; } // if
+  let (top, bottom) = (scroll.y(), scroll.y() + scroll.h());
+  if widget.y() < top
+  {
+    scroll.scroll_to(0, scroll.yposition() - (top - widget.y()));
+  } // if
+  else if widget.y() + widget.h() > bottom
+  {
+    scroll.scroll_to(0, scroll.yposition() + (widget.y() + widget.h() - bottom));
+  } // else if
+} // fn: scroll_to }}}
+
+// pub fn apply() {{{
+pub fn apply(tx: Sender<Msg>, action: Action)
+{
+  let mut nav = NAV.lock().unwrap();
+  let command = nav.focus.apply(action);
+  match command
+  {
+    Command::Focus(index) =>
+    {
+      for (i, (widget, color)) in nav.widgets.iter_mut().enumerate()
+      {
+        widget.set_color(if i == index { color.lighter().lighter() } else { *color });
+        widget.redraw();
+      } // for
+      let widget = nav.widgets[index].0.clone();
+      if let Some(scroll) = nav.scroll.as_mut() { scroll_to(scroll, &widget); } // if
+    },
+    Command::Activate(index) =>
+    {
+      let mut widget = nav.widgets[index].0.clone();
+      drop(nav);
+      widget.do_callback();
+    },
+    Command::Back => if let Some(msg) = nav.back { tx.send(msg); },
+    Command::Launch => if let Some(mut widget) = nav.launch.clone() { drop(nav); widget.do_callback(); },
+    Command::Menu => tx.send(Msg::DrawMenu),
+    Command::None => (),
+  } // match
+} // fn: apply }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{Command, Focus};
+  use crate::input::{actions, Recorded};
+
+  #[test]
+  fn navigate_entries_from_recorded_events()
+  {
+    // Down, down, down, up, A, B, start from the directional pad and the buttons
+    let axis = |value: i16| [0u32.to_ne_bytes().as_slice(), value.to_ne_bytes().as_slice(), &[0x02, 7]].concat();
+    let button = |number: u8| [0u32.to_ne_bytes().as_slice(), 1i16.to_ne_bytes().as_slice(), &[0x01, number]].concat();
+    let bytes = [
+      axis(32767), axis(0), axis(32767), axis(0), axis(32767), axis(0), axis(-32767), axis(0),
+      button(0), button(1), button(7),
+    ].concat();
+    let mut focus = Focus{ len: 2, index: None, columns: 0 };
+    let commands : Vec<Command> = actions(&mut Recorded::from_bytes(&bytes))
+      .into_iter()
+      .map(|e| focus.apply(e))
+      .collect();
+    assert_eq!(commands, [
+      Command::Focus(0), Command::Focus(1), Command::Focus(1), Command::Focus(0),
+      Command::Activate(0), Command::Back, Command::Launch,
+    ]);
+    // Up and down move by a row in a grid, clamped to the entries
+    let mut focus = Focus{ len: 7, index: Some(1), columns: 3 };
+    assert_eq!(focus.apply(crate::input::Action::Down), Command::Focus(4));
+    assert_eq!(focus.apply(crate::input::Action::Right), Command::Focus(5));
+    assert_eq!(focus.apply(crate::input::Action::Down), Command::Focus(6));
+    assert_eq!(focus.apply(crate::input::Action::Up), Command::Focus(3));
+    assert_eq!(focus.apply(crate::input::Action::Up), Command::Focus(0));
+    // Nothing to focus or activate in an empty frame
+    let mut focus = Focus::default();
+    assert_eq!(focus.apply(crate::input::Action::Down), Command::None);
+    assert_eq!(focus.apply(crate::input::Action::Select), Command::None);
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
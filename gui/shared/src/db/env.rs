@@ -0,0 +1,111 @@
+// Environment of each executable, applied by the boot program over the environment of the project
+use std::io::prelude::*;
+use std::fs::File;
+use std::path::PathBuf;
+use std::collections::BTreeMap;
+
+use crate::db::kv::Kv;
+
+// Variable appended to the value of the project instead of replacing it, wine uses the last
+// entry of a library when it is listed more than once
+pub const DLL_OVERRIDES: &str = "WINEDLLOVERRIDES";
+
+// Executable paths, relative to the project directory, to their variables
+pub type Env = BTreeMap<String, Kv>;
+
+// fn open() {{{
+fn open(db: &PathBuf) -> anyhow::Result<Env>
+{
+  Ok(serde_json::from_reader(File::open(db)?)?)
+} // fn open() }}}
+
+// fn save() {{{
+fn save(db: &PathBuf, env: &Env) -> anyhow::Result<()>
+{
+  write!(File::create(db)?, "{}", serde_json::to_string(env)?)?;
+  Ok(())
+} // fn save() }}}
+
+// pub fn read() {{{
+pub fn read(db : &PathBuf) -> anyhow::Result<Env>
+{
+  open(db)
+} // fn: read }}}
+
+// pub fn read_executable() {{{
+// Variables of the executable, empty if it has none
+pub fn read_executable(db : &PathBuf, executable: &str) -> Kv
+{
+  open(db).ok().and_then(|mut e| e.remove(executable)).unwrap_or_default()
+} // fn: read_executable }}}
+
+// pub fn write() {{{
+pub fn write(db : &PathBuf, executable: &str, key: &str, val: &str) -> anyhow::Result<()>
+{
+  // Open existing or create an empty database
+  let mut env = open(db).unwrap_or_default();
+  env.entry(executable.to_string()).or_default().insert(key.to_string(), val.to_string());
+  save(db, &env)
+} // fn: write }}}
+
+// pub fn erase() {{{
+// Erases the variable, executables without variables are removed
+pub fn erase(db : &PathBuf, executable: &str, key: &str) -> anyhow::Result<()>
+{
+  let mut env = read(db)?;
+  if let Some(kv) = env.get_mut(executable)
+  {
+    kv.remove(key);
+    if kv.is_empty() { env.remove(executable); } // if
+  } // if
+  save(db, &env)
+} // erase() }}}
+
+// pub fn resolve() {{{
+// Environment the executable is started with, its variables replace the ones of the project except
+// for the dll overrides, which are appended to the ones of the project
+pub fn resolve(project: &Kv, executable: &Kv) -> Kv
+{
+  let mut env = project.clone();
+  for (key, val) in executable
+  {
+    let val = match env.get(key)
+    {
+      Some(prev) if key == DLL_OVERRIDES && ! prev.is_empty() && ! val.is_empty() => format!("{};{}", prev, val),
+      _ => val.clone(),
+    }; // match
+    env.insert(key.clone(), val);
+  } // for
+  env
+} // fn: resolve }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{resolve, DLL_OVERRIDES};
+  use crate::db::kv::Kv;
+
+  // fn kv() {{{
+  fn kv(entries: &[(&str, &str)]) -> Kv
+  {
+    entries.iter().map(|(k,v)| (k.to_string(), v.to_string())).collect()
+  } // fn: kv }}}
+
+  #[test]
+  fn executable_overrides_project()
+  {
+    let project = kv(&[("DXVK_HUD", "fps"), (DLL_OVERRIDES, "d3d9=n,b"), ("LANG", "C")]);
+    let executable = kv(&[("DXVK_HUD", "0"), (DLL_OVERRIDES, "dinput8=n"), ("EDITOR", "1")]);
+    assert_eq!(resolve(&project, &executable), kv(&[
+      ("DXVK_HUD", "0"),
+      (DLL_OVERRIDES, "d3d9=n,b;dinput8=n"),
+      ("LANG", "C"),
+      ("EDITOR", "1"),
+    ]));
+    // Without overrides in the project the ones of the executable are used as is
+    assert_eq!(resolve(&kv(&[]), &executable)[DLL_OVERRIDES], "dinput8=n");
+    assert_eq!(resolve(&project, &kv(&[])), project);
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,176 @@
+// Commands the launcher runs before a game starts and after it exits, e.g., to mount a drive or to
+// back up the saves
+use std::io::prelude::*;
+use std::fs::File;
+use std::path::{Path,PathBuf};
+
+use anyhow::anyhow as ah;
+use serde::{Deserialize, Serialize};
+
+// Database in the project directory
+pub const FILE: &str = "gameimage.hooks.json";
+// Directory in the project with the scripts the hooks call
+pub const DIR_SCRIPTS: &str = "hooks";
+
+// pub enum Stage {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage
+{
+  Pre, // before the game starts, a failure cancels the launch
+  Post, // after the game exits, every hook runs regardless of failures
+} // enum Stage }}}
+
+// pub struct Hooks {{{
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hooks
+{
+  #[serde(default)]
+  pub pre: Vec<String>, // shell commands, run in order
+  #[serde(default)]
+  pub post: Vec<String>,
+} // struct Hooks }}}
+
+// pub fn read() {{{
+// Hooks of the project, none if the project has no database
+pub fn read(db : &PathBuf) -> anyhow::Result<Hooks>
+{
+  if ! db.exists() { return Ok(Hooks::default()); } // if
+  Ok(serde_json::from_reader(File::open(db)?)?)
+} // fn: read }}}
+
+// pub fn write() {{{
+pub fn write(db : &PathBuf, hooks: &Hooks) -> anyhow::Result<()>
+{
+  write!(File::create(db)?, "{}", serde_json::to_string(hooks)?)?;
+  Ok(())
+} // fn: write }}}
+
+// Bytes of stderr kept for the error of a failed hook
+const SIZE_STDERR: u64 = 4096;
+
+// fn file_stderr() {{{
+// Unlinked file which receives the stderr of a hook. A pipe stays open while a process the hook
+// started in the background is alive, which would block the launcher until it exits
+fn file_stderr() -> anyhow::Result<File>
+{
+  static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+  let path_file = std::env::temp_dir().join(format!("gameimage-hook-{}-{}.log"
+    , std::process::id()
+    , COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)));
+  let file = std::fs::OpenOptions::new().read(true).write(true).create_new(true).open(&path_file)?;
+  std::fs::remove_file(&path_file)?;
+  Ok(file)
+} // fn: file_stderr }}}
+
+// pub fn run_command() {{{
+// Runs the command with 'sh -c' in the project directory, the error has the exit code and the
+// last line the command wrote to stderr. Processes the command leaves in the background keep
+// running, the launcher does not wait for them
+pub fn run_command(command: &str, path_dir_root: &Path) -> anyhow::Result<()>
+{
+  let mut file = file_stderr().map_err(|e| ah!("Could not create stderr file of hook '{}': {}", command, e))?;
+  let status = std::process::Command::new("sh")
+    .args(["-c", command])
+    .current_dir(path_dir_root)
+    .env("GIMG_LAUNCHER_ROOT", path_dir_root)
+    .stdin(std::process::Stdio::null())
+    .stdout(std::process::Stdio::inherit())
+    .stderr(file.try_clone()?)
+    .status()
+    .map_err(|e| ah!("Could not run hook '{}': {}", command, e))?;
+  // Only the tail, background processes may still write to the file
+  let size = file.metadata()?.len();
+  let mut stderr = vec![];
+  file.seek(std::io::SeekFrom::Start(size.saturating_sub(SIZE_STDERR)))?;
+  file.take(SIZE_STDERR).read_to_end(&mut stderr)?;
+  let stderr = String::from_utf8_lossy(&stderr);
+  eprint!("{}", stderr);
+  if status.success() { return Ok(()); } // if
+  let reason = match status.code()
+  {
+    Some(code) => format!("exit code {}", code),
+    None => "killed by a signal".to_string(),
+  }; // match
+  match stderr.lines().rev().map(|e| e.trim()).find(|e| ! e.is_empty())
+  {
+    Some(line) => Err(ah!("Hook '{}' failed with {}: {}", command, reason, line)),
+    None => Err(ah!("Hook '{}' failed with {}", command, reason)),
+  } // match
+} // fn: run_command }}}
+
+// impl Hooks {{{
+impl Hooks
+{
+  // pub fn get() {{{
+  pub fn get(&self, stage: Stage) -> &Vec<String>
+  {
+    match stage { Stage::Pre => &self.pre, Stage::Post => &self.post }
+  } // fn: get }}}
+
+  // pub fn get_mut() {{{
+  pub fn get_mut(&mut self, stage: Stage) -> &mut Vec<String>
+  {
+    match stage { Stage::Pre => &mut self.pre, Stage::Post => &mut self.post }
+  } // fn: get_mut }}}
+
+  // pub fn run() {{{
+  // Runs the hooks of the stage and returns their failures, the pre-launch hooks stop at the first
+  // failure since the later ones may depend on it
+  pub fn run(&self, stage: Stage, path_dir_root: &Path) -> Vec<String>
+  {
+    let mut failures = vec![];
+    for command in self.get(stage)
+    {
+      if let Err(e) = run_command(command, path_dir_root)
+      {
+        failures.push(e.to_string());
+        if stage == Stage::Pre { break; } // if
+      } // if
+    } // for
+    failures
+  } // fn: run }}}
+} // impl Hooks }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{read, write, Hooks, Stage};
+
+  #[test]
+  fn run_hooks_in_project_directory()
+  {
+    let dir = crate::testing::TempDir::new();
+    let path_dir_root = dir.path.clone();
+    let db = path_dir_root.join(super::FILE);
+    assert_eq!(read(&db).unwrap(), Hooks::default());
+    let hooks = Hooks
+    {
+      pre: vec!["echo pre > pre.txt".into(), "echo 'not mounted' >&2; exit 3".into(), "touch skipped".into()],
+      post: vec!["false".into(), "test \"$GIMG_LAUNCHER_ROOT\" = \"$PWD\" && touch post".into()],
+    };
+    write(&db, &hooks).unwrap();
+    assert_eq!(read(&db).unwrap(), hooks);
+    // A failed pre-launch hook stops the ones after it
+    assert_eq!(hooks.run(Stage::Pre, &path_dir_root), vec!["Hook 'echo 'not mounted' >&2; exit 3' failed with exit code 3: not mounted"]);
+    assert!(path_dir_root.join("pre.txt").exists());
+    assert!(! path_dir_root.join("skipped").exists());
+    // Post-exit hooks run after failures
+    assert_eq!(hooks.run(Stage::Post, &path_dir_root), vec!["Hook 'false' failed with exit code 1"]);
+    assert!(path_dir_root.join("post").exists());
+  }
+
+  #[test]
+  fn background_processes_do_not_block_hooks()
+  {
+    let dir = crate::testing::TempDir::new();
+    let instant = std::time::Instant::now();
+    // The background process keeps stderr open
+    let hooks = Hooks{ pre: vec!["sleep 30 > /dev/null & echo $! > pid; echo started >&2".into()], post: vec![] };
+    assert!(hooks.run(Stage::Pre, &dir.path).is_empty());
+    assert!(instant.elapsed() < std::time::Duration::from_secs(10), "Hook took {:?}", instant.elapsed());
+    let pid = std::fs::read_to_string(dir.path.join("pid")).unwrap();
+    let _ = std::process::Command::new("kill").arg(pid.trim()).status();
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -1 +1,3 @@
 pub mod kv;
+pub mod env;
+pub mod hooks;
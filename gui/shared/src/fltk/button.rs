@@ -101,7 +101,8 @@ macro_rules! create_buttons
 }
 
 create_buttons!(search, terminal, filter, install , home, back, configure, list, switch, add, del,
-  folder, save, check, check_all, cloud, refresh, joystick, arrow_backward, arrow_forward, play, resize_down
+  folder, save, check, check_all, cloud, refresh, joystick, arrow_backward, arrow_forward, play, resize_down,
+  star, eye_slash
 );
 
 pub fn checkbutton() -> fltk::button::CheckButton
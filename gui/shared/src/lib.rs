@@ -5,5 +5,7 @@ pub mod svg;
 pub mod std;
 pub mod image;
 pub mod db;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -119,6 +119,19 @@ const ICON_SAVE: &str = r#"
 </svg>
 "#;
 
+const ICON_STAR: &str = r#"
+<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" fill="white" class="bi bi-star-fill" viewBox="0 0 16 16">
+  <path d="M3.612 15.443c-.386.198-.824-.149-.746-.592l.83-4.73L.173 6.765c-.329-.314-.158-.888.283-.95l4.898-.696L7.538.792c.197-.39.73-.39.927 0l2.184 4.327 4.898.696c.441.062.612.636.282.95l-3.522 3.356.83 4.73c.078.443-.36.79-.746.592L8 13.187l-4.389 2.256z"/>
+</svg>
+"#;
+
+const ICON_EYE_SLASH: &str = r#"
+<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" fill="white" class="bi bi-eye-slash-fill" viewBox="0 0 16 16">
+  <path d="m10.79 12.912-1.614-1.615a3.5 3.5 0 0 1-4.474-4.474l-2.06-2.06C.938 6.278 0 8 0 8s3 5.5 8 5.5a7 7 0 0 0 2.79-.588M5.21 3.088A7 7 0 0 1 8 2.5c5 0 8 5.5 8 5.5s-.939 1.721-2.641 3.238l-2.062-2.062a3.5 3.5 0 0 0-4.474-4.474z"/>
+  <path d="M5.525 7.646a2.5 2.5 0 0 0 2.829 2.829zm4.95.708-2.829-2.83a2.5 2.5 0 0 1 2.829 2.829zm3.171 6-12-12 .708-.708 12 12z"/>
+</svg>
+"#;
+
 const ICON_BOX_HEART: &str = r#"
 <svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" fill="white" class="bi bi-box2-heart-fill" viewBox="0 0 16 16">
   <path d="M3.75 0a1 1 0 0 0-.8.4L.1 4.2a.5.5 0 0 0-.1.3V15a1 1 0 0 0 1 1h14a1 1 0 0 0 1-1V4.5a.5.5 0 0 0-.1-.3L13.05.4a1 1 0 0 0-.8-.4zM8.5 4h6l.5.667V5H1v-.333L1.5 4h6V1h1zM8 7.993c1.664-1.711 5.825 1.283 0 5.132-5.825-3.85-1.664-6.843 0-5.132"/>
@@ -364,6 +377,8 @@ icon!(icon_joystick, ICON_JOYSTICK, 16, 16);
 icon!(icon_save, ICON_SAVE, 16, 16);
 icon!(icon_cloud, ICON_CLOUD, 18, 18);
 icon!(icon_box_heart, ICON_BOX_HEART, 16, 16);
+icon!(icon_star, ICON_STAR, 16, 16);
+icon!(icon_eye_slash, ICON_EYE_SLASH, 16, 16);
 icon!(icon_folder, ICON_FOLDER, 16, 16);
 icon!(icon_check, ICON_CHECK, 24, 24);
 icon!(icon_check_all, ICON_CHECK_ALL, 24, 24);
@@ -0,0 +1,40 @@
+// Helpers shared by the test suites of the crates, enabled with the 'testing' feature
+
+use std::
+{
+  path::PathBuf,
+  sync::atomic::{AtomicU64,Ordering},
+};
+
+// pub struct TempDir {{{
+// Directory removed on drop, its name is unique in the machine
+pub struct TempDir
+{
+  pub path: PathBuf,
+} // struct TempDir }}}
+
+impl TempDir
+{
+  // pub fn new() {{{
+  #[allow(clippy::new_without_default)]
+  pub fn new() -> TempDir
+  {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let path = std::env::temp_dir().join(format!("gameimage-test-{}-{}"
+      , std::process::id()
+      , COUNTER.fetch_add(1, Ordering::Relaxed)));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).expect("Could not create temporary directory");
+    TempDir{ path }
+  } // fn: new }}}
+} // impl TempDir
+
+impl Drop for TempDir
+{
+  fn drop(&mut self)
+  {
+    let _ = std::fs::remove_dir_all(&self.path);
+  } // fn: drop
+} // impl Drop for TempDir
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
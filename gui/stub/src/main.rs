@@ -0,0 +1,101 @@
+// Test double of gameimage-cli
+//
+// The stub is driven by the files in the directory of GIMG_STUB_DIR:
+// * requests.jsonl: each invocation appends a line with the json array of its arguments
+// * script.json: replies keyed by the operation of the request, e.g.,
+//   { "search.local": { "events": [{ "type": "item", "value": "rom/game.exe" }], "code": 0 } }
+//   The key '<op>.<inner op>' takes precedence over '<op>', unknown operations exit with 0. A
+//   reply with a "command" runs it with 'sh -c' before exiting, e.g., to start long running children
+//
+// Events are sent as single part frames to the unix socket in GIMG_IPC_SOCKET
+
+use std::
+{
+  io::Write,
+  os::unix::net::UnixStream,
+  path::{Path,PathBuf},
+};
+
+use serde_json::{json, Value};
+
+// fn record() {{{
+// Appends the arguments of this invocation to the requests file
+fn record(path_dir_stub: &Path, args: &[String]) -> std::io::Result<()>
+{
+  let mut file = std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(path_dir_stub.join("requests.jsonl"))?;
+  writeln!(file, "{}", json!(args))
+} // fn: record }}}
+
+// fn reply() {{{
+// Looks up the scripted reply for the request
+fn reply(path_dir_stub: &Path, request: &Value) -> Value
+{
+  let script : Value = std::fs::read_to_string(path_dir_stub.join("script.json"))
+    .ok()
+    .and_then(|e| serde_json::from_str(&e).ok())
+    .unwrap_or(Value::Null);
+  let op = request["op"].as_str().unwrap_or_default();
+  let op_inner = request[op]["op"].as_str().unwrap_or_default();
+  match script.get(format!("{}.{}", op, op_inner))
+  {
+    Some(reply) => reply.clone(),
+    None => script.get(op).cloned().unwrap_or(Value::Null),
+  } // match
+} // fn: reply }}}
+
+// fn send() {{{
+// Writes the events to the ipc socket of the frontend
+fn send(events: &[Value]) -> std::io::Result<()>
+{
+  if events.is_empty() { return Ok(()); }
+  let path_socket = match std::env::var("GIMG_IPC_SOCKET")
+  {
+    Ok(path_socket) => path_socket,
+    Err(_) => { eprintln!("GIMG_IPC_SOCKET is not set, discarding events"); return Ok(()); },
+  }; // match
+  let mut stream = UnixStream::connect(path_socket)?;
+  for (id, event) in events.iter().enumerate()
+  {
+    writeln!(stream, "{}", json!({ "id": id, "part": 0, "parts": 1, "data": event.to_string() }))?;
+  } // for
+  Ok(())
+} // fn: send }}}
+
+// fn main() {{{
+fn main()
+{
+  let path_dir_stub = match std::env::var("GIMG_STUB_DIR")
+  {
+    Ok(path_dir_stub) => PathBuf::from(path_dir_stub),
+    Err(_) => { eprintln!("GIMG_STUB_DIR is not set"); std::process::exit(1); },
+  }; // match
+  let args : Vec<String> = std::env::args().skip(1).collect();
+  if let Err(e) = record(&path_dir_stub, &args)
+  {
+    eprintln!("Could not record request: {}", e);
+    std::process::exit(1);
+  } // if
+  let request : Value = args.first()
+    .and_then(|e| serde_json::from_str(e).ok())
+    .unwrap_or(Value::Null);
+  let reply = reply(&path_dir_stub, &request);
+  if let Err(e) = send(reply["events"].as_array().map(|e| e.as_slice()).unwrap_or_default())
+  {
+    eprintln!("Could not send events: {}", e);
+  } // if
+  if let Some(stdout) = reply["stdout"].as_str()
+  {
+    print!("{}", stdout);
+  } // if
+  if let Some(command) = reply["command"].as_str()
+  && let Err(e) = std::process::Command::new("sh").args(["-c", command]).status()
+  {
+    eprintln!("Could not run command: {}", e);
+  } // if
+  std::process::exit(reply["code"].as_i64().unwrap_or(0) as i32);
+} // fn: main }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
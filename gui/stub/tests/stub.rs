@@ -0,0 +1,69 @@
+// Checks the stub itself, this also makes cargo build the binary used by the other test suites
+
+use std::
+{
+  io::BufRead,
+  os::unix::net::UnixListener,
+  path::PathBuf,
+  process::Command,
+};
+
+// fn tempdir() {{{
+fn tempdir(name: &str) -> PathBuf
+{
+  let path_dir = std::env::temp_dir().join(format!("gameimage-stub-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&path_dir);
+  std::fs::create_dir_all(&path_dir).unwrap();
+  path_dir
+} // fn: tempdir }}}
+
+#[test]
+fn records_requests_and_exits_with_scripted_code()
+{
+  let path_dir = tempdir("record");
+  std::fs::write(path_dir.join("script.json"), r#"{ "fetch.installed": { "code": 3 }, "fetch": { "code": 4 } }"#).unwrap();
+  let f_run = |arg: &str| Command::new(env!("CARGO_BIN_EXE_gameimage-cli-stub"))
+    .env("GIMG_STUB_DIR", &path_dir)
+    .env_remove("GIMG_IPC_SOCKET")
+    .arg(arg)
+    .status()
+    .unwrap()
+    .code();
+  assert_eq!(f_run(r#"{"op":"fetch","fetch":{"op":"installed"}}"#), Some(3));
+  assert_eq!(f_run(r#"{"op":"fetch","fetch":{"op":"sources"}}"#), Some(4));
+  assert_eq!(f_run(r#"{"op":"test"}"#), Some(0));
+  let requests = std::fs::read_to_string(path_dir.join("requests.jsonl")).unwrap();
+  let requests : Vec<Vec<String>> = requests.lines().map(|e| serde_json::from_str(e).unwrap()).collect();
+  assert_eq!(requests.len(), 3);
+  assert_eq!(requests[2], vec![r#"{"op":"test"}"#.to_string()]);
+  std::fs::remove_dir_all(&path_dir).unwrap();
+}
+
+#[test]
+fn sends_scripted_events_to_socket()
+{
+  let path_dir = tempdir("events");
+  std::fs::write(path_dir.join("script.json")
+    , r#"{ "search": { "events": [{ "type": "item", "value": "rom/a.exe" }, { "type": "progress", "value": 50.0 }] } }"#
+  ).unwrap();
+  let path_socket = path_dir.join("ipc.sock");
+  let listener = UnixListener::bind(&path_socket).unwrap();
+  let status = Command::new(env!("CARGO_BIN_EXE_gameimage-cli-stub"))
+    .env("GIMG_STUB_DIR", &path_dir)
+    .env("GIMG_IPC_SOCKET", &path_socket)
+    .arg(r#"{"op":"search","search":{"op":"local","query":"rom"}}"#)
+    .status()
+    .unwrap();
+  assert!(status.success());
+  let (stream, _) = listener.accept().unwrap();
+  let frames : Vec<serde_json::Value> = std::io::BufReader::new(stream)
+    .lines()
+    .map(|e| serde_json::from_str(&e.unwrap()).unwrap())
+    .collect();
+  assert_eq!(frames.len(), 2);
+  assert_eq!(frames[0]["parts"], 1);
+  assert_eq!(frames[0]["data"], r#"{"type":"item","value":"rom/a.exe"}"#);
+  std::fs::remove_dir_all(&path_dir).unwrap();
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -6,13 +6,15 @@ use std::
 };
 
 // pub enum Platform {{{
-#[derive(Eq, PartialEq, Hash, Clone)]
+#[derive(Eq, PartialEq, Hash, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Platform
 {
   Linux,
   Wine,
   Retroarch,
   Pcsx2,
+  #[serde(rename = "rpcs3")]
   Rcps3,
 } // }}}
 
@@ -46,12 +48,13 @@ impl Platform
 } // impl IconFrame }}}
 
 // pub enum Msg {{{
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Msg
 {
   DrawWelcome,
   DrawPlatform,
   DrawCreator,
+  DrawHooks,
   DrawDesktop,
 
   DrawLinuxName,
@@ -66,6 +69,8 @@ pub enum Msg
   DrawWineConfigure,
   DrawWineTricks,
   DrawWineEnvironment,
+  DrawWineEnvironmentExecutable,
+  DrawWineSettings,
   DrawWineRom,
   DrawWineCompress,
 
@@ -100,9 +105,39 @@ pub enum Msg
   Quit,
 } // }}}
 
+// impl Msg {{{
+impl Msg
+{
+  // Frames that use the result of every queued job, they wait for the queue to finish, e.g., the
+  // wine prefix cannot be used to install or test executables while winetricks runs
+  pub fn depends_on_jobs(&self) -> bool
+  {
+    matches!(self
+      , Msg::DrawCreator
+      | Msg::DrawDesktop
+      | Msg::DrawFinish
+      | Msg::DrawLinuxCompress
+      | Msg::DrawWineRom
+      | Msg::DrawWineCompress
+      | Msg::DrawRetroarchTest
+      | Msg::DrawRetroarchCompress
+      | Msg::DrawPcsx2Test
+      | Msg::DrawPcsx2Compress
+      | Msg::DrawRpcs3Test
+      | Msg::DrawRpcs3Compress
+    )
+  } // fn: depends_on_jobs
+} // impl Msg }}}
+
+// Set when running without a window, log messages are only written to stderr
+pub static HEADLESS: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
 // impl_log() {{{
 pub fn impl_log(value : &str)
 {
+  // There is no terminal widget to append to
+  if HEADLESS.load(std::sync::atomic::Ordering::Relaxed) { return; }
+
   static TX: OnceLock<std::sync::mpsc::Sender<String>> = OnceLock::new();
 
   // Initialize the logging channel and logger thread the first time impl_log is called
@@ -83,4 +83,52 @@ pub fn update<F>(f: F) -> anyhow::Result<()>
   Ok(())
 } // fn: update }}}
 
+#[cfg(test)]
+mod tests
+{
+  use crate::testing;
+
+  #[test]
+  fn read_parses_build_database()
+  {
+    let _guard = testing::lock();
+    let dir = testing::fixture("build");
+    std::env::set_var("GIMG_DIR", &dir.path);
+    let entry = super::read().unwrap();
+    assert_eq!(entry.project, "game");
+    assert_eq!(entry.path_dir_build, dir.path);
+    assert_eq!(entry.dist_wine, "default");
+    assert_eq!(entry.projects.len(), 4);
+    assert_eq!(entry.get_project_dir("emu").unwrap(), dir.path.join("emu"));
+    assert!(entry.get_project_dir("none").is_err());
+    let current = super::get_current_project().unwrap();
+    assert_eq!(current.path_dir_project, dir.path.join("game"));
+    assert_eq!(current.platform, "WINE");
+  }
+
+  #[test]
+  fn read_fails_without_database()
+  {
+    let _guard = testing::lock();
+    let dir = testing::TempDir::new();
+    std::env::set_var("GIMG_DIR", &dir.path);
+    assert!(super::read().is_err());
+    std::env::remove_var("GIMG_DIR");
+    assert!(super::read().is_err());
+  }
+
+  #[test]
+  fn update_writes_database()
+  {
+    let _guard = testing::lock();
+    let dir = testing::fixture("build");
+    std::env::set_var("GIMG_DIR", &dir.path);
+    super::update(|mut db| { db.project = "emu".into(); db.dist_wine = "staging".into(); db }).unwrap();
+    let entry = super::read().unwrap();
+    assert_eq!(entry.project, "emu");
+    assert_eq!(entry.dist_wine, "staging");
+    assert_eq!(super::get_current_project().unwrap().platform, "RETROARCH");
+  }
+}
+
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
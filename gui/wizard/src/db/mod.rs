@@ -1,3 +1,5 @@
 pub mod project;
 pub mod global;
 pub mod fetch;
+pub mod recipe;
+pub mod winetricks;
@@ -27,6 +27,14 @@ pub struct Entry
   path_file_rom  : Option<PathBuf>,
   path_file_core : Option<PathBuf>,
   path_file_bios : Option<PathBuf>,
+  #[serde(default)]
+  paths_file_rom  : Vec<PathBuf>,
+  #[serde(default)]
+  paths_file_core : Vec<PathBuf>,
+  #[serde(default)]
+  paths_file_bios : Vec<PathBuf>,
+  #[serde(default)]
+  path_dir_keys   : Option<PathBuf>,
 } // Entry
 
 impl Entry
@@ -92,6 +100,31 @@ pub fn get_path_relative(&self, entry: EntryName) -> anyhow::Result<PathBuf>
   Ok(some_path_project_relative.ok_or(ah!("Could not read relative path"))?)
 } // get_dir_relative
 
+pub fn get_paths_absolute(&self, entry: EntryName) -> anyhow::Result<Vec<PathBuf>>
+{
+  // Get project dir == build_dir / project_name
+  let project_dir_self = self.get_dir_self()?;
+
+  let paths_file_relative = match entry
+  {
+    EntryName::PathFileIcon => self.path_file_icon.clone().into_iter().collect(),
+    EntryName::PathFileRom  => self.paths_file_rom.clone(),
+    EntryName::PathFileCore => self.paths_file_core.clone(),
+    EntryName::PathFileBios => self.paths_file_bios.clone(),
+  }; // match
+
+  Ok(paths_file_relative.into_iter()
+    .filter(|e| ! e.as_os_str().is_empty())
+    .map(|e| project_dir_self.join(e))
+    .collect())
+} // get_paths_absolute
+
+pub fn get_dir_keys(&self) -> anyhow::Result<PathBuf>
+{
+  let path_dir_keys = self.path_dir_keys.clone().ok_or(ah!("Could not read keys directory"))?;
+  Ok(self.get_dir_self()?.join(path_dir_keys))
+} // get_dir_keys
+
 }
 // struct Entry }}}
 
@@ -151,4 +184,55 @@ pub fn current() -> anyhow::Result<Entry>
   Ok(serde_json::from_reader(file)?)
 } // current() }}}
 
+#[cfg(test)]
+mod tests
+{
+  use super::EntryName;
+  use crate::testing;
+
+  #[test]
+  fn list_skips_invalid_projects()
+  {
+    let _guard = testing::lock();
+    let dir = testing::fixture("build");
+    std::env::set_var("GIMG_DIR", &dir.path);
+    let mut projects : Vec<String> = super::list().unwrap().iter().map(|e| e.get_project()).collect();
+    projects.sort();
+    assert_eq!(projects, vec!["emu", "game"]);
+  }
+
+  #[test]
+  fn current_resolves_paths()
+  {
+    let _guard = testing::lock();
+    let dir = testing::fixture("build");
+    std::env::set_var("GIMG_DIR", &dir.path);
+    let entry = super::current().unwrap();
+    let path_dir_project = dir.path.join("game");
+    assert_eq!(entry.get_project(), "game");
+    assert_eq!(entry.get_platform(), "WINE");
+    assert_eq!(entry.get_dir_self().unwrap(), path_dir_project);
+    assert_eq!(entry.get_path_relative(EntryName::PathFileRom).unwrap(), std::path::PathBuf::from("rom/game.exe"));
+    assert_eq!(entry.get_path_absolute(EntryName::PathFileIcon).unwrap(), path_dir_project.join("icon/icon.png"));
+    assert_eq!(entry.get_paths_absolute(EntryName::PathFileRom).unwrap()
+      , vec![path_dir_project.join("rom/game.exe"), path_dir_project.join("rom/setup.exe")]
+    );
+    assert_eq!(entry.get_dir_keys().unwrap(), path_dir_project.join("keys"));
+  }
+
+  #[test]
+  fn paths_absolute_skips_empty_entries()
+  {
+    let _guard = testing::lock();
+    let dir = testing::fixture("build");
+    std::env::set_var("GIMG_DIR", &dir.path);
+    let entry = super::list().unwrap().into_iter().find(|e| e.get_project() == "emu").unwrap();
+    assert_eq!(entry.get_paths_absolute(EntryName::PathFileCore).unwrap()
+      , vec![dir.path.join("emu/core/snes9x_libretro.so")]
+    );
+    assert!(entry.get_paths_absolute(EntryName::PathFileBios).unwrap().is_empty());
+    assert!(entry.get_dir_keys().is_err());
+  }
+}
+
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
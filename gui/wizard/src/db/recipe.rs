@@ -0,0 +1,332 @@
+use std::path::{Path,PathBuf};
+use std::collections::BTreeMap;
+use std::fs::File;
+
+use anyhow::anyhow as ah;
+use serde::{Deserialize, Serialize};
+
+use shared::std::PathBufExt;
+
+use crate::log;
+use crate::db;
+use crate::common;
+
+// Version of the recipe file format
+pub const VERSION: u32 = 1;
+
+// Commands run with wine in the project, replayed when the project is imported
+pub const FILE_WINE: &str = "gameimage.wine.json";
+
+fn default_version() -> u32 { VERSION }
+
+// struct Desktop {{{
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Desktop
+{
+  pub icon: PathBuf, // icon used in the desktop integration
+  #[serde(default)]
+  pub items: Vec<String>, // integration items, e.g. entry, icon, mimetype
+} // Desktop }}}
+
+// struct Project {{{
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Project
+{
+  pub name: String, // name of the project
+  pub platform: common::Platform, // platform of the project
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub path_dir: Option<PathBuf>, // directory the paths of the project are relative to, defaults to the recipe directory
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub dist_wine: Option<String>, // wine distribution to fetch, wine only
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub icon: Option<PathBuf>, // icon of the project
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub rom: Vec<PathBuf>, // rom files to install
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub core: Vec<PathBuf>, // core files to install, retroarch only
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub core_remote: Vec<String>, // cores to download, retroarch only
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub bios: Vec<PathBuf>, // bios files to install
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub keys: Vec<PathBuf>, // key files to install
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub wine: Vec<Vec<String>>, // commands to run with wine, e.g. silent installers
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub winetricks: Vec<String>, // winetricks verbs to install
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub default_rom: Option<PathBuf>, // default rom, relative to the project directory
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub env: BTreeMap<String,String>, // gameimage.env.json
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub args: BTreeMap<String,String>, // gameimage.args.json
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub alias: BTreeMap<String,String>, // gameimage.alias.json
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub executable: BTreeMap<String,String>, // gameimage.executable.json
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub env_executable: BTreeMap<String,BTreeMap<String,String>>, // gameimage.env.executable.json
+} // Project }}}
+
+// impl Project {{{
+impl Project
+{
+  // Key-value databases of the project, paired with the file name they are stored in
+  pub fn databases(&self) -> [(&'static str, &BTreeMap<String,String>); 4]
+  {
+    [
+      ("gameimage.env.json", &self.env),
+      ("gameimage.args.json", &self.args),
+      ("gameimage.alias.json", &self.alias),
+      ("gameimage.executable.json", &self.executable),
+    ]
+  } // fn: databases
+} // impl Project }}}
+
+// struct Recipe {{{
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Recipe
+{
+  #[serde(default = "default_version")]
+  pub version: u32, // version of the recipe format
+  pub name: String, // name of the package
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub path_dir_build: Option<PathBuf>, // build directory, defaults to 'build' next to the recipe
+  pub projects: Vec<Project>, // projects to include in the package
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub desktop: Option<Desktop>, // desktop integration
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub compression: Option<u32>, // compression level from 0 to 10
+} // Recipe }}}
+
+// fn resolve() {{{
+// Makes a path from the recipe absolute, relative paths are relative to the recipe directory
+fn resolve(path_dir_recipe: &Path, path: &Path) -> PathBuf
+{
+  if path.is_absolute() { path.to_path_buf() } else { path_dir_recipe.join(path) }
+} // fn: resolve }}}
+
+// read() {{{
+pub fn read(path_file_recipe: &Path) -> anyhow::Result<Recipe>
+{
+  let file = File::open(path_file_recipe)
+    .map_err(|e| ah!("Could not open recipe '{}': {}", path_file_recipe.display(), e))?;
+  let mut recipe : Recipe = serde_yaml::from_reader(file)?;
+  if recipe.version != VERSION
+  {
+    return Err(ah!("Unsupported recipe version {}, expected {}", recipe.version, VERSION));
+  } // if
+  // Resolve paths relative to the recipe directory
+  let path_dir_recipe = std::fs::canonicalize(path_file_recipe)?
+    .parent()
+    .ok_or(ah!("Could not get parent directory of recipe"))?
+    .to_path_buf();
+  recipe.path_dir_build = Some(resolve(&path_dir_recipe
+    , &recipe.path_dir_build.clone().unwrap_or(PathBuf::from("build"))));
+  for project in recipe.projects.iter_mut()
+  {
+    let path_dir_project = match project.path_dir.as_ref()
+    {
+      Some(path_dir) => resolve(&path_dir_recipe, path_dir),
+      None => path_dir_recipe.clone(),
+    }; // match
+    project.icon = project.icon.as_ref().map(|e| resolve(&path_dir_project, e));
+    for path in project.rom.iter_mut().chain(project.core.iter_mut())
+      .chain(project.bios.iter_mut())
+      .chain(project.keys.iter_mut())
+    {
+      *path = resolve(&path_dir_project, path);
+    } // for
+    // The first element of a wine command is the executable, resolve it if it is a local file
+    for command in project.wine.iter_mut()
+    {
+      if let Some(program) = command.first_mut()
+      && path_dir_project.join(&program).exists()
+      {
+        *program = resolve(&path_dir_project, Path::new(program)).to_string_lossy().to_string();
+      } // if
+    } // for
+  } // for
+  if let Some(desktop) = recipe.desktop.as_mut()
+  {
+    desktop.icon = resolve(&path_dir_recipe, &desktop.icon);
+  } // if
+  Ok(recipe)
+} // fn: read }}}
+
+// write() {{{
+pub fn write(path_file_recipe: &Path, recipe: &Recipe) -> anyhow::Result<()>
+{
+  let file = File::create(path_file_recipe)
+    .map_err(|e| ah!("Could not create recipe '{}': {}", path_file_recipe.display(), e))?;
+  serde_yaml::to_writer(file, recipe)?;
+  Ok(())
+} // fn: write }}}
+
+// record_wine() {{{
+// Saves a command run with wine in the project, so it is exported with the project
+pub fn record_wine(path_dir_project: &Path, command: &[String]) -> anyhow::Result<()>
+{
+  let mut commands = read_wine(path_dir_project);
+  if commands.iter().any(|e| e == command) { return Ok(()); } // if
+  commands.push(command.to_vec());
+  std::fs::write(path_dir_project.join(FILE_WINE), serde_json::to_string_pretty(&commands)?)?;
+  Ok(())
+} // fn: record_wine }}}
+
+// read_wine() {{{
+fn read_wine(path_dir_project: &Path) -> Vec<Vec<String>>
+{
+  std::fs::read_to_string(path_dir_project.join(FILE_WINE))
+    .ok()
+    .and_then(|e| serde_json::from_str(&e).ok())
+    .unwrap_or_default()
+} // fn: read_wine }}}
+
+// from_project() {{{
+// Captures the state of an existing project. Paths are relative to the project directory, which is
+// expected in the directory with the project name next to the recipe, together with the installers
+// run with wine
+fn from_project(entry: &db::project::Entry) -> anyhow::Result<Project>
+{
+  let platform = common::Platform::from_str(&entry.get_platform().to_lowercase())
+    .ok_or(ah!("Invalid platform '{}'", entry.get_platform()))?;
+  let path_dir_project = entry.get_dir_self()?;
+  let f_relative = |path: PathBuf| -> PathBuf
+  {
+    path.strip_prefix(&path_dir_project).map(|e| e.to_path_buf()).unwrap_or(path)
+  };
+  let f_paths = |name| -> Vec<PathBuf>
+  {
+    entry.get_paths_absolute(name)
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|e| e.is_file() || e.is_dir())
+      .map(f_relative)
+      .collect()
+  };
+  let f_kv = |name: &str| -> BTreeMap<String,String>
+  {
+    shared::db::kv::read(&path_dir_project.join(name)).unwrap_or_default().into_iter().collect()
+  };
+  // Files in the keys directory
+  let keys = entry.get_dir_keys()
+    .and_then(|e| Ok(std::fs::read_dir(e)?))
+    .map(|e| e.filter_map(|e| e.ok()).map(|e| e.path()).filter(|e| e.is_file()).map(f_relative).collect())
+    .unwrap_or_default();
+  // Installers are only recorded since recipes exist, older projects export without them
+  if platform == common::Platform::Wine && ! path_dir_project.join(FILE_WINE).exists()
+  {
+    log!("Warning: project '{}' has no recorded installers, add them to the 'wine' list of the recipe"
+      , entry.get_project());
+  } // if
+  // Wine installs the application in the prefix with the installers instead of copying rom files,
+  // the installers are referred to by their file name in the project directory
+  let (rom, wine, winetricks) = match platform
+  {
+    common::Platform::Wine =>
+    (
+      vec![],
+      read_wine(&path_dir_project).into_iter().map(|mut command|
+      {
+        if let Some(program) = command.first_mut()
+        && Path::new(program).is_absolute()
+        && let Some(name) = Path::new(program).file_name()
+        {
+          *program = name.to_string_lossy().to_string();
+        } // if
+        command
+      }).collect(),
+      // Verbs installed in the wine prefix, in installation order
+      db::winetricks::installed(&path_dir_project.join("wine")),
+    ),
+    _ => (f_paths(db::project::EntryName::PathFileRom), vec![], vec![]),
+  }; // match
+  Ok(Project
+  {
+    name: entry.get_project(),
+    dist_wine: if platform == common::Platform::Wine { db::global::read().ok().map(|e| e.dist_wine) } else { None },
+    platform,
+    path_dir: Some(PathBuf::from(entry.get_project())),
+    icon: f_paths(db::project::EntryName::PathFileIcon).into_iter().next(),
+    rom,
+    core: f_paths(db::project::EntryName::PathFileCore),
+    core_remote: vec![],
+    bios: f_paths(db::project::EntryName::PathFileBios),
+    keys,
+    wine,
+    winetricks,
+    default_rom: entry.get_path_relative(db::project::EntryName::PathFileRom).ok().filter(|e| ! e.string().is_empty()),
+    env: f_kv("gameimage.env.json"),
+    args: f_kv("gameimage.args.json"),
+    alias: f_kv("gameimage.alias.json"),
+    executable: f_kv("gameimage.executable.json"),
+    env_executable: shared::db::env::read(&path_dir_project.join("gameimage.env.executable.json"))
+      .unwrap_or_default()
+      .into_iter()
+      .map(|(executable, kv)| (executable, kv.into_iter().collect()))
+      .collect(),
+  })
+} // fn: from_project }}}
+
+// from_projects() {{{
+pub fn from_projects(name: &str, entries: &[db::project::Entry]) -> anyhow::Result<Recipe>
+{
+  Ok(Recipe
+  {
+    version: VERSION,
+    name: name.to_string(),
+    path_dir_build: None,
+    projects: entries.iter().map(from_project).collect::<anyhow::Result<Vec<Project>>>()?,
+    desktop: None,
+    compression: std::env::var("FIM_COMPRESSION_LEVEL").ok().and_then(|e| e.parse().ok()),
+  })
+} // fn: from_projects }}}
+
+#[cfg(test)]
+mod tests
+{
+  use std::path::PathBuf;
+
+  use crate::db;
+  use crate::common;
+  use crate::testing;
+
+  #[test]
+  fn export_and_import_in_another_directory()
+  {
+    let _guard = testing::lock();
+    let dir = testing::fixture("build");
+    std::env::set_var("GIMG_DIR", &dir.path);
+    // Installer run from outside of the build directory
+    super::record_wine(&dir.path.join("game"), &["/home/user/Downloads/setup.exe".into(), "/S".into()]).unwrap();
+    let mut entries = db::project::list().unwrap();
+    entries.sort_by_key(|e| e.get_project());
+    let recipe = super::from_projects("game", &entries).unwrap();
+    // Copy the recipe, the projects and the installer to another directory
+    let dir_other = testing::TempDir::new();
+    let path_file_recipe = dir_other.path.join("game.yml");
+    super::write(&path_file_recipe, &recipe).unwrap();
+    assert!(! std::fs::read_to_string(&path_file_recipe).unwrap().contains(&*dir.path.to_string_lossy()));
+    testing::copy(&dir.path, &dir_other.path, &dir_other.path);
+    std::fs::write(dir_other.path.join("game/setup.exe"), "").unwrap();
+    drop(dir);
+    // Paths resolve to the other directory
+    let path_dir_other = std::fs::canonicalize(&dir_other.path).unwrap();
+    let recipe = super::read(&path_file_recipe).unwrap();
+    let emu = &recipe.projects[0];
+    assert_eq!(emu.platform, common::Platform::Retroarch);
+    assert_eq!(emu.rom, [path_dir_other.join("emu/rom/game.sfc")]);
+    assert_eq!(emu.core, [path_dir_other.join("emu/core/snes9x_libretro.so")]);
+    assert!(emu.wine.is_empty());
+    let game = &recipe.projects[1];
+    assert_eq!(game.platform, common::Platform::Wine);
+    assert!(game.rom.is_empty());
+    assert_eq!(game.icon, Some(path_dir_other.join("game/icon/icon.png")));
+    assert_eq!(game.keys, [path_dir_other.join("game/keys/prod.keys")]);
+    assert_eq!(game.wine, [[path_dir_other.join("game/setup.exe").to_string_lossy().to_string(), "/S".to_string()]]);
+    assert_eq!(game.default_rom, Some(PathBuf::from("rom/game.exe")));
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,446 @@
+use std::path::{Path,PathBuf};
+use std::fs::File;
+
+use anyhow::anyhow as ah;
+use serde::{Deserialize, Serialize};
+
+use shared::std::PathBufExt;
+
+use crate::log;
+use crate::common;
+
+// Version of the catalog file format
+pub const VERSION: u32 = 1;
+
+// Group of the verbs required by the libraries the executable imports
+pub const GROUP_REQUIRED: &str = "Required by imports";
+
+// Fallback for installations without the catalog data file
+const CATALOG: &str = include_str!("../../data/winetricks.json");
+
+// enum Arch {{{
+// Architecture of the executables that require the verb
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arch
+{
+  #[default]
+  Any,
+  Win32,
+  Win64,
+} // Arch }}}
+
+// struct Verb {{{
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Verb
+{
+  pub verb: String, // winetricks verb
+  #[serde(default)]
+  pub description: String, // description shown in the wizard
+  #[serde(default)]
+  pub group: String, // group the verb is shown in, e.g., .NET
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub year_min: Option<u32>, // first release year the verb is recommended for
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub year_max: Option<u32>, // last release year the verb is recommended for
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<String>, // engines or features, tagged verbs are only recommended for these
+  #[serde(default)]
+  pub arch: Arch, // architecture of the executables that require the verb
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub imports: Vec<String>, // libraries that require the verb, a trailing '*' matches any suffix
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub only_imports: bool, // only recommended when an imported library requires it
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub disabled: bool, // removes a verb of the shipped catalog in the user catalog
+} // Verb }}}
+
+impl Verb
+{
+
+// is_recommended() {{{
+// Checks if the verb is recommended for a game of the year, tag and architecture, untagged verbs
+// are recommended for every tag
+pub fn is_recommended(&self, year: u32, tag: Option<&str>, arch: Arch) -> bool
+{
+  ! self.disabled
+    && ! self.only_imports
+    && self.year_min.is_none_or(|e| e <= year)
+    && self.year_max.is_none_or(|e| year <= e)
+    && (self.tags.is_empty() || tag.is_some_and(|tag| self.tags.iter().any(|e| e == tag)))
+    && (arch == Arch::Any || self.arch == Arch::Any || self.arch == arch)
+} // is_recommended() }}}
+
+// required_by() {{{
+// Imported libraries, in lowercase, which are provided by the verb
+pub fn required_by(&self, imports: &[String]) -> Vec<String>
+{
+  if self.disabled { return vec![]; } // if
+  imports.iter().filter(|import| self.imports.iter().any(|pattern|
+  {
+    let pattern = pattern.to_lowercase();
+    match pattern.strip_suffix('*')
+    {
+      Some(prefix) => import.starts_with(prefix),
+      None => **import == pattern,
+    } // match
+  })).cloned().collect()
+} // required_by() }}}
+
+// is_required() {{{
+pub fn is_required(&self, imports: &[String]) -> bool
+{
+  ! self.required_by(imports).is_empty()
+} // is_required() }}}
+
+}
+
+// struct Catalog {{{
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Catalog
+{
+  pub version: u32, // version of the file format
+  pub verbs: Vec<Verb>, // verbs in display order
+} // Catalog }}}
+
+impl Catalog
+{
+
+// from_str() {{{
+fn from_str(src: &str) -> anyhow::Result<Catalog>
+{
+  let catalog : Catalog = serde_json::from_str(src)?;
+  if catalog.version > VERSION
+  {
+    return Err(ah!("Catalog version is {}, the newest supported version is {}", catalog.version, VERSION));
+  } // if
+  Ok(catalog)
+} // from_str() }}}
+
+// merge() {{{
+// Verbs of the other catalog replace the ones with the same name, new verbs are appended
+fn merge(mut self, other: Catalog) -> Catalog
+{
+  for verb in other.verbs
+  {
+    match self.verbs.iter_mut().find(|e| e.verb == verb.verb)
+    {
+      Some(entry) => *entry = verb,
+      None => self.verbs.push(verb),
+    } // match
+  } // for
+  self
+} // merge() }}}
+
+// recommend() {{{
+// Recommended verbs grouped in the order the groups first appear in the catalog, the verbs required
+// by the imported libraries come first in their own group
+pub fn recommend(&self, year: u32, tag: Option<&str>, arch: Arch, imports: &[String]) -> Vec<(String, Vec<Verb>)>
+{
+  let mut groups : Vec<(String, Vec<Verb>)> = vec![];
+  let required : Vec<Verb> = self.verbs.iter().filter(|e| e.is_required(imports)).cloned().collect();
+  if ! required.is_empty()
+  {
+    groups.push((GROUP_REQUIRED.to_string(), required));
+  } // if
+  let count_required = groups.len();
+  for verb in self.verbs.iter().filter(|e| e.is_recommended(year, tag, arch) && ! e.is_required(imports))
+  {
+    match groups.iter_mut().skip(count_required).find(|e| e.0 == verb.group)
+    {
+      Some(group) => group.1.push(verb.clone()),
+      None => groups.push((verb.group.clone(), vec![verb.clone()])),
+    } // match
+  } // for
+  groups
+} // recommend() }}}
+
+// tags() {{{
+// Tags of the catalog in the order they first appear
+pub fn tags(&self) -> Vec<String>
+{
+  let mut tags : Vec<String> = vec![];
+  for tag in self.verbs.iter().filter(|e| ! e.disabled).flat_map(|e| e.tags.iter())
+  {
+    if ! tags.contains(tag) { tags.push(tag.clone()); }
+  } // for
+  tags
+} // tags() }}}
+
+// years() {{{
+// Release years to choose from, up to the current one
+pub fn years(&self) -> std::ops::RangeInclusive<u32>
+{
+  let secs = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|e| e.as_secs())
+    .unwrap_or_default();
+  // Average length of a gregorian year in seconds
+  let year_current = 1970 + (secs / 31_556_952) as u32;
+  let year_max = self.verbs.iter().filter_map(|e| e.year_min).chain([year_current]).max().unwrap_or(year_current);
+  1990..=year_max
+} // years() }}}
+
+}
+
+// struct Available {{{
+// Verb listed by 'winetricks list-all'
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Available
+{
+  pub verb: String, // winetricks verb
+  pub category: String, // category of winetricks, e.g., dlls
+  pub description: String, // description with the publisher and year
+} // struct Available }}}
+
+// parse_list() {{{
+// Parses the output of 'winetricks list-all', verbs are listed below their '===== category ====='
+pub fn parse_list(lines: &[String]) -> Vec<Available>
+{
+  let mut list = vec![];
+  let mut category = String::new();
+  for line in lines.iter().map(|e| e.trim()).filter(|e| ! e.is_empty())
+  {
+    if let Some(header) = line.strip_prefix("=====").and_then(|e| e.strip_suffix("====="))
+    {
+      category = header.trim().to_string();
+      continue;
+    } // if
+    let (verb, description) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    // Download state is not relevant to the wizard, e.g., '[downloadable]' or '[cached]'
+    let description = description.trim();
+    let description = match description.rfind(" [")
+    {
+      Some(i) if description.ends_with(']') => &description[..i],
+      _ => description,
+    }; // match
+    list.push(Available{ verb: verb.to_string(), category: category.clone(), description: description.trim().to_string() });
+  } // for
+  list
+} // parse_list() }}}
+
+// search() {{{
+// Verbs with every word of the query in their name, category or description
+pub fn search<'a>(list: &'a [Available], query: &str) -> Vec<&'a Available>
+{
+  let words : Vec<String> = query.split_whitespace().map(|e| e.to_lowercase()).collect();
+  list.iter().filter(|e|
+  {
+    let text = format!("{} {} {}", e.verb, e.category, e.description).to_lowercase();
+    words.iter().all(|word| text.contains(word))
+  }).collect()
+} // search() }}}
+
+// installed() {{{
+// Verbs applied to the prefix, in the order winetricks logged them
+pub fn installed(path_dir_prefix: &Path) -> Vec<String>
+{
+  let mut verbs : Vec<String> = vec![];
+  if let Ok(str_log) = std::fs::read_to_string(path_dir_prefix.join("winetricks.log"))
+  {
+    for verb in str_log.lines().map(|e| e.trim()).filter(|e| ! e.is_empty())
+    {
+      if ! verbs.iter().any(|e| e == verb) { verbs.push(verb.to_string()); }
+    } // for
+  } // if
+  verbs
+} // installed() }}}
+
+// path_file_user() {{{
+// User catalog, merged over the shipped one
+pub fn path_file_user() -> anyhow::Result<PathBuf>
+{
+  let path_dir_config = match std::env::var("XDG_CONFIG_HOME")
+  {
+    Ok(path) if ! path.is_empty() => PathBuf::from(path),
+    _ => PathBuf::from(std::env::var("HOME")?).join(".config"),
+  }; // match
+  Ok(path_dir_config.join("gameimage").join("winetricks.json"))
+} // path_file_user() }}}
+
+// path_file_shipped() {{{
+// Catalog installed with the wizard, in share/gameimage next to the bin directory
+pub fn path_file_shipped() -> anyhow::Result<PathBuf>
+{
+  let path_file_exe = std::env::current_exe()?;
+  let path_dir_prefix = path_file_exe.parent()
+    .and_then(|e| e.parent())
+    .ok_or(ah!("Could not find the installation directory of '{}'", path_file_exe.string()))?;
+  Ok(path_dir_prefix.join("share").join("gameimage").join("winetricks.json"))
+} // path_file_shipped() }}}
+
+// read_shipped() {{{
+// Reads the catalog data file, the embedded copy is used when it is missing or broken
+fn read_shipped(path_file_shipped: &Path) -> anyhow::Result<Catalog>
+{
+  if ! path_file_shipped.exists()
+  {
+    return Catalog::from_str(CATALOG);
+  } // if
+  let catalog = std::io::read_to_string(File::open(path_file_shipped)?)
+    .map_err(|e| ah!(e))
+    .and_then(|e| Catalog::from_str(&e));
+  match catalog
+  {
+    Ok(catalog) => Ok(catalog),
+    Err(e) =>
+    {
+      log!("Ignoring catalog '{}', using the built-in one: {}", path_file_shipped.display(), e);
+      Catalog::from_str(CATALOG)
+    },
+  } // match
+} // read_shipped() }}}
+
+// read() {{{
+// Reads the shipped catalog, overridden by the user catalog if any
+pub fn read() -> anyhow::Result<Catalog>
+{
+  let catalog = match path_file_shipped()
+  {
+    Ok(path_file_shipped) => read_shipped(&path_file_shipped)?,
+    Err(_) => Catalog::from_str(CATALOG)?,
+  }; // match
+  let path_file_user = match path_file_user()
+  {
+    Ok(path) if path.exists() => path,
+    _ => return Ok(catalog),
+  }; // match
+  // A broken user catalog should not hide the shipped recommendations
+  let catalog_user = std::io::read_to_string(File::open(&path_file_user)?)
+    .map_err(|e| ah!(e))
+    .and_then(|e| Catalog::from_str(&e));
+  match catalog_user
+  {
+    Ok(catalog_user) => Ok(catalog.merge(catalog_user)),
+    Err(e) => { log!("Ignoring user catalog '{}': {}", path_file_user.string(), e); Ok(catalog) },
+  } // match
+} // fn: read }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{Arch, Catalog};
+
+  #[test]
+  fn shipped_catalog_is_valid()
+  {
+    let catalog = Catalog::from_str(super::CATALOG).unwrap();
+    assert_eq!(catalog.version, super::VERSION);
+    assert!(catalog.verbs.iter().all(|e| ! e.verb.is_empty() && ! e.description.is_empty() && ! e.group.is_empty()));
+    let verbs = |year, tag, arch| -> Vec<String>
+    {
+      catalog.recommend(year, tag, arch, &[]).into_iter().flat_map(|e| e.1).map(|e| e.verb).collect()
+    };
+    assert!(verbs(2000, None, Arch::Any).contains(&"vcrun6".to_string()));
+    assert!(! verbs(2010, None, Arch::Any).contains(&"vcrun6".to_string()));
+    assert!(verbs(2010, None, Arch::Win32).contains(&"xact".to_string()));
+    assert!(! verbs(2010, None, Arch::Win32).contains(&"xact_x64".to_string()));
+    assert!(! verbs(2010, None, Arch::Any).contains(&"physx".to_string()));
+    assert!(verbs(2010, Some("unreal"), Arch::Any).contains(&"physx".to_string()));
+  }
+
+  #[test]
+  fn user_catalog_overrides_verbs()
+  {
+    let catalog = Catalog::from_str(r#"{ "version": 1, "verbs":
+    [
+      { "verb": "a", "description": "A", "group": "First" },
+      { "verb": "b", "description": "B", "group": "Second", "year_max": 2000 },
+      { "verb": "c", "description": "C", "group": "First", "tags": ["unity"] }
+    ]}"#).unwrap();
+    let user = Catalog::from_str(r#"{ "version": 1, "verbs":
+    [
+      { "verb": "a", "disabled": true },
+      { "verb": "b", "description": "B2", "group": "Second" },
+      { "verb": "d", "description": "D", "group": "First" }
+    ]}"#).unwrap();
+    let catalog = catalog.merge(user);
+    let groups = catalog.recommend(2020, Some("unity"), Arch::Win64, &[]);
+    let groups : Vec<(String, Vec<String>)> = groups.into_iter()
+      .map(|(group, verbs)| (group, verbs.into_iter().map(|e| e.description).collect()))
+      .collect();
+    assert_eq!(groups, vec![
+      ("Second".to_string(), vec!["B2".to_string()]),
+      ("First".to_string(), vec!["C".to_string(), "D".to_string()]),
+    ]);
+    assert_eq!(catalog.tags(), vec!["unity"]);
+  }
+
+  #[test]
+  fn imports_require_verbs()
+  {
+    let catalog = Catalog::from_str(super::CATALOG).unwrap();
+    let imports : Vec<String> = ["kernel32.dll", "d3dx9_43.dll", "msvcp140.dll", "xinput1_3.dll", "d3dx11_43.dll"]
+      .iter()
+      .map(|e| e.to_string())
+      .collect();
+    let groups = catalog.recommend(1998, None, Arch::Win32, &imports);
+    assert_eq!(groups[0].0, super::GROUP_REQUIRED);
+    let required : Vec<String> = groups[0].1.iter().map(|e| e.verb.clone()).collect();
+    assert_eq!(required, vec!["xinput", "vcrun2022", "d3dx11_43", "d3dx9"]);
+    assert_eq!(groups[0].1[3].required_by(&imports), vec!["d3dx9_43.dll"]);
+    // Required verbs are not repeated in their groups, verbs only required by imports are hidden
+    let others : Vec<String> = groups[1..].iter().flat_map(|e| e.1.iter()).map(|e| e.verb.clone()).collect();
+    assert!(! others.contains(&"xinput".to_string()));
+    assert!(! others.contains(&"d3dx10".to_string()));
+    assert!(others.contains(&"vcrun6".to_string()));
+    assert!(catalog.recommend(1998, None, Arch::Win32, &[]).iter().all(|e| e.0 != super::GROUP_REQUIRED));
+  }
+
+  #[test]
+  fn parse_winetricks_list()
+  {
+    let lines : Vec<String> = [
+      "===== apps =====",
+      "7zip                     7-Zip 24.09 (Igor Pavlov, 2024) [downloadable]",
+      "===== dlls =====",
+      "d3dx9                    MS d3dx9_??.dll from DirectX 9 redistributable (Microsoft, 2010) [cached]",
+      "",
+      "xact_x64                 MS XACT Engine (64-bit only) (Microsoft, 2010)",
+    ].iter().map(|e| e.to_string()).collect();
+    let list = super::parse_list(&lines);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list[0], super::Available{ verb: "7zip".into(), category: "apps".into(), description: "7-Zip 24.09 (Igor Pavlov, 2024)".into() });
+    assert_eq!(list[1].category, "dlls");
+    assert_eq!(list[1].description, "MS d3dx9_??.dll from DirectX 9 redistributable (Microsoft, 2010)");
+    assert_eq!(list[2].description, "MS XACT Engine (64-bit only) (Microsoft, 2010)");
+    let verbs = |query| super::search(&list, query).iter().map(|e| e.verb.clone()).collect::<Vec<_>>();
+    assert_eq!(verbs("microsoft"), vec!["d3dx9", "xact_x64"]);
+    assert_eq!(verbs("DLLS 64-bit"), vec!["xact_x64"]);
+    assert_eq!(verbs(""), vec!["7zip", "d3dx9", "xact_x64"]);
+  }
+
+  #[test]
+  fn installed_verbs_from_log()
+  {
+    let dir = crate::testing::TempDir::new();
+    assert!(super::installed(&dir.path).is_empty());
+    std::fs::write(dir.path.join("winetricks.log"), "fontsmooth=rgb\nd3dx9\n\nvcrun2019\nd3dx9\n").unwrap();
+    assert_eq!(super::installed(&dir.path), vec!["fontsmooth=rgb", "d3dx9", "vcrun2019"]);
+  }
+
+  #[test]
+  fn shipped_catalog_is_read_from_the_data_file()
+  {
+    let dir = crate::testing::TempDir::new();
+    let path_file_shipped = dir.path.join("winetricks.json");
+    // Missing or broken data file falls back to the built-in catalog
+    let verbs = |path: &std::path::Path| -> Vec<String>
+    {
+      super::read_shipped(path).unwrap().verbs.into_iter().map(|e| e.verb).collect()
+    };
+    assert!(verbs(&path_file_shipped).contains(&"vcrun6".to_string()));
+    std::fs::write(&path_file_shipped, "{ broken").unwrap();
+    assert!(verbs(&path_file_shipped).contains(&"vcrun6".to_string()));
+    std::fs::write(&path_file_shipped, r#"{ "version": 1, "verbs": [{ "verb": "a", "description": "A", "group": "First" }] }"#).unwrap();
+    assert_eq!(verbs(&path_file_shipped), vec!["a"]);
+  }
+
+  #[test]
+  fn newer_catalog_is_rejected()
+  {
+    assert!(Catalog::from_str(r#"{ "version": 99, "verbs": [] }"#).is_err());
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -11,6 +11,7 @@ use shared::{tabs,hover_blink,hseparator_fixed,column,row,add,fixed};
 
 use crate::dimm;
 use crate::frame;
+use crate::gameimage;
 
 // pub fn layout() {{{
 pub fn layout()
@@ -20,6 +21,10 @@ pub fn layout()
     col.set_spacing(dimm::border_half());
     row!(row_header,
       fixed!(row_header, btn_term, shared::fltk::button::rect::terminal().with_color(Color::Blue), dimm::width_button_rec());
+      fixed!(row_header, btn_jobs, shared::fltk::button::rect::list()
+        .with_id("btn_jobs")
+        .with_color(Color::Blue), dimm::width_button_rec()
+      );
       add!(row_header, frame_title, Frame::default().with_id("header_title").with_align(Align::Inside | Align::Center));
       fixed!(row_header, btn_resize, shared::fltk::button::rect::resize_down()
         .with_id("btn_resize")
@@ -34,6 +39,7 @@ pub fn layout()
         col_content_footer.set_color(Color::BackGround);
         column!(group_content, group_content.set_id("content"););
         col_content_footer.add(&group_content);
+        col_content_footer.fixed(&frame::jobs::panel(), dimm::height_button_wide()*5);
         hseparator_fixed!(col_content_footer, dimm::width_wizard() - dimm::border()*2, dimm::border_half());
         row!(footer,
           footer.set_id("footer");
@@ -61,6 +67,7 @@ pub fn layout()
 
   // Configure buttons
   hover_blink!(btn_term);
+  hover_blink!(btn_jobs);
   hover_blink!(btn_resize);
   hover_blink!(btn_prev);
   hover_blink!(btn_next);
@@ -68,6 +75,9 @@ pub fn layout()
   // Title font size
   frame_title.clone().set_label_size((dimm::height_text() as f32 * 1.5) as i32);
 
+  // Show or hide the queued jobs
+  btn_jobs.clone().set_callback(|_| frame::jobs::toggle());
+
   // Switch between tabs
   btn_term.clone().set_callback({
     let col_content_term = col_content_term.clone();
@@ -87,4 +97,38 @@ pub fn layout()
 
 } // }}}
 
+// pub fn cancel() {{{
+// Button that cancels the running backend jobs, it is only active while the window is deactivated
+pub fn cancel() -> fltk::button::Button
+{
+  let mut btn_cancel = shared::fltk::button::wide::default()
+    .with_id("btn_cancel")
+    .with_label("Cancel")
+    .with_color(Color::Red);
+  hover_blink!(btn_cancel);
+  btn_cancel.set_callback(|_| gameimage::job::cancel_all());
+  btn_cancel.deactivate();
+  btn_cancel
+} // fn: cancel }}}
+
+// pub fn cancel_set_active() {{{
+// Toggles the cancel button of the current frame, if any
+pub fn cancel_set_active(make_active: bool)
+{
+  let mut btn_cancel : fltk::button::Button = match fltk::app::widget_from_id("btn_cancel")
+  {
+    Some(btn_cancel) => btn_cancel,
+    None => return,
+  }; // match
+  if ! make_active { btn_cancel.deactivate(); return; }
+  // The parents were deactivated with the window content, their other children remain inactive
+  btn_cancel.activate();
+  let mut parent = btn_cancel.parent();
+  while let Some(mut group) = parent
+  {
+    group.activate();
+    parent = group.parent();
+  } // while
+} // fn: cancel_set_active }}}
+
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
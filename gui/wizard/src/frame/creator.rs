@@ -28,6 +28,8 @@ use crate::log;
 use crate::log_err;
 use crate::log_status;
 use crate::db;
+use crate::headless;
+use crate::frame;
 
 lazy_static!
 {
@@ -35,7 +37,7 @@ lazy_static!
 }
 
 // fn create_entry() {{{
-fn create_entry(project : db::project::Entry, height: i32)
+fn create_entry(tx: Sender<common::Msg>, project : db::project::Entry, height: i32)
   -> anyhow::Result<(group::Flex, button::CheckButton, db::project::Entry)>
 {
   let mut row = fltk::group::Flex::default()
@@ -104,6 +106,27 @@ fn create_entry(project : db::project::Entry, height: i32)
   frame_info.end();
   row.add(&frame_info);
 
+  //
+  // Hooks
+  //
+  let mut btn_hooks = shared::fltk::button::rect::configure()
+    .with_size(dimm::width_button_rec(), dimm::height_button_rec())
+    .with_color(Color::Blue);
+  btn_hooks.set_tooltip("Configure the commands to run before launch and after exit");
+  hover_blink!(btn_hooks);
+  let clone_project = project.clone();
+  btn_hooks.set_callback(move |_|
+  {
+    *frame::hooks::PROJECT.lock().unwrap() = Some(clone_project.clone());
+    tx.send_awake(common::Msg::DrawHooks);
+  });
+  column!(col_hooks,
+    col_hooks.add(&Frame::default());
+    col_hooks.fixed(&btn_hooks, dimm::height_button_rec());
+    col_hooks.add(&Frame::default());
+  );
+  row.fixed(&col_hooks, dimm::width_button_rec());
+
   //
   // CheckButton
   //
@@ -155,6 +178,8 @@ pub fn creator(tx: Sender<common::Msg>, title: &str)
       fixed!(col_buttons, btn_add, shared::fltk::button::rect::add().with_color(Color::Green), dimm::height_button_rec());
       fixed!(col_buttons, btn_del, shared::fltk::button::rect::del().with_color(Color::Red), dimm::height_button_rec());
       fixed!(col_buttons, btn_sel_all, shared::fltk::button::rect::check_all().with_color(Color::Blue), dimm::height_button_rec());
+      fixed!(col_buttons, btn_export, shared::fltk::button::rect::save().with_color(Color::Blue), dimm::height_button_rec());
+      fixed!(col_buttons, btn_import, shared::fltk::button::rect::folder().with_color(Color::Blue), dimm::height_button_rec());
       col_buttons.add(&Frame::default_fill());
     );
     row.fixed(&col_buttons, dimm::width_button_rec());
@@ -192,7 +217,7 @@ pub fn creator(tx: Sender<common::Msg>, title: &str)
     // Include select all button and projects in the column
     for project in &projects
     {
-      let (row_project, button, project) = match create_entry(project.clone(), dimm::height_button_rec()*4)
+      let (row_project, button, project) = match create_entry(tx, project.clone(), dimm::height_button_rec()*4)
       {
         Ok(ret) => ret,
         Err(e) => { log_status!("Could not create entry for project with error: {}", e); continue; },
@@ -229,6 +254,59 @@ pub fn creator(tx: Sender<common::Msg>, title: &str)
     });
   });
 
+  // Export selected projects to a recipe
+  let mut btn_export = btn_export.clone();
+  hover_blink!(btn_export);
+  let clone_vec_checkbutton = vec_btn.clone();
+  btn_export.set_callback(move |_|
+  {
+    let vec_project : Vec<db::project::Entry> = clone_vec_checkbutton.lock().unwrap().iter()
+      .filter(|e| e.0.is_checked())
+      .map(|e| e.1.clone())
+      .collect();
+    let name = match vec_project.first()
+    {
+      Some(project) => project.get_project(),
+      None => { log_status!("No project was selected"); return; },
+    }; // match
+    let mut chooser = dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseSaveFile);
+    chooser.set_title("Export recipe as...");
+    chooser.set_filter("*.{yml,yaml}");
+    chooser.set_preset_file(&format!("{}.yml", name));
+    chooser.set_option(dialog::NativeFileChooserOptions::SaveAsConfirm);
+    chooser.show();
+    let path_file_recipe = chooser.filename();
+    if path_file_recipe.as_os_str().is_empty() { log_status!("No file selected"); return; } // if
+    match db::recipe::from_projects(&name, &vec_project)
+      .and_then(|recipe| db::recipe::write(&path_file_recipe, &recipe))
+    {
+      Ok(()) => log_status!("Exported recipe to '{}'", path_file_recipe.string()),
+      Err(e) => log_status!("Could not export recipe: {}", e),
+    } // match
+  });
+
+  // Import projects from a recipe
+  let mut btn_import = btn_import.clone();
+  hover_blink!(btn_import);
+  btn_import.set_callback(move |_|
+  {
+    let path_file_recipe = match dialog::file_chooser("Import recipe", "*.{yml,yaml}", ".", false)
+    {
+      Some(value) => PathBuf::from(value),
+      None => { log_status!("No file selected"); return; },
+    }; // match
+    tx.send_awake(common::Msg::WindDeactivate);
+    std::thread::spawn(move ||
+    {
+      match headless::import(&path_file_recipe)
+      {
+        Ok(()) => log_status!("Imported recipe '{}', compress its projects before including them", path_file_recipe.string()),
+        Err(e) => log_status!("Could not import recipe: {}", e),
+      } // match
+      tx.send_activate(common::Msg::DrawCreator);
+    });
+  });
+
   // Finish package creation on click next
   let clone_vec_btn = vec_btn.clone();
   ui.btn_next.clone().set_callback(move |_|
@@ -0,0 +1,180 @@
+// Gui
+use std::
+{
+  path::PathBuf,
+  sync::{Mutex,LazyLock},
+  os::unix::fs::PermissionsExt,
+};
+
+use fltk::prelude::*;
+use fltk::{
+  app::Sender,
+  frame::Frame,
+  dialog,
+  enums::{Color,Align},
+};
+
+use shared::fltk::WidgetExtExtra;
+use shared::fltk::SenderExt;
+use shared::std::PathBufExt;
+use shared::dimm;
+use shared::db::hooks::Stage;
+use shared::{rescope,hover_blink,column,row,fixed,scroll,hpack};
+
+use crate::log;
+use crate::log_status;
+use crate::log_return_void;
+use crate::common;
+use crate::db;
+
+// Project whose hooks are edited, selected in the creator frame
+pub static PROJECT: LazyLock<Mutex<Option<db::project::Entry>>> = LazyLock::new(|| Mutex::new(None));
+
+// fn get_path_dir_project() {{{
+fn get_path_dir_project() -> anyhow::Result<PathBuf>
+{
+  match PROJECT.lock().unwrap().as_ref()
+  {
+    Some(project) => project.get_dir_self(),
+    None => Err(anyhow::anyhow!("No project was selected")),
+  } // match
+} // fn: get_path_dir_project }}}
+
+// fn update() {{{
+// Applies f to the hooks of the project and saves them
+fn update(f: impl FnOnce(&mut shared::db::hooks::Hooks)) -> anyhow::Result<()>
+{
+  let path_file_db = get_path_dir_project()?.join(shared::db::hooks::FILE);
+  let mut hooks = shared::db::hooks::read(&path_file_db)?;
+  f(&mut hooks);
+  shared::db::hooks::write(&path_file_db, &hooks)
+} // fn: update }}}
+
+// fn script() {{{
+// Copies the script into the project, the command which runs it is relative to the project
+// directory, where the launcher runs the hooks
+fn script(path_file_script: &PathBuf) -> anyhow::Result<String>
+{
+  let name = path_file_script.file_name_string();
+  let path_dir_scripts = get_path_dir_project()?.join(shared::db::hooks::DIR_SCRIPTS);
+  std::fs::create_dir_all(&path_dir_scripts)?;
+  let path_file_dst = path_dir_scripts.join(&name);
+  std::fs::copy(path_file_script, &path_file_dst)?;
+  std::fs::set_permissions(&path_file_dst, std::fs::Permissions::from_mode(0o755))?;
+  Ok(format!("'./{}/{}'", shared::db::hooks::DIR_SCRIPTS, name.replace('\'', "'\\''")))
+} // fn: script }}}
+
+// fn stage() {{{
+// Commands of the stage and the widgets to add new ones
+fn stage(tx: Sender<common::Msg>, stage: Stage, label: &str, commands: &[String])
+{
+  let mut frame_label = Frame::default()
+    .with_align(Align::Inside | Align::Left)
+    .with_label(label);
+  frame_label.resize(frame_label.x(), frame_label.y(), frame_label.w(), dimm::height_text());
+  for (index, command) in commands.iter().enumerate()
+  {
+    row!(row,
+      let mut output_command = fltk::output::Output::default();
+      output_command.set_value(command);
+      row.add(&output_command);
+      fixed!(row, btn_del, shared::fltk::button::rect::del(), dimm::width_button_rec());
+    );
+    row.resize(row.x(), row.y(), row.w(), dimm::height_button_rec());
+    hover_blink!(btn_del);
+    btn_del.clone().with_color(Color::Red).set_callback(move |_|
+    {
+      if let Err(e) = update(|hooks| { hooks.get_mut(stage).remove(index); })
+      {
+        log_status!("Could not erase hook: {}", e);
+      } // if
+      tx.send_awake(common::Msg::DrawHooks);
+    });
+  } // for
+  row!(row,
+    let input_command = fltk::input::Input::default();
+    row.add(&input_command);
+    fixed!(row, btn_add, shared::fltk::button::rect::add(), dimm::width_button_rec());
+    fixed!(row, btn_script, shared::fltk::button::rect::folder(), dimm::width_button_rec());
+  );
+  row.resize(row.x(), row.y(), row.w(), dimm::height_button_rec());
+  hover_blink!(btn_add);
+  hover_blink!(btn_script);
+  // Add the command of the input field
+  let mut btn_add = btn_add.clone().with_color(Color::Green);
+  btn_add.set_tooltip("Add the command");
+  btn_add.set_callback(move |_|
+  {
+    let command = input_command.value().trim().to_string();
+    if command.is_empty() { log_status!("Type a command to add it"); return; } // if
+    if let Err(e) = update(|hooks| hooks.get_mut(stage).push(command))
+    {
+      log_status!("Could not add hook: {}", e);
+    } // if
+    tx.send_awake(common::Msg::DrawHooks);
+  });
+  // Add a script
+  let mut btn_script = btn_script.clone().with_color(Color::Blue);
+  btn_script.set_tooltip("Copy a script into the project and add it");
+  btn_script.set_callback(move |_|
+  {
+    let path_file_script = match dialog::file_chooser("Select the script to run", "*", ".", false)
+    {
+      Some(value) => PathBuf::from(value),
+      None => { log_status!("No file selected"); return; },
+    }; // match
+    match script(&path_file_script).and_then(|command| update(|hooks| hooks.get_mut(stage).push(command)))
+    {
+      Ok(()) => log_status!("Added script '{}'", path_file_script.string()),
+      Err(e) => log_status!("Could not add script: {}", e),
+    } // match
+    tx.send_awake(common::Msg::DrawHooks);
+  });
+} // fn: stage }}}
+
+// pub fn hooks() {{{
+// Commands the launcher runs before the game of the project starts and after it exits
+pub fn hooks(tx: Sender<common::Msg>, title: &str)
+{
+  let ui = crate::GUI.lock().unwrap().ui.clone()(title);
+  // Layout
+  column!(col,
+    col.set_spacing(dimm::border_half());
+    let mut output_project = fltk::output::Output::default();
+    col.fixed(&output_project, dimm::height_button_wide());
+    col.fixed(&Frame::default()
+      .with_align(Align::Inside | Align::Left)
+      .with_label("Commands run with 'sh -c' in the project directory, a failed launch hook cancels the launch")
+      , dimm::height_text()
+    );
+    col.fixed(&shared::fltk::separator::horizontal(col.w()), dimm::height_sep());
+    scroll!(scroll,
+      hpack!(col_content, col_content.set_spacing(dimm::border_half()););
+    );
+  );
+  // Configure footer
+  ui.btn_prev.clone().emit(tx, common::Msg::DrawCreator);
+  ui.btn_next.clone().deactivate();
+  // Resize content to scroll width
+  scroll.set_type(fltk::group::ScrollType::VerticalAlways);
+  scroll.resize_callback({
+    let mut col_content = col_content.clone();
+    move |_,x,y,w,_|
+    {
+      col_content.resize(x,y,w-dimm::border_half()*3,col_content.h());
+    }
+  });
+  if let Some(project) = PROJECT.lock().unwrap().as_ref() { output_project.set_value(&project.get_project()); } // if
+  let hooks = match get_path_dir_project().and_then(|e| shared::db::hooks::read(&e.join(shared::db::hooks::FILE)))
+  {
+    Ok(hooks) => hooks,
+    Err(e) => log_return_void!("Could not read hooks: {}", e),
+  }; // match
+  rescope!(col_content,
+    stage(tx, Stage::Pre, "Before launch, e.g., mount a drive, set the resolution or start an input mapper", &hooks.pre);
+    col_content.add(&shared::fltk::separator::horizontal(col_content.w()));
+    stage(tx, Stage::Post, "After exit, e.g., restore the resolution or back up the saves", &hooks.post);
+  );
+} // fn: hooks }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -24,10 +24,12 @@ use crate::db;
 use crate::common;
 use crate::log;
 use crate::log_status;
-use crate::log_return_void;
 use crate::log_err;
 use crate::gameimage;
 
+// Previously selected icon path
+static OPTION_PATH_FILE_ICON : once_cell::sync::Lazy<Arc<Mutex<Option<PathBuf>>>> = once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(None)));
+
 // resize_draw_image() {{{
 pub fn resize_draw_image(mut frame : Frame, path_file_icon : PathBuf) -> anyhow::Result<()>
 {
@@ -86,9 +88,6 @@ pub fn icon(tx: Sender<common::Msg>
   , msg_prev : common::Msg
   , _msg_curr : common::Msg) -> (crate::Ui, Icon)
 {
-  // Save previously selected icon path
-  static OPTION_PATH_FILE_ICON : once_cell::sync::Lazy<Arc<Mutex<Option<PathBuf>>>> = once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(None)));
-
   let ui = crate::GUI.lock().unwrap().ui.clone()(title);
 
   let mut ret = Icon
@@ -170,6 +169,23 @@ pub fn icon(tx: Sender<common::Msg>
   (ui, ret)
 } // }}}
 
+// pub fn suggest() {{{
+// Preselects the icon if the user has not picked one yet
+pub fn suggest(path_file_icon: PathBuf) -> bool
+{
+  let mut option_path_file_icon = OPTION_PATH_FILE_ICON.lock().unwrap();
+  if option_path_file_icon.is_some() { return false; } // if
+  *option_path_file_icon = Some(path_file_icon);
+  true
+} // fn: suggest }}}
+
+// pub fn reset() {{{
+// Forgets the icon selected for the previous project
+pub fn reset()
+{
+  *OPTION_PATH_FILE_ICON.lock().unwrap() = None;
+} // fn: reset }}}
+
 // pub fn project() {{{
 pub fn project(tx: Sender<common::Msg>
   , title: &str
@@ -198,22 +214,13 @@ pub fn project(tx: Sender<common::Msg>
       return;
     };
 
-    // Set selected icon as icon
-    clone_tx.send_awake(common::Msg::WindDeactivate);
-    let clone_tx = clone_tx.clone();
-    std::thread::spawn(move ||
+    // Set selected icon as icon in the background, a failure is retried from the job panel
+    gameimage::queue::push("Install icon", vec![msg_curr], move ||
     {
-      // Try to install icon
-      log_status!("Installing icon...");
-
-      match gameimage::install::icon(&path_file_icon)
-      {
-        Ok(_) => log_status!("Successfully installed icon"),
-        Err(e) => { clone_tx.send_activate(msg_curr); log_return_void!("Could not install icon with error: {}", e); },
-      } // match
-
-      clone_tx.send_activate(msg_next);
+      gameimage::install::icon(&path_file_icon)
     });
+    log_status!("Queued icon installation");
+    clone_tx.send_awake(msg_next);
   });
 } // }}}
 
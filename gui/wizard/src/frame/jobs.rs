@@ -0,0 +1,164 @@
+// Gui
+use std::sync::atomic::{AtomicU64,Ordering};
+
+use fltk::prelude::*;
+use fltk::{
+  browser::HoldBrowser,
+  menu::MenuButton,
+  text::{TextDisplay,TextBuffer},
+  enums::{Color,FrameType},
+};
+
+use shared::fltk::WidgetExtExtra;
+use shared::{hover_blink,column,row,add,fixed};
+
+use crate::dimm;
+use crate::gameimage::queue::{self,State};
+
+// Job which output is displayed
+static SELECTED: AtomicU64 = AtomicU64::new(0);
+// Job which prompt is displayed
+static PROMPTED: AtomicU64 = AtomicU64::new(0);
+
+// pub fn panel() {{{
+// Collapsible panel with the state, output and exit code of the queued jobs
+pub fn panel() -> fltk::group::Flex
+{
+  column!(col,
+    col.set_id("jobs_panel");
+    row!(row,
+      add!(row, browser, HoldBrowser::default().with_id("jobs_browser"));
+      add!(row, display, TextDisplay::default().with_id("jobs_output"));
+    );
+    row!(row_buttons,
+      fixed!(row_buttons, btn_retry, shared::fltk::button::wide::default().with_label("Retry"), dimm::width_button_wide());
+      fixed!(row_buttons, btn_cancel, shared::fltk::button::wide::default().with_label("Cancel"), dimm::width_button_wide());
+      row_buttons.add(&fltk::frame::Frame::default());
+      fixed!(row_buttons, menu_prompt, MenuButton::default().with_id("jobs_prompt"), dimm::width_button_wide()*2);
+      fixed!(row_buttons, btn_clear, shared::fltk::button::wide::default().with_label("Clear"), dimm::width_button_wide());
+    );
+    col.fixed(&row_buttons, dimm::height_button_wide());
+  );
+  // Jobs
+  let mut browser = browser.clone();
+  browser.set_frame(FrameType::BorderBox);
+  browser.set_text_size(dimm::height_text());
+  browser.set_column_char('\t');
+  browser.set_column_widths(&[dimm::width_button_wide(), dimm::width_button_wide()]);
+  browser.set_callback(|e|
+  {
+    if let Some(entry) = queue::entries().get((e.value() as usize).wrapping_sub(1))
+    {
+      SELECTED.store(entry.id, Ordering::Relaxed);
+    } // if
+    refresh();
+  });
+  // Output of the selected job
+  let mut display = display.clone();
+  display.set_buffer(TextBuffer::default());
+  display.set_text_size(dimm::height_text());
+  display.set_scrollbar_size(dimm::border());
+  // Buttons
+  hover_blink!(btn_retry);
+  hover_blink!(btn_cancel);
+  hover_blink!(btn_clear);
+  btn_retry.clone().with_color(Color::Green).set_callback(|_| queue::retry(SELECTED.load(Ordering::Relaxed)));
+  btn_cancel.clone().with_color(Color::Red).set_callback(|_| queue::cancel(SELECTED.load(Ordering::Relaxed)));
+  btn_clear.clone().with_color(Color::Blue).set_callback(|_| queue::clear());
+  // Choices a running job asks for, e.g., the disc to insert
+  let mut menu_prompt = menu_prompt.clone();
+  menu_prompt.set_callback(|e|
+  {
+    let index = e.value();
+    if index < 0 { return; } // if
+    queue::choose(PROMPTED.load(Ordering::Relaxed), index as usize);
+  });
+  menu_prompt.hide();
+  // Starts collapsed
+  col.clone().hide();
+  col
+} // fn: panel }}}
+
+// pub fn toggle() {{{
+pub fn toggle()
+{
+  let mut panel : fltk::group::Flex = match fltk::app::widget_from_id("jobs_panel")
+  {
+    Some(panel) => panel,
+    None => return,
+  }; // match
+  if panel.visible() { panel.hide(); } else { panel.show(); refresh(); }
+  if let Some(parent) = panel.parent().and_then(|e| fltk::group::Flex::from_dyn_widget(&e))
+  {
+    parent.layout();
+  } // if
+} // fn: toggle }}}
+
+// pub fn refresh() {{{
+// Redraws the list of jobs and the output of the selected one
+pub fn refresh()
+{
+  let entries = queue::entries();
+  // Highlight the toggle button while jobs are pending
+  if let Some(mut btn_jobs) = fltk::app::widget_from_id::<fltk::button::Button>("btn_jobs")
+  {
+    let count = entries.iter().filter(|e| e.state.is_pending()).count();
+    btn_jobs.set_color(if count > 0 { Color::Green } else { Color::Blue });
+    btn_jobs.set_tooltip(&format!("{} pending job(s)", count));
+    btn_jobs.redraw();
+  } // if
+  // Prompt of the selected job, or of the first job with one
+  if let Some(mut menu_prompt) = fltk::app::widget_from_id::<MenuButton>("jobs_prompt")
+  {
+    let id_selected = SELECTED.load(Ordering::Relaxed);
+    let prompted = entries.iter()
+      .filter(|e| e.prompt.is_some())
+      .min_by_key(|e| e.id != id_selected)
+      .and_then(|e| e.prompt.clone().map(|prompt| (e.id, prompt)));
+    match prompted
+    {
+      Some((id, prompt)) =>
+      {
+        PROMPTED.store(id, Ordering::Relaxed);
+        menu_prompt.clear();
+        for choice in prompt.choices.iter() { menu_prompt.add_choice(&choice.replace('/', "\\/")); } // for
+        menu_prompt.set_label(prompt.choices.get(prompt.selected).map(|e| e.as_str()).unwrap_or_default());
+        menu_prompt.set_tooltip(&prompt.label);
+        menu_prompt.show();
+      },
+      None => menu_prompt.hide(),
+    } // match
+  } // if
+  let (mut browser, mut display) = match (fltk::app::widget_from_id::<HoldBrowser>("jobs_browser")
+    , fltk::app::widget_from_id::<TextDisplay>("jobs_output"))
+  {
+    (Some(browser), Some(display)) if browser.visible_r() => (browser, display),
+    _ => return,
+  }; // match
+  let id_selected = SELECTED.load(Ordering::Relaxed);
+  browser.clear();
+  for entry in entries.iter()
+  {
+    let color = match entry.state
+    {
+      State::Failed => "@C1",
+      State::Done => "@C2",
+      _ => "",
+    }; // match
+    let code = entry.code.map(|e| format!(" ({})", e)).unwrap_or_default();
+    browser.add(&format!("{}{}{}\t{}\t{}", color, entry.state.as_str(), code, entry.id, entry.label));
+  } // for
+  let output = match entries.iter().position(|e| e.id == id_selected)
+  {
+    Some(idx) => { browser.select(idx as i32 + 1); entries[idx].output.join("\n") },
+    None => String::new(),
+  }; // match
+  if let Some(mut buffer) = display.buffer() && buffer.text() != output
+  {
+    buffer.set_text(&output);
+    let lines = display.count_lines(0, buffer.length(), true);
+    display.scroll(lines, 0);
+  } // if
+} // fn: refresh }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
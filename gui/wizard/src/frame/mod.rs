@@ -3,6 +3,8 @@ pub mod common;
 pub mod welcome;
 pub mod platform;
 pub mod creator;
+pub mod hooks;
 pub mod desktop;
 pub mod finish;
 pub mod icon;
+pub mod jobs;
@@ -16,9 +16,12 @@ use shared::fltk::SenderExt;
 use shared::{hover_blink,column,row,add,fixed};
 
 use crate::db;
+use crate::lib;
 use crate::dimm;
+use crate::frame;
 use crate::common;
 use crate::log;
+use crate::log_status;
 use crate::log_err_status;
 use crate::gameimage;
 
@@ -55,28 +58,38 @@ fn fetch_backend(tx: Sender<common::Msg>
 {
   tx.send_awake(common::Msg::WindDeactivate);
   let clone_tx = tx.clone();
-  let f_progress = move |rx: std::sync::mpsc::Receiver<String>|
+  let mut f_progress = move |event: lib::ipc::Event|
   {
-    while let Ok(msg) = rx.recv()
+    match event
     {
-      match msg.parse::<f64>()
+      // Show the size, rate and eta of the download in the bar
+      lib::ipc::Event::Download(download) =>
       {
-        Ok(progress) => widget_progress.set_value(progress),
-        Err(e) => { log!("Could not convert progress to float: {}", e); return; },
-      }; // match
-    }; // match
+        widget_progress.set_value(download.percentage());
+        widget_progress.set_label(&format!(" {}", download));
+      },
+      lib::ipc::Event::Progress{ value } => widget_progress.set_value(value),
+      _ => (),
+    } // match
   };
   std::thread::spawn(move ||
   {
-    match gameimage::fetch::fetch(platform, f_progress)
+    loop
     {
-      Ok(_) => log!("Successfully fetched file"),
-      Err(e) =>
+      match gameimage::fetch::fetch(platform.clone(), &mut f_progress)
       {
-        fltk::dialog::alert_default(&format!("Failed to fetch file: {}", e));
-        log!("Failed to fetch file: {}", e);
-      },
-    }; // match
+        Ok(_) => log!("Successfully fetched file"),
+        Err(e) if gameimage::job::is_cancelled(&e) => log_status!("Cancelled"),
+        Err(e) =>
+        {
+          log_status!("Failed to fetch file: {}", e);
+          // The partially downloaded file is kept, a retry resumes from it
+          let msg = format!("Failed to fetch file: {}\nRetry? The download resumes where it stopped.", e);
+          if fltk::dialog::choice2_default(&msg, "No", "Yes", "") == Some(1) { continue; }
+        },
+      }; // match
+      break;
+    } // loop
     clone_tx.send_activate(common::Msg::DrawPlatform);
   });
 } // fn fetch_backend() }}}
@@ -214,6 +227,13 @@ fn platform_list(tx: Sender<common::Msg>, widget: &fltk::group::Flex) -> anyhow:
   col.fixed(&row_retroarch, dimm::height_button_wide());
   col.fixed(&row_pcsx2, dimm::height_button_wide());
   col.fixed(&row_wine, dimm::height_button_wide()*2 + dimm::border());
+  // Cancel the fetch in progress
+  col.add(&fltk::frame::Frame::default());
+  row!(row_cancel,
+    row_cancel.add(&fltk::frame::Frame::default());
+    row_cancel.fixed(&frame::common::cancel(), dimm::width_button_wide());
+  );
+  col.fixed(&row_cancel, dimm::height_button_wide());
   if ! vec_platforms.contains(&common::Platform::Linux)
   {
     shared::fltk::set_active(row_rpcs3, false);
@@ -21,8 +21,10 @@ use shared::{column,row,add,fixed,hover_blink};
 
 use crate::dimm;
 use crate::common;
+use crate::gameimage::job::Job;
 use crate::log;
 use crate::log_err;
+use crate::log_status;
 
 // struct Term {{{
 #[derive(Clone)]
@@ -30,6 +32,8 @@ pub struct Term
 {
   // Current process in the terminal
   opt_proc_thread : Option<(Arc<Mutex<std::process::Child>>, Arc<Mutex<Option<std::thread::JoinHandle<()>>>>)>,
+  // Job of the current process, shared by the clones of the terminal
+  opt_job : Option<Arc<Job>>,
   // Terminal gui
   pub term : SimpleTerminal,
   // Terminal message sender
@@ -44,9 +48,14 @@ impl Drop for Term
 {
 
   // drop() {{{
+  // The last clone of the terminal terminates the process if it is still running
   fn drop(&mut self)
   {
-    self.kill(self.opt_proc_thread.clone());
+    if let Some(job) = self.opt_job.take().and_then(Arc::into_inner)
+    && ! job.is_finished()
+    {
+      self.kill(self.opt_proc_thread.clone(), Some(job));
+    } // if
   } // drop() }}}
 
 } // impl
@@ -117,12 +126,17 @@ pub fn default() -> Term
   });
 
   // Return new term
-  Term{ term, opt_proc_thread: None, tx, btn_save, group: row }
+  Term{ term, opt_proc_thread: None, opt_job: None, tx, btn_save, group: row }
 } // new_with_id() }}}
 
 // kill() {{{
-fn kill(&mut self, opt_proc_thread : Option<(Arc<Mutex<std::process::Child>>, Arc<Mutex<Option<std::thread::JoinHandle<()>>>>)>)
+fn kill(&mut self
+  , opt_proc_thread : Option<(Arc<Mutex<std::process::Child>>, Arc<Mutex<Option<std::thread::JoinHandle<()>>>>)>
+  , opt_job : Option<Job>)
 {
+  // Terminate the children of the process
+  if let Some(job) = opt_job && ! job.is_finished() { job.cancel(); }
+
   let (proc, thread) = match opt_proc_thread
   {
     Some(e) => (e.0, e.1),
@@ -139,25 +153,25 @@ fn kill(&mut self, opt_proc_thread : Option<(Arc<Mutex<std::process::Child>>, Ar
   // Wait for thread
   match thread.lock()
   {
-    Ok(mut guard) => { let _ = guard.take().unwrap().join(); },
+    Ok(mut guard) => if let Some(handle) = guard.take() { let _ = handle.join(); },
     Err(e) => { log!("Could not lock arc with error: {}", e); return; }
   }; // match
 } // kill() }}}
 
 // pub fn dispatch() {{{
-pub fn dispatch<F>(&mut self, args : Vec<&str>, mut callback : F) -> anyhow::Result<Arc<Mutex<Child>>>
+pub fn dispatch<F>(&mut self, args : Vec<&str>, mut callback : F) -> anyhow::Result<(Arc<Mutex<Child>>, Job)>
   where F : FnMut(i32) + Send + 'static
 {
   let (cmd_base, cmd_args) = args.split_first().ok_or(ah!("No command to execute"))?;
 
-  let mut reader_cmd = Command::new(cmd_base)
+  let (mut reader_cmd, job) = Job::spawn(Command::new(cmd_base)
     .env_remove("LD_PRELOAD")
     .env("FIM_FIFO", "0")
     .args(cmd_args)
     .stdin(Stdio::piped())
     .stderr(Stdio::piped())
     .stdout(Stdio::piped())
-    .spawn()?;
+  )?;
 
   // Create arc reader for stdout
   let arc_stdout = Arc::new(Mutex::new(reader_cmd.stdout.take()));
@@ -167,13 +181,14 @@ pub fn dispatch<F>(&mut self, args : Vec<&str>, mut callback : F) -> anyhow::Res
   let arc_reader = Arc::new(Mutex::new(reader_cmd));
 
   // Kill existing process if any
-  self.kill(self.opt_proc_thread.clone());
+  self.kill(self.opt_proc_thread.clone(), self.opt_job.as_deref().cloned());
 
   // Setup callback
   let clone_arc_stdout = arc_stdout.clone();
   let clone_arc_stderr = arc_stderr.clone();
   let clone_arc_reader = arc_reader.clone();
   let clone_tx = self.tx.clone();
+  let clone_job = job.clone();
   let handle = std::thread::spawn(move ||
   {
     let (tx_log, rx_log) = mpsc::channel::<String>();
@@ -192,13 +207,20 @@ pub fn dispatch<F>(&mut self, args : Vec<&str>, mut callback : F) -> anyhow::Res
       && let Ok(status) = lock.wait()
       && let Some(code) = status.code() { code }
     else { 1 }; // else
+    clone_job.finish();
+    if clone_job.is_cancelled()
+    {
+      let _ = clone_tx.send("Cancelled".to_string());
+      log_status!("Cancelled");
+    } // if
     callback(code_return);
   });
 
   // Save proc & thread handle
   self.opt_proc_thread = Some((arc_reader.clone(), Arc::new(Mutex::new(Some(handle)))));
+  self.opt_job = Some(Arc::new(job.clone()));
 
-  Ok(arc_reader.clone())
+  Ok((arc_reader.clone(), job))
 } // dispatch() }}}
 
 // pub fn append() {{{
@@ -24,7 +24,7 @@ use shared::std::PathBufExt;
 use shared::{column,row,add,fixed};
 
 // check_version() {{{
-fn check_version() -> anyhow::Result<()>
+pub fn check_version() -> anyhow::Result<()>
 {
   let db_fetch = match db::fetch::read()
   {
@@ -1,69 +1,35 @@
-use std::sync::{mpsc,Arc,Mutex};
-
-use serde_json::json;
-
 use anyhow::anyhow as ah;
 
 use crate::log;
 use crate::common;
-use crate::gameimage;
+use crate::lib::ipc::Event;
+use crate::gameimage::protocol::{self, Backend};
 
 // fetch() {{{
-pub fn fetch<F>(platform: common::Platform, f: F) -> anyhow::Result<i32>
-  where F: FnMut(mpsc::Receiver<String>) + Send + 'static
+pub fn fetch<F>(platform: common::Platform, mut f: F) -> anyhow::Result<i32>
+  where F: FnMut(Event)
 {
-  let mut json_args = json!({});
-  json_args["op"] = "fetch".into();
-  json_args["fetch"]["op"] = "fetch".into();
-  json_args["fetch"]["platform"] = platform.as_str().into();
-  match gameimage::gameimage::gameimage_sync_ipc(vec![&json_args.to_string()], f)
+  match protocol::Cli.fetch(platform, &mut f)
   {
-    0 => { log!("Fetch on backend finished successfully"); Ok(0) },
-    rc => { return Err(ah!("Failed to execute fetch on backend with {}", rc)); },
+    Ok(()) => { log!("Fetch on backend finished successfully"); Ok(0) },
+    Err(e) => { return Err(e.context("Failed to execute fetch on backend")); },
   } // match
 } // fetch() }}}
 
 // installed() {{{
 pub fn installed() -> anyhow::Result<Vec<common::Platform>>
 {
-  let mut json_args = json!({});
-  json_args["op"] = "fetch".into();
-  json_args["fetch"]["op"] = "installed".into();
-  let arc_platforms : Arc<Mutex<Vec<common::Platform>>> = Arc::new(Mutex::new(vec![]));
-  let clone_arc_platforms = arc_platforms.clone();
-  gameimage::gameimage::gameimage_sync_ipc(vec![&json_args.to_string()], move |rx|
-  {
-    while let Ok(msg) = rx.recv()
-    {
-      match clone_arc_platforms.lock()
-      {
-        Ok(mut guard) => match common::Platform::from_str(&msg)
-        {
-          Some(platform) => guard.push(platform),
-          None => log!("Invalid platform: {}", msg),
-        }
-        Err(e) => log!("Could not lock installed vec: {}", e),
-      };
-    } // while
-  });
-
-  match arc_platforms.clone().lock()
-  {
-    Ok(platforms) => Ok(platforms.clone()),
-    Err(e) => return Err(ah!("Could not lock platforms: {}", e)),
-  }
+  protocol::Cli.fetch_installed().map_err(|e| ah!("Could not query installed platforms: {}", e))
 } // installed() }}}
 
 // sources() {{{
 pub fn sources() -> anyhow::Result<i32>
 {
-  let mut json_args = json!({});
-  json_args["op"] = "fetch".into();
-  json_args["fetch"]["op"] = "sources".into();
-  match gameimage::gameimage::gameimage_sync(vec![&json_args.to_string()])
+  match protocol::Cli.fetch_sources()
   {
-    0 => { log!("Fetch on backend finished successfully"); Ok(0)},
-    rc => { log!("Failed to execute fetch on backend with {}", rc); Ok(rc)},
+    Ok(()) => { log!("Fetch on backend finished successfully"); Ok(0)},
+    Err(protocol::Error::Exit{ code, .. }) => { log!("Failed to execute fetch on backend with {}", code); Ok(code)},
+    Err(e) => Err(ah!("Failed to execute fetch on backend: {}", e)),
   } // match
 } // sources() }}}
 
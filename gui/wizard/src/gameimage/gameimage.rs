@@ -5,6 +5,7 @@ use std::
 };
 
 use crate::lib;
+use crate::gameimage::{job,queue};
 use crate::common;
 use crate::log_err;
 use crate::log;
@@ -24,20 +25,28 @@ pub fn dir_build() -> anyhow::Result<()>
 } // fn: dir_build }}}
 
 // pub fn gameimage_async() {{{
-pub fn gameimage_async(args : Vec<&str>) -> anyhow::Result<(mpsc::Receiver<String>, mpsc::Receiver<i32>)>
+pub fn gameimage_async(args : Vec<&str>) -> anyhow::Result<(mpsc::Receiver<lib::ipc::Event>, mpsc::Receiver<i32>, job::Job)>
 {
   dir_build()?;
 
   let path_binary_gameimage = binary()?;
 
-  let mut handle = std::process::Command::new(&path_binary_gameimage)
+  // Open ipc before spawning the backend, each invocation owns its transport
+  let transport : Arc<dyn lib::ipc::Transport> = Arc::from(lib::ipc::open()?);
+
+  let (mut handle, job) = job::Job::spawn(std::process::Command::new(&path_binary_gameimage)
     .stdout(std::process::Stdio::piped())
     .stderr(std::process::Stdio::piped())
+    .envs(transport.env())
     .args(&args)
-    .spawn()?;
+  ).inspect_err(|_| transport.close())?;
 
   log!("Dispatch command: {:?} : {:?}", path_binary_gameimage, args);
 
+  // Commands started by a queued job report to it
+  let opt_id_queue = queue::current();
+  if let Some(id) = opt_id_queue { queue::attach(id, job.clone()); }
+
   // Create arc reader for stdout
   let arc_stdout = Arc::new(Mutex::new(handle.stdout.take()));
   let arc_stderr = Arc::new(Mutex::new(handle.stderr.take()));
@@ -47,6 +56,8 @@ pub fn gameimage_async(args : Vec<&str>) -> anyhow::Result<(mpsc::Receiver<Strin
   let stdout = arc_stdout.lock().unwrap().take();
   let stderr = arc_stderr.lock().unwrap().take();
   let (tx_code, rx_code) = mpsc::channel();
+  let transport_close = transport.clone();
+  let clone_job = job.clone();
   std::thread::spawn(move ||
   {
     let (tx_log, rx_log) = mpsc::channel();
@@ -58,6 +69,7 @@ pub fn gameimage_async(args : Vec<&str>) -> anyhow::Result<(mpsc::Receiver<Strin
       for line in msg.split('\n')
       {
         log!("{}", line);
+        if let Some(id) = opt_id_queue { queue::output(id, line); }
       }
     } // while
 
@@ -65,71 +77,45 @@ pub fn gameimage_async(args : Vec<&str>) -> anyhow::Result<(mpsc::Receiver<Strin
     log_err!(handle_stderr.join());
 
     // Close IPC
-    lib::ipc::Ipc::close();
+    transport_close.close();
 
     // Send exit code
     if let Ok(mut guard) = arc_handle.lock()
     && let Ok(status) = guard.wait()
     && let Some(code) = status.code()
     {
+      if let Some(id) = opt_id_queue { queue::exit(id, code); }
       log_err!(tx_code.send(code));
     }
     else
     {
       log_err!(tx_code.send(1));
     } // else
+
+    clone_job.finish();
   });
 
   let (tx_ipc, rx_ipc) = mpsc::channel();
   // Write from ipc to channel
   std::thread::spawn(move ||
   {
-    // Open ipc
-    let ipc = match lib::ipc::Ipc::new()
-    {
-      Ok(ipc) => ipc,
-      Err(e) => { log!("Could not create ipc instance: {}", e); return; },
-    }; // match
-    // Write received message to transmitter
-    while let Ok(msg) = ipc.recv()
+    // Decode received frames and write events to transmitter
+    let mut decoder = lib::ipc::Decoder::default();
+    while let Ok(msg) = transport.recv()
     {
-      if let Err(e) = tx_ipc.send(msg)
+      let event = match decoder.push(msg)
+      {
+        Some(event) => event,
+        None => continue,
+      }; // match
+      if let Err(e) = tx_ipc.send(event)
       {
         log!("Could not send ipc retrieved message: {}", e);
       } // if
     } // while
   });
 
-  Ok((rx_ipc, rx_code))
+  Ok((rx_ipc, rx_code, job))
 } // fn: gameimage_async }}}
 
-// pub fn gameimage_sync() {{{
-pub fn gameimage_sync(args : Vec<&str>) -> i32
-{
-  let (_, rx_code) = match gameimage_async(args)
-  {
-    Ok((rx_ipc, rx_code)) => (rx_ipc, rx_code),
-    Err(e) => { log!("Could not start backend: {}", e); return 1; },
-  }; // if
-
-  rx_code.recv().unwrap_or(1)
-} // fn: gameimage_sync }}}
-
-// pub fn gameimage_sync_ipc() {{{
-pub fn gameimage_sync_ipc<F>(args : Vec<&str>, mut f: F) -> i32
-  where F: FnMut(mpsc::Receiver<String>) + Send + 'static
-{
-  let (rx_ipc, rx_code) = match gameimage_async(args)
-  {
-    Ok((rx_ipc, rx_code)) => (rx_ipc, rx_code),
-    Err(e) => { log!("Could not start backend: {}", e); return 1; },
-  }; // if
-
-  // Receive messages
-  f(rx_ipc);
-
-  // Recover exit code
-  rx_code.recv().unwrap_or(1)
-} // fn: gameimage_sync_ipc }}}
-
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
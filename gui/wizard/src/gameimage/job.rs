@@ -0,0 +1,152 @@
+use std::
+{
+  process::{Child,Command},
+  os::unix::process::CommandExt,
+  sync::{Arc,Mutex,LazyLock,atomic::{AtomicBool,AtomicU64,Ordering}},
+};
+
+use anyhow::anyhow as ah;
+
+use crate::log;
+use crate::common;
+use crate::gameimage::protocol;
+
+// Time the processes of a cancelled job have to exit before they are killed
+const SECS_GRACE: u64 = 3;
+
+// Jobs which process did not exit yet
+static JOBS: LazyLock<Mutex<Vec<Job>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+// pub struct Job {{{
+// Handle to a spawned process, the process leads its own process group so its children are
+// terminated with it
+#[derive(Clone)]
+pub struct Job
+{
+  pgid: i32,
+  tag: String,
+  cancelled: Arc<AtomicBool>,
+} // struct Job }}}
+
+impl Job
+{
+
+// pub fn spawn() {{{
+// Spawns the command in a new process group and registers it as a running job
+pub fn spawn(command: &mut Command) -> anyhow::Result<(Child, Job)>
+{
+  static COUNTER: AtomicU64 = AtomicU64::new(0);
+  // Daemons such as wineserver leave the process group, they are found by the tag in their
+  // environment
+  let tag = format!("{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed));
+  let child = command
+    .process_group(0)
+    .env("GIMG_JOB", &tag)
+    .spawn()?;
+  let job = Job{ pgid: child.id() as i32, tag, cancelled: Arc::new(AtomicBool::new(false)) };
+  JOBS.lock().map_err(|e| ah!("Could not lock jobs: {}", e))?.push(job.clone());
+  Ok((child, job))
+} // fn: spawn }}}
+
+// pub fn cancel() {{{
+// Asks the processes of the job to terminate, the remaining ones are killed after a grace period
+pub fn cancel(&self)
+{
+  if self.cancelled.swap(true, Ordering::SeqCst) { return; }
+  log!("Cancelling job {}", self.tag);
+  self.signal(libc::SIGTERM);
+  let job = self.clone();
+  std::thread::spawn(move ||
+  {
+    std::thread::sleep(std::time::Duration::from_secs(SECS_GRACE));
+    job.signal(libc::SIGKILL);
+  });
+} // fn: cancel }}}
+
+// pub fn is_cancelled() {{{
+pub fn is_cancelled(&self) -> bool
+{
+  self.cancelled.load(Ordering::SeqCst)
+} // fn: is_cancelled }}}
+
+// pub fn finish() {{{
+// Unregisters the job, called after the process was waited for
+pub fn finish(&self)
+{
+  match JOBS.lock()
+  {
+    Ok(mut jobs) => jobs.retain(|e| e.tag != self.tag),
+    Err(e) => log!("Could not lock jobs: {}", e),
+  } // match
+} // fn: finish }}}
+
+// pub fn is_finished() {{{
+// Checks if the process of the job was waited for
+pub fn is_finished(&self) -> bool
+{
+  ! self.is_running()
+} // fn: is_finished }}}
+
+// fn is_running() {{{
+fn is_running(&self) -> bool
+{
+  JOBS.lock().map(|jobs| jobs.iter().any(|e| e.tag == self.tag)).unwrap_or(false)
+} // fn: is_running }}}
+
+// fn signal() {{{
+// Sends the signal to the process group and to the processes tagged with the job
+fn signal(&self, signal: i32)
+{
+  // The process group id could be reused once the job is finished, the tag is unique
+  if self.is_running()
+  {
+    unsafe { libc::killpg(self.pgid, signal); }
+  } // if
+  for pid in self.tagged()
+  {
+    unsafe { libc::kill(pid, signal); }
+  } // for
+} // fn: signal }}}
+
+// fn tagged() {{{
+// Processes with the tag of the job in their environment
+fn tagged(&self) -> Vec<i32>
+{
+  let entry = format!("GIMG_JOB={}", self.tag);
+  let dir = match std::fs::read_dir("/proc")
+  {
+    Ok(dir) => dir,
+    Err(e) => { log!("Could not list processes: {}", e); return vec![]; },
+  }; // match
+  dir.filter_map(|e| e.ok())
+    .filter_map(|e| e.file_name().to_str().and_then(|e| e.parse::<i32>().ok()))
+    .filter(|pid| std::fs::read(format!("/proc/{}/environ", pid))
+      .map(|environ| environ.split(|c| *c == 0).any(|e| e == entry.as_bytes()))
+      .unwrap_or(false)
+    )
+    .collect()
+} // fn: tagged }}}
+
+} // impl Job
+
+// pub fn cancel_all() {{{
+// Cancels all running jobs
+pub fn cancel_all()
+{
+  let jobs = match JOBS.lock()
+  {
+    Ok(jobs) => jobs.clone(),
+    Err(e) => { log!("Could not lock jobs: {}", e); return; },
+  }; // match
+  if jobs.is_empty() { log!("No job to cancel"); }
+  jobs.iter().for_each(Job::cancel);
+} // fn: cancel_all }}}
+
+// pub fn is_cancelled() {{{
+// Checks if the error comes from a cancelled backend command
+pub fn is_cancelled(e: &anyhow::Error) -> bool
+{
+  matches!(e.downcast_ref::<protocol::Error>(), Some(protocol::Error::Cancelled{..}))
+} // fn: is_cancelled }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
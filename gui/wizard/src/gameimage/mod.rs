@@ -8,3 +8,8 @@ pub mod select;
 pub mod init;
 pub mod test;
 pub mod project;
+pub mod protocol;
+pub mod job;
+pub mod queue;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,455 @@
+use std::
+{
+  path::{Path,PathBuf},
+  sync::{mpsc,OnceLock},
+};
+
+use anyhow::anyhow as ah;
+use serde::{Serialize, Deserialize};
+
+use shared::std::PathBufExt;
+
+use crate::log;
+use crate::common;
+use crate::lib::ipc::Event;
+use crate::gameimage::gameimage;
+use crate::gameimage::job::Job;
+
+// Version of the json protocol spoken with gameimage-cli, must match PROTOCOL_VERSION in the
+// backend parser
+pub const VERSION: u32 = 3;
+
+// pub enum SubOp {{{
+// Target of the install/select/search operations
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubOp
+{
+  Icon,
+  Rom,
+  Linux,
+  Core,
+  Bios,
+  Keys,
+  Config,
+  Data,
+  Gui,
+  Wine,
+  Winetricks,
+  Dxvk,
+  Vkd3d,
+} // }}}
+
+// impl SubOp {{{
+impl SubOp
+{
+  pub fn from_str(src : &str) -> Result<SubOp, Error>
+  {
+    serde_json::from_value(serde_json::Value::String(src.to_lowercase()))
+      .map_err(|_| Error::Invalid(format!("Unknown operation '{}'", src)))
+  } // fn: from_str
+} // impl SubOp }}}
+
+// pub enum Init {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Init
+{
+  Build { path_dir_build: String },
+  Project { name: String, platform: common::Platform },
+} // }}}
+
+// pub enum Fetch {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Fetch
+{
+  Sources,
+  Fetch { platform: common::Platform },
+  Installed,
+  Sha { platform: common::Platform },
+} // }}}
+
+// pub enum Project {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Project
+{
+  Set { name: String },
+  Del { name: String },
+} // }}}
+
+// pub enum InstallOp {{{
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallOp
+{
+  Install,
+  Remote,
+  Remove,
+} // }}}
+
+// pub struct Install {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Install
+{
+  pub op: InstallOp,
+  pub sub_op: SubOp,
+  pub args: Vec<String>,
+} // }}}
+
+// pub enum Search {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Search
+{
+  Local { query: SubOp },
+  Remote { query: SubOp },
+} // }}}
+
+// pub struct Select {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Select
+{
+  pub op: SubOp,
+  pub path_file_target: String,
+} // }}}
+
+// pub enum Desktop {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Desktop
+{
+  Icon { path_file_icon: String },
+  // Items is a comma separated list of desktop integration items
+  Setup { name: String, items: String },
+} // }}}
+
+// pub struct Package {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Package
+{
+  pub name: String,
+  pub projects: Vec<String>,
+} // }}}
+
+// pub enum Command {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Command
+{
+  Version,
+  Init { init: Init },
+  Fetch { fetch: Fetch },
+  Project { project: Project },
+  Install { install: Install },
+  Compress,
+  Search { search: Search },
+  Select { select: Select },
+  Test,
+  Desktop { desktop: Desktop },
+  Package { package: Package },
+} // }}}
+
+// impl Command {{{
+impl Command
+{
+  pub fn name(&self) -> &'static str
+  {
+    match self
+    {
+      Command::Version      => "version",
+      Command::Init{..}     => "init",
+      Command::Fetch{..}    => "fetch",
+      Command::Project{..}  => "project",
+      Command::Install{..}  => "install",
+      Command::Compress     => "compress",
+      Command::Search{..}   => "search",
+      Command::Select{..}   => "select",
+      Command::Test         => "test",
+      Command::Desktop{..}  => "desktop",
+      Command::Package{..}  => "package",
+    } // match
+  } // fn: name
+} // impl Command }}}
+
+// pub struct Request {{{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Request
+{
+  pub version: u32,
+  #[serde(flatten)]
+  pub command: Command,
+} // }}}
+
+// impl Request {{{
+impl Request
+{
+  pub fn new(command: Command) -> Request
+  {
+    Request { version: VERSION, command }
+  } // fn: new
+
+  pub fn to_json(&self) -> String
+  {
+    // Serialization only fails for non-string map keys, which the protocol does not have
+    serde_json::to_string(self).unwrap_or_default()
+  } // fn: to_json
+} // impl Request }}}
+
+// impl From<Command> for Request {{{
+impl From<Command> for Request
+{
+  fn from(command: Command) -> Request
+  {
+    Request::new(command)
+  } // fn: from
+} // }}}
+
+// pub enum Error {{{
+#[derive(Debug, Clone)]
+pub enum Error
+{
+  // Could not start the backend or retrieve its exit code
+  Spawn(String),
+  // Backend exited with a non-zero code, with the message of the last error event if any
+  Exit { op: &'static str, code: i32, message: Option<String> },
+  // Backend speaks another protocol version, or none at all
+  Version { expected: u32, found: Option<u32> },
+  // Invalid argument or malformed backend message
+  Invalid(String),
+  // Backend was terminated by the user
+  Cancelled { op: &'static str },
+} // }}}
+
+// impl std::fmt::Display for Error {{{
+impl std::fmt::Display for Error
+{
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    match self
+    {
+      Error::Spawn(e) => write!(f, "Could not start backend: {}", e),
+      Error::Exit{op, code, message: Some(message)} =>
+        write!(f, "Backend command '{}' exited with code {}: {}", op, code, message),
+      Error::Exit{op, code, message: None} => write!(f, "Backend command '{}' exited with code {}", op, code),
+      Error::Version{expected, found: Some(found)} =>
+        write!(f, "Backend protocol version is {}, expected {}", found, expected),
+      Error::Version{expected, found: None} =>
+        write!(f, "Backend did not report a protocol version, expected {}", expected),
+      Error::Invalid(e) => write!(f, "{}", e),
+      Error::Cancelled{op} => write!(f, "Cancelled backend command '{}'", op),
+    } // match
+  } // fn: fmt
+} // }}}
+
+impl std::error::Error for Error {}
+
+// impl Error {{{
+impl Error
+{
+  // fn context() {{{
+  // Prefixes the message of the error, cancellations are kept as is to be told apart with
+  // job::is_cancelled
+  pub fn context(self, msg: &str) -> anyhow::Error
+  {
+    match self
+    {
+      Error::Cancelled{..} => anyhow::Error::new(self),
+      e => ah!("{}: {}", msg, e),
+    } // match
+  } // fn: context }}}
+} // impl Error }}}
+
+// pub trait Backend {{{
+pub trait Backend
+{
+  // Starts the request, returns the receivers for the ipc messages and the exit code, and the
+  // handle to cancel it
+  fn dispatch(&self, request: &Request) -> Result<(mpsc::Receiver<Event>, mpsc::Receiver<i32>, Job), Error>;
+
+  // fn run() {{{
+  fn run(&self, command: Command) -> Result<(), Error>
+  {
+    self.run_ipc(command, &mut |_| {})
+  } // fn: run }}}
+
+  // fn run_ipc() {{{
+  // Calls f for each event received until the backend exits
+  fn run_ipc(&self, command: Command, f: &mut dyn FnMut(Event)) -> Result<(), Error>
+  {
+    let (rx_ipc, rx_code, job) = self.dispatch(&Request::new(command.clone()))?;
+    let mut message = None;
+    while let Ok(event) = rx_ipc.recv()
+    {
+      match &event
+      {
+        Event::Log{ line } => log!("{}", line),
+        Event::Warning{ message } => log!("Warning: {}", message),
+        Event::Error{ code, message: msg } => { log!("Error {}: {}", code, msg); message = Some(msg.clone()); },
+        _ => (),
+      } // match
+      f(event);
+    } // while
+    match rx_code.recv()
+    {
+      Ok(_) if job.is_cancelled() => Err(Error::Cancelled{ op: command.name() }),
+      Ok(0) => Ok(()),
+      Ok(code) => Err(Error::Exit{ op: command.name(), code, message }),
+      Err(e) => Err(Error::Spawn(format!("Could not retrieve exit code: {}", e))),
+    } // match
+  } // fn: run_ipc }}}
+
+  // fn collect() {{{
+  // Runs the command and returns the received result items
+  fn collect(&self, command: Command) -> Result<Vec<String>, Error>
+  {
+    let mut vec = vec![];
+    self.run_ipc(command, &mut |event| if let Event::Item{ value } = event { vec.push(value); })?;
+    Ok(vec)
+  } // fn: collect }}}
+
+  // fn version() {{{
+  fn version(&self) -> Result<u32, Error>
+  {
+    let found = self.collect(Command::Version)
+      .ok()
+      .and_then(|msgs| msgs.first().and_then(|e| e.trim().parse::<u32>().ok()));
+    match found
+    {
+      Some(VERSION) => Ok(VERSION),
+      found => Err(Error::Version{ expected: VERSION, found }),
+    } // match
+  } // fn: version }}}
+
+  // fn init_build() {{{
+  fn init_build(&self, path_dir_build: &Path) -> Result<(), Error>
+  {
+    self.run(Command::Init{ init: Init::Build{ path_dir_build: path_dir_build.to_path_buf().string() } })
+  } // fn: init_build }}}
+
+  // fn init_project() {{{
+  fn init_project(&self, name: &str, platform: common::Platform) -> Result<(), Error>
+  {
+    self.run(Command::Init{ init: Init::Project{ name: name.to_string(), platform } })
+  } // fn: init_project }}}
+
+  // fn fetch() {{{
+  fn fetch(&self, platform: common::Platform, f: &mut dyn FnMut(Event)) -> Result<(), Error>
+  {
+    self.run_ipc(Command::Fetch{ fetch: Fetch::Fetch{ platform } }, f)
+  } // fn: fetch }}}
+
+  // fn fetch_installed() {{{
+  fn fetch_installed(&self) -> Result<Vec<common::Platform>, Error>
+  {
+    Ok(self.collect(Command::Fetch{ fetch: Fetch::Installed })?
+      .into_iter()
+      .filter_map(|msg| match common::Platform::from_str(&msg)
+      {
+        Some(platform) => Some(platform),
+        None => { log!("Invalid platform: {}", msg); None },
+      })
+      .collect())
+  } // fn: fetch_installed }}}
+
+  // fn fetch_sources() {{{
+  fn fetch_sources(&self) -> Result<(), Error>
+  {
+    self.run(Command::Fetch{ fetch: Fetch::Sources })
+  } // fn: fetch_sources }}}
+
+  // fn install() {{{
+  fn install(&self, op: InstallOp, sub_op: SubOp, args: Vec<String>) -> Result<(), Error>
+  {
+    self.run(Command::Install{ install: Install{ op, sub_op, args } })
+  } // fn: install }}}
+
+  // fn select() {{{
+  fn select(&self, op: SubOp, path_file_target: &Path) -> Result<(), Error>
+  {
+    self.run(Command::Select{ select: Select{ op, path_file_target: path_file_target.to_path_buf().string() } })
+  } // fn: select }}}
+
+  // fn search() {{{
+  fn search(&self, query: SubOp, use_remote: bool) -> Result<Vec<PathBuf>, Error>
+  {
+    let search = if use_remote { Search::Remote{ query } } else { Search::Local{ query } };
+    Ok(self.collect(Command::Search{ search })?.into_iter().map(PathBuf::from).collect())
+  } // fn: search }}}
+
+  // fn project_set() {{{
+  fn project_set(&self, name: &str) -> Result<(), Error>
+  {
+    self.run(Command::Project{ project: Project::Set{ name: name.to_string() } })
+  } // fn: project_set }}}
+
+  // fn project_del() {{{
+  fn project_del(&self, name: &str) -> Result<(), Error>
+  {
+    self.run(Command::Project{ project: Project::Del{ name: name.to_string() } })
+  } // fn: project_del }}}
+
+  // fn package() {{{
+  fn package(&self, name: &str, projects: Vec<String>) -> Result<(), Error>
+  {
+    self.run(Command::Package{ package: Package{ name: name.to_string(), projects } })
+  } // fn: package }}}
+
+  // fn desktop_setup() {{{
+  fn desktop_setup(&self, name: &str, items: &str) -> Result<(), Error>
+  {
+    self.run(Command::Desktop{ desktop: Desktop::Setup{ name: name.to_string(), items: items.to_string() } })
+  } // fn: desktop_setup }}}
+
+  // fn desktop_icon() {{{
+  fn desktop_icon(&self, path_file_icon: &Path) -> Result<(), Error>
+  {
+    self.run(Command::Desktop{ desktop: Desktop::Icon{ path_file_icon: path_file_icon.to_path_buf().string() } })
+  } // fn: desktop_icon }}}
+
+  // fn test() {{{
+  fn test(&self) -> Result<(), Error>
+  {
+    self.run(Command::Test)
+  } // fn: test }}}
+
+  // fn compress() {{{
+  fn compress(&self) -> Result<(), Error>
+  {
+    self.run(Command::Compress)
+  } // fn: compress }}}
+} // trait Backend }}}
+
+// pub struct Cli {{{
+// Backend that spawns gameimage-cli for each request
+pub struct Cli;
+// }}}
+
+// impl Cli {{{
+impl Cli
+{
+  // fn check() {{{
+  // Queries the backend version once per session
+  pub fn check(&self) -> Result<(), Error>
+  {
+    static CHECK: OnceLock<Result<(), Error>> = OnceLock::new();
+    CHECK.get_or_init(|| self.version().map(|_| ())).clone()
+  } // fn: check }}}
+} // impl Cli }}}
+
+// impl Backend for Cli {{{
+impl Backend for Cli
+{
+  fn dispatch(&self, request: &Request) -> Result<(mpsc::Receiver<Event>, mpsc::Receiver<i32>, Job), Error>
+  {
+    // Refuse to talk to a mismatched backend
+    if ! matches!(request.command, Command::Version)
+    {
+      self.check()?;
+    } // if
+    gameimage::gameimage_async(vec![&request.to_json()]).map_err(|e| Error::Spawn(e.to_string()))
+  } // fn: dispatch
+} // impl Backend for Cli }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,364 @@
+use std::
+{
+  cell::Cell,
+  sync::{Arc,Mutex,LazyLock,atomic::{AtomicBool,AtomicU64,Ordering}},
+};
+
+use crate::log;
+use crate::common;
+use crate::gameimage::job;
+
+// Operation of a queued job, it is called again when the job is retried
+type Action = Arc<dyn Fn() -> anyhow::Result<()> + Send + Sync>;
+// Called with the index of the choice the user picked
+type Choose = Arc<dyn Fn(usize) -> anyhow::Result<()> + Send + Sync>;
+
+// pub enum State {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State
+{
+  Queued,
+  Running,
+  Done,
+  Failed,
+  Cancelled,
+} // }}}
+
+// impl State {{{
+impl State
+{
+  pub fn as_str(&self) -> &'static str
+  {
+    match self
+    {
+      State::Queued    => "Queued",
+      State::Running   => "Running",
+      State::Done      => "Done",
+      State::Failed    => "Failed",
+      State::Cancelled => "Cancelled",
+    } // match
+  } // fn: as_str
+
+  pub fn is_pending(&self) -> bool
+  {
+    matches!(self, State::Queued | State::Running)
+  } // fn: is_pending
+} // impl State }}}
+
+// pub struct Prompt {{{
+// Choices a running job asks the user for, e.g., the disc to insert when the installer asks for it
+#[derive(Clone)]
+pub struct Prompt
+{
+  pub label: String,
+  pub choices: Vec<String>,
+  pub selected: usize,
+  choose: Choose,
+} // struct Prompt }}}
+
+// pub struct Entry {{{
+#[derive(Clone)]
+pub struct Entry
+{
+  pub id: u64,
+  pub label: String,
+  pub state: State,
+  // Output of the backend processes of the job
+  pub output: Vec<String>,
+  // Exit code of the last backend process of the job
+  pub code: Option<i32>,
+  // Choices of the job while it runs
+  pub prompt: Option<Prompt>,
+  // Frames which display the data changed by the job, they are drawn again once it finishes
+  frames: Vec<common::Msg>,
+  action: Action,
+  // Backend processes started by the job
+  jobs: Vec<job::Job>,
+  is_cancelled: bool,
+} // struct Entry }}}
+
+// struct Queue {{{
+struct Queue
+{
+  entries: Vec<Entry>,
+  // Frames of the jobs which finished since the last call to take_finished
+  finished: Vec<common::Msg>,
+  is_working: bool,
+} // struct Queue }}}
+
+// Set when the queue changed since the last call to take_changed
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+static QUEUE: LazyLock<Mutex<Queue>> = LazyLock::new(||
+{
+  Mutex::new(Queue{ entries: vec![], finished: vec![], is_working: false })
+});
+
+thread_local!
+{
+  // Job run by the current thread, backend processes spawned by it are attached to the job
+  static CURRENT: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+// fn with() {{{
+// Runs f with the locked queue, a poisoned lock is recovered since the entries stay consistent
+fn with<T>(f: impl FnOnce(&mut Queue) -> T) -> T
+{
+  let mut queue = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+  f(&mut queue)
+} // fn: with }}}
+
+// fn notify() {{{
+// The main loop polls the changes to refresh the job panel, output lines arrive too often to send
+// a message for each one
+fn notify()
+{
+  CHANGED.store(true, Ordering::SeqCst);
+} // fn: notify }}}
+
+// fn work() {{{
+// Runs the queued jobs one at the time, the worker exits once the queue is empty
+fn work()
+{
+  loop
+  {
+    let next = with(|queue|
+    {
+      match queue.entries.iter_mut().find(|e| e.state == State::Queued)
+      {
+        Some(entry) => { entry.state = State::Running; Some((entry.id, entry.action.clone())) },
+        None => { queue.is_working = false; None },
+      } // match
+    });
+    let (id, action) = match next
+    {
+      Some(next) => next,
+      None => break,
+    }; // match
+    notify();
+    CURRENT.with(|e| e.set(Some(id)));
+    let result = action();
+    CURRENT.with(|e| e.set(None));
+    with(|queue|
+    {
+      let entry = match queue.entries.iter_mut().find(|e| e.id == id)
+      {
+        Some(entry) => entry,
+        None => return,
+      }; // match
+      entry.jobs.clear();
+      entry.prompt = None;
+      entry.state = match result
+      {
+        Ok(()) => State::Done,
+        Err(_) if entry.is_cancelled => State::Cancelled,
+        Err(e) if job::is_cancelled(&e) => State::Cancelled,
+        Err(e) => { entry.output.push(format!("Error: {}", e)); State::Failed },
+      }; // match
+      log!("Job '{}' finished with state '{}'", entry.label, entry.state.as_str());
+      let frames = entry.frames.clone();
+      queue.finished.extend(frames);
+    });
+    notify();
+  } // loop
+} // fn: work }}}
+
+// fn start() {{{
+fn start()
+{
+  let is_working = with(|queue| std::mem::replace(&mut queue.is_working, true));
+  if ! is_working
+  {
+    std::thread::spawn(work);
+  } // if
+} // fn: start }}}
+
+// pub fn push() {{{
+// Enqueues an operation, frames are the ones which display the data it changes
+pub fn push<F>(label: &str, frames: Vec<common::Msg>, f: F) -> u64
+  where F: Fn() -> anyhow::Result<()> + Send + Sync + 'static
+{
+  static COUNTER: AtomicU64 = AtomicU64::new(1);
+  let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+  with(|queue|
+  {
+    queue.entries.push(Entry
+    {
+      id,
+      label: label.to_string(),
+      state: State::Queued,
+      output: vec![],
+      code: None,
+      prompt: None,
+      frames,
+      action: Arc::new(f),
+      jobs: vec![],
+      is_cancelled: false,
+    });
+  });
+  log!("Queued job '{}'", label);
+  start();
+  notify();
+  id
+} // fn: push }}}
+
+// pub fn retry() {{{
+// Enqueues a failed or cancelled job again
+pub fn retry(id: u64)
+{
+  let is_retried = with(|queue|
+  {
+    match queue.entries.iter_mut().find(|e| e.id == id && matches!(e.state, State::Failed | State::Cancelled))
+    {
+      Some(entry) =>
+      {
+        entry.state = State::Queued;
+        entry.output.clear();
+        entry.code = None;
+        entry.is_cancelled = false;
+        true
+      },
+      None => false,
+    } // match
+  });
+  if is_retried { start(); notify(); }
+} // fn: retry }}}
+
+// pub fn cancel() {{{
+// Drops a queued job or terminates the backend processes of a running one
+pub fn cancel(id: u64)
+{
+  let jobs = with(|queue|
+  {
+    match queue.entries.iter_mut().find(|e| e.id == id)
+    {
+      Some(entry) if entry.state == State::Queued => { entry.state = State::Cancelled; vec![] },
+      Some(entry) if entry.state == State::Running => { entry.is_cancelled = true; entry.jobs.clone() },
+      _ => vec![],
+    } // match
+  });
+  jobs.iter().for_each(job::Job::cancel);
+  notify();
+} // fn: cancel }}}
+
+// pub fn clear() {{{
+// Removes the jobs which finished successfully or were cancelled
+pub fn clear()
+{
+  with(|queue| queue.entries.retain(|e| ! matches!(e.state, State::Done | State::Cancelled)));
+  notify();
+} // fn: clear }}}
+
+// pub fn entries() {{{
+pub fn entries() -> Vec<Entry>
+{
+  with(|queue| queue.entries.clone())
+} // fn: entries }}}
+
+// pub fn current() {{{
+// Job run by the calling thread, if any
+pub fn current() -> Option<u64>
+{
+  CURRENT.with(|e| e.get())
+} // fn: current }}}
+
+// pub fn attach() {{{
+// Attaches a backend process to the job, it is terminated when the job is cancelled
+pub fn attach(id: u64, backend: job::Job)
+{
+  let is_cancelled = with(|queue|
+  {
+    match queue.entries.iter_mut().find(|e| e.id == id)
+    {
+      Some(entry) => { entry.jobs.push(backend.clone()); entry.is_cancelled },
+      None => false,
+    } // match
+  });
+  // The job was cancelled between two backend processes
+  if is_cancelled { backend.cancel(); }
+} // fn: attach }}}
+
+// pub fn output() {{{
+pub fn output(id: u64, line: &str)
+{
+  with(|queue| if let Some(entry) = queue.entries.iter_mut().find(|e| e.id == id)
+  {
+    entry.output.push(line.to_string());
+  });
+  notify();
+} // fn: output }}}
+
+// pub fn exit() {{{
+pub fn exit(id: u64, code: i32)
+{
+  with(|queue| if let Some(entry) = queue.entries.iter_mut().find(|e| e.id == id)
+  {
+    entry.code = Some(code);
+  });
+} // fn: exit }}}
+
+// pub fn prompt() {{{
+// Shows the choices on the job until it finishes, the selected choice is the current one
+pub fn prompt<F>(id: u64, label: &str, choices: Vec<String>, selected: usize, f: F)
+  where F: Fn(usize) -> anyhow::Result<()> + Send + Sync + 'static
+{
+  with(|queue| if let Some(entry) = queue.entries.iter_mut().find(|e| e.id == id)
+  {
+    entry.prompt = Some(Prompt{ label: label.to_string(), choices, selected, choose: Arc::new(f) });
+  });
+  notify();
+} // fn: prompt }}}
+
+// pub fn choose() {{{
+// Picks a choice of the prompt of the job, it is applied in the background since the job waits
+// for it
+pub fn choose(id: u64, index: usize)
+{
+  let choose = with(|queue|
+  {
+    queue.entries.iter_mut()
+      .find(|e| e.id == id)
+      .and_then(|e| e.prompt.as_mut())
+      .filter(|e| index < e.choices.len())
+      .map(|e| { e.selected = index; e.choose.clone() })
+  });
+  let choose = match choose
+  {
+    Some(choose) => choose,
+    None => { log!("Job {} has no choice {}", id, index); return; },
+  }; // match
+  notify();
+  std::thread::spawn(move ||
+  {
+    if let Err(e) = choose(index) { output(id, &format!("Error: {}", e)); } // if
+  });
+} // fn: choose }}}
+
+// pub fn is_blocked() {{{
+// Checks if the frame has to wait for the queued jobs, e.g., the package must not be compressed
+// while files are installed into it
+pub fn is_blocked(frame: common::Msg) -> bool
+{
+  frame.depends_on_jobs() && with(|queue| queue.entries.iter().any(|e| e.state.is_pending()))
+} // fn: is_blocked }}}
+
+// pub fn count_pending() {{{
+pub fn count_pending() -> usize
+{
+  with(|queue| queue.entries.iter().filter(|e| e.state.is_pending()).count())
+} // fn: count_pending }}}
+
+// pub fn take_changed() {{{
+pub fn take_changed() -> bool
+{
+  CHANGED.swap(false, Ordering::SeqCst)
+} // fn: take_changed }}}
+
+// pub fn take_finished() {{{
+// Frames of the jobs which finished since the last call
+pub fn take_finished() -> Vec<common::Msg>
+{
+  with(|queue| std::mem::take(&mut queue.finished))
+} // fn: take_finished }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -1,19 +1,15 @@
 use anyhow::anyhow as ah;
 
-use serde_json::json;
-
 use crate::log;
 use crate::common;
-use crate::gameimage::gameimage;
+use crate::gameimage::protocol::{self, Backend};
 
 // pub fn test() {{{
 pub fn test() -> anyhow::Result<()>
 {
-  let mut json_args = json!({});
-  json_args["op"] = "test".into();
-  match gameimage::gameimage_sync(vec![&json_args.to_string()])
+  match protocol::Cli.test()
   {
-    0 => { log!("test returned successfully"); return Ok(()) },
-    ret => return Err(ah!("test returned with error: {}", ret)),
+    Ok(()) => { log!("test returned successfully"); return Ok(()) },
+    Err(e) => return Err(ah!("test returned with error: {}", e)),
   } // match
 } // fn: test }}}
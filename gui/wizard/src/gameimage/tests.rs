@@ -0,0 +1,300 @@
+// Wrappers of the backend commands against the gameimage-cli-stub test double
+
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::common::Platform;
+use crate::lib::ipc::{Event,Download};
+use crate::testing::Stub;
+use crate::gameimage::{self,job,queue,protocol::{self,Backend}};
+
+// fn item() {{{
+fn item(value: &str) -> Event
+{
+  Event::Item{ value: value.to_string() }
+} // fn: item }}}
+
+#[test]
+fn init_build_and_project()
+{
+  let stub = Stub::new();
+  gameimage::init::build(stub.dir.path.clone()).unwrap();
+  gameimage::init::project("game".into(), "wine".into()).unwrap();
+  assert!(gameimage::init::project("game".into(), "dos".into()).is_err());
+  assert_eq!(stub.requests(), vec![
+    json!({ "version": protocol::VERSION, "op": "init", "init": { "op": "build", "path_dir_build": stub.dir.path } }),
+    json!({ "version": protocol::VERSION, "op": "init", "init": { "op": "project", "name": "game", "platform": "wine" } }),
+  ]);
+}
+
+#[test]
+fn fetch_forwards_events()
+{
+  let stub = Stub::new();
+  let download = Download{ name: "wine".into(), bytes: 50, total: 100, rate: 10.0, eta: Some(5) };
+  stub.reply("fetch.fetch", vec![Event::Download(download.clone()), Event::Progress{ value: 100.0 }], 0);
+  let mut events = vec![];
+  assert_eq!(gameimage::fetch::fetch(Platform::Wine, |e| events.push(e)).unwrap(), 0);
+  assert_eq!(events, vec![Event::Download(download), Event::Progress{ value: 100.0 }]);
+  assert_eq!(stub.request(), json!({ "version": protocol::VERSION, "op": "fetch", "fetch": { "op": "fetch", "platform": "wine" } }));
+}
+
+#[test]
+fn fetch_reports_last_error()
+{
+  let stub = Stub::new();
+  stub.reply("fetch.fetch", vec![
+    Event::Error{ code: 1, message: "first".into() },
+    Event::Error{ code: 2, message: "checksum mismatch".into() },
+  ], 2);
+  let e = gameimage::fetch::fetch(Platform::Linux, |_| {}).unwrap_err();
+  assert!(e.to_string().contains("exited with code 2: checksum mismatch"), "{}", e);
+  assert!(!job::is_cancelled(&e));
+}
+
+#[test]
+fn fetch_installed_skips_invalid_platforms()
+{
+  let stub = Stub::new();
+  stub.reply("fetch.installed", vec![item("wine"), item("dos"), item("retroarch")], 0);
+  assert_eq!(gameimage::fetch::installed().unwrap(), vec![Platform::Wine, Platform::Retroarch]);
+  assert_eq!(stub.request(), json!({ "version": protocol::VERSION, "op": "fetch", "fetch": { "op": "installed" } }));
+}
+
+#[test]
+fn fetch_sources_returns_exit_code()
+{
+  let stub = Stub::new();
+  assert_eq!(gameimage::fetch::sources().unwrap(), 0);
+  stub.reply("fetch.sources", vec![], 7);
+  assert_eq!(gameimage::fetch::sources().unwrap(), 7);
+  assert_eq!(stub.requests().len(), 2);
+  assert_eq!(stub.requests()[1], json!({ "version": protocol::VERSION, "op": "fetch", "fetch": { "op": "sources" } }));
+}
+
+#[test]
+fn install_operations()
+{
+  let stub = Stub::new();
+  let f_install = |op: &str, sub_op: &str, args: Vec<&str>| json!(
+  {
+    "version": protocol::VERSION,
+    "op": "install",
+    "install": { "op": op, "sub_op": sub_op, "args": args },
+  });
+  gameimage::install::icon(&PathBuf::from("/tmp/icon.png")).unwrap();
+  gameimage::install::install("rom", vec!["/tmp/a.exe".into(), "/tmp/b.exe".into()]).unwrap();
+  gameimage::install::remote("core", vec!["snes9x".into()]).unwrap();
+  gameimage::install::remove("bios", vec!["scph.bin".into()]).unwrap();
+  gameimage::install::gui().unwrap();
+  gameimage::install::winetricks(vec!["-q".into(), "d3dx9".into()]).unwrap();
+  gameimage::install::wine(vec!["winecfg".into()]).unwrap();
+  assert!(gameimage::install::install("floppy", vec![]).is_err());
+  assert_eq!(stub.requests(), vec![
+    f_install("install", "icon", vec!["/tmp/icon.png"]),
+    f_install("install", "rom", vec!["/tmp/a.exe", "/tmp/b.exe"]),
+    f_install("remote", "core", vec!["snes9x"]),
+    f_install("remove", "bios", vec!["scph.bin"]),
+    f_install("install", "gui", vec![]),
+    f_install("install", "winetricks", vec!["-q", "d3dx9"]),
+    f_install("install", "wine", vec!["winecfg"]),
+  ]);
+}
+
+#[test]
+fn install_failure_carries_exit_code()
+{
+  let stub = Stub::new();
+  stub.reply("install.install", vec![], 3);
+  let e = gameimage::install::install("rom", vec!["/tmp/a.exe".into()]).unwrap_err();
+  assert!(e.to_string().starts_with("Could not install files"), "{}", e);
+  assert!(e.to_string().contains("exited with code 3"), "{}", e);
+}
+
+#[test]
+fn search_local_and_remote()
+{
+  let stub = Stub::new();
+  stub.reply("search.local", vec![item("rom/game.exe"), item("rom/setup.exe")], 0);
+  stub.reply("search.remote", vec![item("snes9x_libretro.so")], 0);
+  assert_eq!(gameimage::search::search_local("rom").unwrap()
+    , vec![PathBuf::from("rom/game.exe"), PathBuf::from("rom/setup.exe")]
+  );
+  assert_eq!(gameimage::search::search_remote("core").unwrap(), vec![PathBuf::from("snes9x_libretro.so")]);
+  assert!(gameimage::search::search_local("floppy").is_err());
+  assert_eq!(stub.requests(), vec![
+    json!({ "version": protocol::VERSION, "op": "search", "search": { "op": "local", "query": "rom" } }),
+    json!({ "version": protocol::VERSION, "op": "search", "search": { "op": "remote", "query": "core" } }),
+  ]);
+}
+
+#[test]
+fn select_and_project()
+{
+  let stub = Stub::new();
+  gameimage::select::select("rom", &PathBuf::from("rom/game.exe")).unwrap();
+  gameimage::project::set("game").unwrap();
+  gameimage::project::del("emu").unwrap();
+  assert_eq!(stub.requests(), vec![
+    json!({ "version": protocol::VERSION, "op": "select", "select": { "op": "rom", "path_file_target": "rom/game.exe" } }),
+    json!({ "version": protocol::VERSION, "op": "project", "project": { "op": "set", "name": "game" } }),
+    json!({ "version": protocol::VERSION, "op": "project", "project": { "op": "del", "name": "emu" } }),
+  ]);
+}
+
+#[test]
+fn package_and_desktop()
+{
+  let stub = Stub::new();
+  gameimage::package::package("Game", vec!["game".into(), "emu".into()]).unwrap();
+  gameimage::desktop::icon(&PathBuf::from("/tmp/icon.png")).unwrap();
+  gameimage::desktop::desktop("Game", "entry,icon").unwrap();
+  assert_eq!(stub.requests(), vec![
+    json!({ "version": protocol::VERSION, "op": "package", "package": { "name": "Game", "projects": ["game", "emu"] } }),
+    json!({ "version": protocol::VERSION, "op": "desktop", "desktop": { "op": "icon", "path_file_icon": "/tmp/icon.png" } }),
+    json!({ "version": protocol::VERSION, "op": "desktop", "desktop": { "op": "setup", "name": "Game", "items": "entry,icon" } }),
+  ]);
+}
+
+#[test]
+fn test_and_compress()
+{
+  let stub = Stub::new();
+  gameimage::test::test().unwrap();
+  protocol::Cli.compress().unwrap();
+  stub.reply("compress", vec![], 1);
+  assert!(matches!(protocol::Cli.compress(), Err(protocol::Error::Exit{ op: "compress", code: 1, .. })));
+  assert_eq!(stub.requests(), vec![
+    json!({ "version": protocol::VERSION, "op": "test" }),
+    json!({ "version": protocol::VERSION, "op": "compress" }),
+    json!({ "version": protocol::VERSION, "op": "compress" }),
+  ]);
+}
+
+#[test]
+fn version_mismatch()
+{
+  let stub = Stub::new();
+  assert!(matches!(protocol::Cli.version(), Ok(protocol::VERSION)));
+  stub.reply("version", vec![item("1")], 0);
+  assert!(matches!(protocol::Cli.version(), Err(protocol::Error::Version{ found: Some(1), .. })));
+  stub.reply("version", vec![], 0);
+  assert!(matches!(protocol::Cli.version(), Err(protocol::Error::Version{ found: None, .. })));
+}
+
+// fn wait() {{{
+// Waits for the queued job to finish
+fn wait(id: u64) -> queue::Entry
+{
+  for _ in 0..500
+  {
+    match queue::entries().into_iter().find(|e| e.id == id)
+    {
+      Some(entry) if ! entry.state.is_pending() => return entry,
+      _ => std::thread::sleep(std::time::Duration::from_millis(10)),
+    } // match
+  } // for
+  panic!("Job {} did not finish", id);
+} // fn: wait }}}
+
+#[test]
+fn queue_records_output_and_retries()
+{
+  let stub = Stub::new();
+  stub.reply("install.install", vec![], 2);
+  let id_fail = queue::push("Install rom", vec![], || gameimage::install::install("rom", vec!["a.exe".into()]));
+  let id_done = queue::push("Test", vec![], gameimage::test::test);
+  let entry = wait(id_fail);
+  assert_eq!(entry.state, queue::State::Failed);
+  assert_eq!(entry.code, Some(2));
+  assert!(entry.output.last().unwrap().starts_with("Error: Could not install files"), "{:?}", entry.output);
+  let entry = wait(id_done);
+  assert_eq!(entry.state, queue::State::Done);
+  assert_eq!(entry.code, Some(0));
+  // Jobs run in the order they were queued
+  assert_eq!(stub.requests().iter().map(|e| e["op"].clone()).collect::<Vec<_>>(), vec!["install", "test"]);
+  // Retry the failed job
+  stub.reply("install.install", vec![], 0);
+  queue::retry(id_fail);
+  let entry = wait(id_fail);
+  assert_eq!(entry.state, queue::State::Done);
+  assert_eq!(stub.requests().len(), 3);
+  queue::clear();
+  assert!(queue::entries().iter().all(|e| e.id != id_fail && e.id != id_done));
+}
+
+#[test]
+fn queue_prompts_choices_while_the_job_runs()
+{
+  let _stub = Stub::new();
+  let chosen = std::sync::Arc::new(std::sync::Mutex::new(None));
+  let chosen_job = chosen.clone();
+  let id = queue::push("Install from 'Disc 1.iso'", vec![], move ||
+  {
+    let chosen = chosen_job.clone();
+    queue::prompt(queue::current().unwrap(), "Disc in the drive D:", vec!["Disc 1.iso".into(), "Disc 2.iso".into()], 0, move |index|
+    {
+      *chosen.lock().unwrap() = Some(index);
+      Ok(())
+    });
+    // The installer waits for the second disc
+    for _ in 0..500
+    {
+      if chosen_job.lock().unwrap().is_some() { return Ok(()); } // if
+      std::thread::sleep(std::time::Duration::from_millis(10));
+    } // for
+    Err(anyhow::anyhow!("No disc was chosen"))
+  });
+  let f_prompt = || queue::entries().into_iter().find(|e| e.id == id).and_then(|e| e.prompt);
+  let prompt = (0..500).find_map(|_| { std::thread::sleep(std::time::Duration::from_millis(10)); f_prompt() }).unwrap();
+  assert_eq!(prompt.choices, ["Disc 1.iso", "Disc 2.iso"]);
+  assert_eq!(prompt.selected, 0);
+  queue::choose(id, 1);
+  assert_eq!(f_prompt().unwrap().selected, 1);
+  // The prompt ends with the job
+  let entry = wait(id);
+  assert_eq!(entry.state, queue::State::Done);
+  assert_eq!(*chosen.lock().unwrap(), Some(1));
+  assert!(entry.prompt.is_none());
+  queue::clear();
+}
+
+#[test]
+fn cancel_terminates_the_process_group_and_tagged_processes()
+{
+  let stub = Stub::new();
+  let path_file_pids = stub.dir.path.join("pids");
+  // A child in the process group and a child that left it and ignores SIGTERM
+  stub.run("fetch.fetch", &format!("sleep 60 & echo $! >> {0}; \
+    setsid sh -c 'trap \"\" TERM; exec sleep 60' & echo $! >> {0}; wait", path_file_pids.display()));
+  let handle = std::thread::spawn(|| gameimage::fetch::fetch(Platform::Linux, |_| {}));
+  let f_pids = || -> Vec<i32>
+  {
+    std::fs::read_to_string(&path_file_pids).unwrap_or_default().lines().filter_map(|e| e.parse().ok()).collect()
+  };
+  for _ in 0..500
+  {
+    if f_pids().len() == 2 { break; } // if
+    std::thread::sleep(std::time::Duration::from_millis(10));
+  } // for
+  let pids = f_pids();
+  assert_eq!(pids.len(), 2);
+  let instant = std::time::Instant::now();
+  job::cancel_all();
+  let e = handle.join().unwrap().unwrap_err();
+  assert!(job::is_cancelled(&e), "{}", e);
+  // Returns once the remaining processes are killed, they hold the output pipes of the command
+  assert!(instant.elapsed() < std::time::Duration::from_secs(10), "Cancel took {:?}", instant.elapsed());
+  // Processes exit on SIGTERM or are killed after the grace period, zombies already exited
+  let f_is_alive = |pid: &i32| std::fs::read_to_string(format!("/proc/{}/stat", pid))
+    .is_ok_and(|e| ! e.rsplit(')').next().unwrap_or_default().trim_start().starts_with('Z'));
+  for _ in 0..100
+  {
+    if ! pids.iter().any(f_is_alive) { break; } // if
+    std::thread::sleep(std::time::Duration::from_millis(100));
+  } // for
+  assert!(! pids.iter().any(f_is_alive), "Processes {:?} are still running", pids);
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
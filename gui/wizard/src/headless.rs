@@ -0,0 +1,229 @@
+use std::env;
+use std::path::Path;
+
+use anyhow::anyhow as ah;
+use fltk::prelude::InputExt;
+
+use shared::std::PathBufExt;
+
+use crate::db;
+use crate::lib;
+use crate::frame;
+use crate::common;
+use crate::log_status;
+use crate::gameimage;
+use crate::gameimage::protocol::{self, Backend};
+
+// fn progress() {{{
+fn progress(stage: &str, msg: impl std::fmt::Display)
+{
+  if common::HEADLESS.load(std::sync::atomic::Ordering::Relaxed)
+  {
+    println!("[{}] {}", stage, msg);
+  } // if
+  else
+  {
+    log_status!("{}", msg);
+  } // else
+} // fn: progress }}}
+
+// fn init() {{{
+fn init(recipe: &db::recipe::Recipe) -> anyhow::Result<()>
+{
+  let path_dir_build = recipe.path_dir_build.clone().ok_or(ah!("No build directory in recipe"))?;
+  progress("init", format!("Build directory is '{}'", path_dir_build.string()));
+  std::fs::create_dir_all(&path_dir_build)?;
+  env::set_var("GIMG_DIR", &path_dir_build.string());
+  gameimage::init::build(path_dir_build)?;
+  // Fetch fetch list
+  match gameimage::fetch::sources()?
+  {
+    0 => (),
+    rc => return Err(ah!("Could not fetch the list of sources, backend exited with {}", rc)),
+  } // match
+  frame::welcome::check_version()
+} // fn: init }}}
+
+// fn fetch() {{{
+fn fetch(recipe: &db::recipe::Recipe) -> anyhow::Result<()>
+{
+  // Platforms required by the recipe, fetched once each
+  let mut platforms: Vec<common::Platform> = vec![];
+  for project in recipe.projects.iter()
+  {
+    if ! platforms.contains(&project.platform) { platforms.push(project.platform.clone()); }
+  } // for
+  let vec_installed = gameimage::fetch::installed()?;
+  for platform in platforms
+  {
+    let str_platform = platform.as_str();
+    let mut is_installed = vec_installed.contains(&platform);
+    // A single wine distribution is included in the build, the first one in the recipe wins
+    if platform == common::Platform::Wine
+    {
+      let dist_wine = recipe.projects.iter()
+        .find_map(|e| e.dist_wine.clone())
+        .unwrap_or(String::from("default"));
+      is_installed = is_installed && db::global::read()?.dist_wine == dist_wine;
+      db::global::update(|mut db| { db.dist_wine = dist_wine.clone(); db })?;
+    } // if
+    if is_installed
+    {
+      progress("fetch", format!("Platform '{}' is already installed", str_platform));
+      continue;
+    } // if
+    progress("fetch", format!("Fetching platform '{}'", str_platform));
+    // Backend sends the download status periodically
+    gameimage::fetch::fetch(platform, |event|
+    {
+      if let lib::ipc::Event::Download(download) = event
+      {
+        progress("fetch", format!("{:.0}% {}", download.percentage(), download));
+      } // if
+    })?;
+  } // for
+  Ok(())
+} // fn: fetch }}}
+
+// fn project() {{{
+fn project(project: &db::recipe::Project) -> anyhow::Result<()>
+{
+  let name = &project.name;
+  if name.is_empty() || ! name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+  {
+    return Err(ah!("Invalid project name '{}', use only alphanumeric characters, '-' and '_'", name));
+  } // if
+  // Create project
+  progress("project", format!("{}: creating project for platform '{}'", name, project.platform.as_str()));
+  gameimage::init::project(name.clone(), project.platform.as_str().to_string())?;
+  // Icon
+  if let Some(path_file_icon) = project.icon.as_ref()
+  {
+    progress("icon", format!("{}: installing icon '{}'", name, path_file_icon.string()));
+    gameimage::install::icon(path_file_icon)?;
+  } // if
+  // Install files
+  for (label, files) in [("rom", &project.rom), ("core", &project.core), ("bios", &project.bios), ("keys", &project.keys)]
+  {
+    if files.is_empty() { continue; }
+    progress("install", format!("{}: installing {} {} file(s)", name, files.len(), label));
+    gameimage::install::install(label, files.iter().map(|e| e.string()).collect())?;
+  } // for
+  if ! project.core_remote.is_empty()
+  {
+    progress("install", format!("{}: downloading {} core(s)", name, project.core_remote.len()));
+    gameimage::install::remote("core", project.core_remote.clone())?;
+  } // if
+  let path_dir_project = db::global::get_current_project()?.path_dir_project;
+  for command in project.wine.iter()
+  {
+    progress("install", format!("{}: running '{}' with wine", name, command.join(" ")));
+    gameimage::install::wine(command.clone())?;
+    db::recipe::record_wine(&path_dir_project, command)?;
+  } // for
+  // Winetricks, one verb at the time since winetricks exits if at least one verb fails
+  for verb in project.winetricks.iter()
+  {
+    progress("winetricks", format!("{}: installing '{}'", name, verb));
+    gameimage::install::winetricks(vec!["-f".into(), "-q".into(), verb.clone()])?;
+  } // for
+  // Executable configuration
+  for (str_file_db, kv) in project.databases()
+  {
+    if kv.is_empty() { continue; }
+    progress("install", format!("{}: writing {} entries to '{}'", name, kv.len(), str_file_db));
+    for (key, val) in kv.iter()
+    {
+      shared::db::kv::write(&path_dir_project.join(str_file_db), key, val)?;
+    } // for
+  } // for
+  // Environment of each executable
+  for (executable, kv) in project.env_executable.iter()
+  {
+    progress("install", format!("{}: writing {} variables of '{}'", name, kv.len(), executable));
+    for (key, val) in kv.iter()
+    {
+      shared::db::env::write(&path_dir_project.join("gameimage.env.executable.json"), executable, key, val)?;
+    } // for
+  } // for
+  // Select the default rom, a single installed rom is the default one
+  let path_file_default = match project.default_rom.clone()
+  {
+    Some(path_file_default) => Some(path_file_default),
+    None => match gameimage::search::search_local("rom")?.as_slice()
+    {
+      [path_file_rom] => Some(path_file_rom.clone()),
+      _ => None,
+    },
+  }; // match
+  if let Some(path_file_default) = path_file_default
+  {
+    progress("select", format!("{}: default rom is '{}'", name, path_file_default.string()));
+    gameimage::select::select("rom", &path_file_default)?;
+  } // if
+  Ok(())
+} // fn: project }}}
+
+// fn compress() {{{
+// Compresses the current project, the package step includes the compressed layer
+fn compress(project: &db::recipe::Project) -> anyhow::Result<()>
+{
+  progress("compress", format!("{}: compressing project", project.name));
+  protocol::Cli.compress().map_err(|e| ah!("Could not compress project '{}': {}", project.name, e))
+} // fn: compress }}}
+
+// fn package() {{{
+fn package(recipe: &db::recipe::Recipe) -> anyhow::Result<()>
+{
+  let projects : Vec<String> = recipe.projects.iter().map(|e| e.name.clone()).collect();
+  progress("package", format!("Including projects '{}' in '{}'", projects.join(","), recipe.name));
+  gameimage::package::package(&recipe.name, projects)?;
+  // Desktop integration
+  if let Some(desktop) = recipe.desktop.as_ref()
+  && ! desktop.items.is_empty()
+  {
+    progress("desktop", format!("Integration items '{}'", desktop.items.join(",")));
+    gameimage::desktop::icon(&desktop.icon)?;
+    gameimage::desktop::desktop(&recipe.name, &desktop.items.join(","))?;
+  } // if
+  Ok(())
+} // fn: package }}}
+
+// pub fn import() {{{
+// Recreates the projects of a recipe in the current build directory, they are compressed when the
+// package is built
+pub fn import(path_file_recipe: &Path) -> anyhow::Result<()>
+{
+  let recipe = db::recipe::read(path_file_recipe)?;
+  if let Some(level) = recipe.compression
+  {
+    env::set_var("FIM_COMPRESSION_LEVEL", level.to_string());
+  } // if
+  fetch(&recipe)?;
+  for entry in recipe.projects.iter()
+  {
+    project(entry)?;
+  } // for
+  Ok(())
+} // fn: import }}}
+
+// pub fn build() {{{
+pub fn build(path_file_recipe: &Path) -> anyhow::Result<()>
+{
+  common::HEADLESS.store(true, std::sync::atomic::Ordering::Relaxed);
+  let recipe = db::recipe::read(path_file_recipe)?;
+  if recipe.projects.is_empty() { return Err(ah!("No projects in recipe")); }
+  env::set_var("FIM_COMPRESSION_LEVEL", recipe.compression.unwrap_or(7).to_string());
+  init(&recipe)?;
+  fetch(&recipe)?;
+  for entry in recipe.projects.iter()
+  {
+    project(entry)?;
+    compress(entry)?;
+  } // for
+  package(&recipe)?;
+  progress("done", format!("Package written to '{}'", db::global::read()?.path_file_output.string()));
+  Ok(())
+} // fn: build }}}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,353 @@
+// Health check of a wine prefix, each finding comes with a suggestion to fix it
+use std::collections::BTreeMap;
+use std::path::{Path,PathBuf};
+
+use crate::lib::winecfg;
+
+// Libraries replaced by dxvk and vkd3d-proton
+const DLLS_DXVK: [&str; 4] = ["d3d9", "d3d10core", "d3d11", "dxgi"];
+const DLLS_VKD3D: [&str; 2] = ["d3d12", "d3d12core"];
+// Fonts installed by the corefonts verb of winetricks, which most games expect
+const FONTS_CORE: [&str; 8] = ["arial.ttf", "times.ttf", "cour.ttf", "verdana.ttf", "georgia.ttf", "trebuc.ttf", "comic.ttf", "impact.ttf"];
+// Broken links listed in a single finding
+const COUNT_LINKS_MAX: usize = 5;
+
+// pub enum Severity {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity
+{
+  Error,
+  Warning,
+  Info,
+} // enum Severity }}}
+
+// impl Severity {{{
+impl Severity
+{
+  pub fn as_str(&self) -> &'static str
+  {
+    match self
+    {
+      Severity::Error   => "Error",
+      Severity::Warning => "Warning",
+      Severity::Info    => "Info",
+    } // match
+  } // fn: as_str
+} // impl Severity }}}
+
+// pub struct Finding {{{
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding
+{
+  pub severity: Severity,
+  pub title: String,
+  pub fix: Option<String>, // suggested fix, none when there is nothing to do
+} // struct Finding }}}
+
+// fn finding() {{{
+fn finding(severity: Severity, title: String, fix: Option<&str>) -> Finding
+{
+  Finding{ severity, title, fix: fix.map(|e| e.to_string()) }
+} // fn: finding }}}
+
+// pub enum Dll {{{
+// Implementation of a library in the prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dll
+{
+  Missing,
+  Builtin, // placeholder of a library implemented by wine
+  Dxvk(Option<String>), // with the version, if found
+  Vkd3d(Option<String>),
+  Native, // other windows library
+} // enum Dll }}}
+
+// fn contains() {{{
+fn contains(data: &[u8], needle: &[u8]) -> bool
+{
+  data.windows(needle.len()).any(|e| e.eq_ignore_ascii_case(needle))
+} // fn: contains }}}
+
+// fn version() {{{
+// First string of the library which looks like a release tag, e.g., 'v2.3' or 'v2.3.1-12-gabcdef'
+fn version(data: &[u8]) -> Option<String>
+{
+  data.split(|e| *e == 0)
+    .filter_map(|e| std::str::from_utf8(e).ok())
+    .find(|e|
+    {
+      let tag = match e.strip_prefix('v') { Some(tag) => tag, None => return false };
+      let number = tag.split('-').next().unwrap_or_default();
+      number.contains('.') && number.split('.').all(|e| ! e.is_empty() && e.chars().all(|c| c.is_ascii_digit()))
+    })
+    .map(|e| e.to_string())
+} // fn: version }}}
+
+// pub fn dll() {{{
+pub fn dll(path_file_dll: &Path) -> Dll
+{
+  let data = match std::fs::read(path_file_dll)
+  {
+    Ok(data) => data,
+    Err(_) => return Dll::Missing,
+  }; // match
+  if contains(&data, b"Wine builtin DLL") || contains(&data, b"Wine placeholder DLL") { return Dll::Builtin; } // if
+  if contains(&data, b"vkd3d-proton") { return Dll::Vkd3d(version(&data)); } // if
+  if contains(&data, b"dxvk") { return Dll::Dxvk(version(&data)); } // if
+  Dll::Native
+} // fn: dll }}}
+
+// pub fn arch() {{{
+// Architecture the prefix was created with, e.g., 'win64'
+pub fn arch(path_dir_prefix: &Path) -> Option<String>
+{
+  std::fs::read_to_string(path_dir_prefix.join("system.reg")).ok()?
+    .lines()
+    .find_map(|e| e.trim().strip_prefix("#arch=").map(|e| e.trim().to_string()))
+} // fn: arch }}}
+
+// fn find_nocase() {{{
+fn find_nocase(path_dir: &Path, name: &str) -> Option<PathBuf>
+{
+  std::fs::read_dir(path_dir).ok()?
+    .filter_map(|e| e.ok())
+    .map(|e| e.path())
+    .find(|e| e.file_name().is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(name)))
+} // fn: find_nocase }}}
+
+// fn broken_links() {{{
+// Links which point to missing files, links are not followed
+fn broken_links(path_dir: &Path, links: &mut Vec<PathBuf>)
+{
+  let entries = match std::fs::read_dir(path_dir)
+  {
+    Ok(entries) => entries,
+    Err(_) => return,
+  }; // match
+  for path in entries.filter_map(|e| e.ok()).map(|e| e.path())
+  {
+    match std::fs::symlink_metadata(&path)
+    {
+      Ok(metadata) if metadata.file_type().is_symlink() => if ! path.exists() { links.push(path); },
+      Ok(metadata) if metadata.is_dir() => broken_links(&path, links),
+      _ => (),
+    } // match
+  } // for
+} // fn: broken_links }}}
+
+// fn check_arch() {{{
+fn check_arch(path_dir_prefix: &Path, is_64bit_executable: Option<bool>) -> (Vec<Finding>, bool)
+{
+  let arch = arch(path_dir_prefix);
+  let is_64bit = arch.as_deref() == Some("win64");
+  let findings = match (arch, is_64bit_executable)
+  {
+    (None, _) => vec![finding(Severity::Warning
+      , "Could not read the architecture of the prefix".into()
+      , Some("Run winecfg or any wine command so wine updates the registry files"))],
+    (Some(arch), Some(true)) if ! is_64bit => vec![finding(Severity::Error
+      , format!("The executable is 64-bit, but the prefix is {}", arch)
+      , Some("Remove the prefix and create it again with a 64-bit wine distribution"))],
+    (Some(arch), _) => vec![finding(Severity::Info, format!("Prefix architecture is {}", arch), None)],
+  }; // match
+  (findings, is_64bit)
+} // fn: check_arch }}}
+
+// fn check_layer() {{{
+// Libraries of a translation layer in every system directory, and the overrides which load them
+fn check_layer(path_dir_prefix: &Path
+  , is_64bit: bool
+  , name: &str
+  , dlls: &[&str]
+  , dll_overrides: &BTreeMap<String, String>) -> Vec<Finding>
+{
+  let path_dir_windows = path_dir_prefix.join("drive_c").join("windows");
+  let dirs = if is_64bit { vec!["system32", "syswow64"] } else { vec!["system32"] };
+  let mut installed : Vec<(String, Dll)> = vec![];
+  let mut other : Vec<(String, Dll)> = vec![];
+  for dir in dirs
+  {
+    for lib in dlls
+    {
+      let kind = dll(&path_dir_windows.join(dir).join(format!("{}.dll", lib)));
+      let is_layer = matches!((&kind, name), (Dll::Dxvk(_), "DXVK") | (Dll::Vkd3d(_), "VKD3D"));
+      let entry = (format!("{} ({})", lib, dir), kind);
+      if is_layer { installed.push(entry); } else { other.push(entry); } // if
+    } // for
+  } // for
+  let mut findings = vec![];
+  // Report the version from the first library which has it
+  let versions : Vec<String> = installed.iter()
+    .filter_map(|(_, kind)| match kind { Dll::Dxvk(version) | Dll::Vkd3d(version) => version.clone(), _ => None })
+    .collect();
+  if installed.is_empty()
+  {
+    findings.push(finding(Severity::Info
+      , format!("{} is not installed", name)
+      , Some(&format!("Install {} in the configure frame if the game renders with direct3d", name))));
+    // Native overrides without the libraries make the game fail to load them
+    for lib in dlls.iter().filter(|lib| dll_overrides.get(**lib).is_some_and(|e| e.starts_with("native")))
+    {
+      if other.iter().any(|(label, kind)| label.starts_with(&format!("{} ", lib)) && *kind == Dll::Native) { continue; } // if
+      findings.push(finding(Severity::Warning
+        , format!("{} is overridden as '{}', but {} is not installed", lib, dll_overrides[*lib], name)
+        , Some("Remove the override in the wine settings or install the library")));
+    } // for
+    return findings;
+  } // if
+  findings.push(finding(Severity::Info
+    , format!("{} {} is installed", name, versions.first().map(|e| e.as_str()).unwrap_or("of unknown version"))
+    , None));
+  if ! other.is_empty()
+  {
+    findings.push(finding(Severity::Warning
+      , format!("{} is partially installed, not replaced: {}", name, other.iter().map(|e| e.0.clone()).collect::<Vec<_>>().join(", "))
+      , Some(&format!("Install {} again in the configure frame", name))));
+  } // if
+  if versions.iter().any(|e| Some(e) != versions.first())
+  {
+    findings.push(finding(Severity::Warning
+      , format!("{} libraries have different versions: {}", name, versions.join(", "))
+      , Some(&format!("Install {} again in the configure frame", name))));
+  } // if
+  // Wine loads its own libraries unless the native ones are preferred
+  let mut libs : Vec<&str> = installed.iter().filter_map(|(label, _)| label.split(' ').next()).collect();
+  // The libraries of win64 prefixes are installed in both system directories
+  libs.sort();
+  libs.dedup();
+  for lib in libs
+  {
+    match dll_overrides.get(lib)
+    {
+      Some(order) if order.starts_with("native") => (),
+      order => findings.push(finding(Severity::Warning
+        , format!("{} of {} is not loaded, its override is '{}'", lib, name, order.map(|e| e.as_str()).unwrap_or("builtin"))
+        , Some(&format!("Set the override '{}=n,b' in the wine settings", lib)))),
+    } // match
+  } // for
+  findings
+} // fn: check_layer }}}
+
+// fn check_fonts() {{{
+fn check_fonts(path_dir_prefix: &Path) -> Vec<Finding>
+{
+  let path_dir_fonts = path_dir_prefix.join("drive_c").join("windows").join("Fonts");
+  let missing : Vec<&str> = FONTS_CORE.iter()
+    .filter(|e| find_nocase(&path_dir_fonts, e).is_none())
+    .copied()
+    .collect();
+  match missing.is_empty()
+  {
+    true => vec![finding(Severity::Info, "Core fonts are installed".into(), None)],
+    false => vec![finding(Severity::Warning
+      , format!("Missing core fonts: {}", missing.join(", "))
+      , Some("Install the 'corefonts' verb in the winetricks frame, games may show no text without them"))],
+  } // match
+} // fn: check_fonts }}}
+
+// fn check_links() {{{
+fn check_links(path_dir_prefix: &Path) -> Vec<Finding>
+{
+  let mut links = vec![];
+  broken_links(&path_dir_prefix.join("drive_c"), &mut links);
+  // Drives are links too, e.g., 'c:' to '../drive_c'
+  for path in std::fs::read_dir(path_dir_prefix.join("dosdevices")).into_iter().flatten().filter_map(|e| e.ok()).map(|e| e.path())
+  {
+    if path.symlink_metadata().is_ok_and(|e| e.file_type().is_symlink()) && ! path.exists() { links.push(path); } // if
+  } // for
+  if links.is_empty() { return vec![]; } // if
+  links.sort();
+  let mut names : Vec<String> = links.iter()
+    .take(COUNT_LINKS_MAX)
+    .map(|e| e.strip_prefix(path_dir_prefix).unwrap_or(e).to_string_lossy().to_string())
+    .collect();
+  if links.len() > COUNT_LINKS_MAX { names.push(format!("and {} more", links.len() - COUNT_LINKS_MAX)); } // if
+  vec![finding(Severity::Error
+    , format!("Broken links in the prefix: {}", names.join(", "))
+    , Some("Remove the links or point them to existing directories, e.g., folders of a user which no longer exists"))]
+} // fn: check_links }}}
+
+// pub fn check() {{{
+// Inspects the prefix, the overrides of the environment take precedence over the ones of the
+// registry. Findings are sorted by severity
+pub fn check(path_dir_prefix: &Path, env_dll_overrides: &str, is_64bit_executable: Option<bool>) -> Vec<Finding>
+{
+  if ! path_dir_prefix.join("drive_c").exists()
+  {
+    return vec![finding(Severity::Error
+      , "The wine prefix does not exist".into()
+      , Some("Click on next in the configure frame to create it"))];
+  } // if
+  let mut dll_overrides = winecfg::Settings::read(path_dir_prefix).map(|e| e.dll_overrides).unwrap_or_default();
+  dll_overrides.extend(winecfg::dll_overrides_from_str(env_dll_overrides));
+  let (mut findings, is_64bit) = check_arch(path_dir_prefix, is_64bit_executable);
+  findings.extend(check_layer(path_dir_prefix, is_64bit, "DXVK", &DLLS_DXVK, &dll_overrides));
+  findings.extend(check_layer(path_dir_prefix, is_64bit, "VKD3D", &DLLS_VKD3D, &dll_overrides));
+  findings.extend(check_fonts(path_dir_prefix));
+  findings.extend(check_links(path_dir_prefix));
+  findings.sort_by_key(|e| e.severity);
+  findings
+} // fn: check }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{check, dll, Dll, Severity};
+  use crate::testing::TempDir;
+
+  #[test]
+  fn detect_libraries()
+  {
+    let dir = TempDir::new();
+    let path_file = dir.path.join("d3d11.dll");
+    assert_eq!(dll(&path_file), Dll::Missing);
+    std::fs::write(&path_file, b"MZ\0\0Wine builtin DLL\0").unwrap();
+    assert_eq!(dll(&path_file), Dll::Builtin);
+    std::fs::write(&path_file, b"MZ\0v1\0vertex\0DXVK: \0v2.3.1-12-gabcdef\0").unwrap();
+    assert_eq!(dll(&path_file), Dll::Dxvk(Some("v2.3.1-12-gabcdef".into())));
+    std::fs::write(&path_file, b"MZ\0vkd3d-proton\0").unwrap();
+    assert_eq!(dll(&path_file), Dll::Vkd3d(None));
+    std::fs::write(&path_file, b"MZ\0Microsoft\0").unwrap();
+    assert_eq!(dll(&path_file), Dll::Native);
+  }
+
+  #[test]
+  fn report_prefix_problems()
+  {
+    let dir = TempDir::new();
+    let prefix = dir.path.join("wine");
+    assert_eq!(check(&prefix, "", None)[0].severity, Severity::Error);
+    let path_dir_windows = prefix.join("drive_c").join("windows");
+    for sub in ["system32", "syswow64", "Fonts"] { std::fs::create_dir_all(path_dir_windows.join(sub)).unwrap(); } // for
+    std::fs::write(prefix.join("system.reg"), "WINE REGISTRY Version 2\n#arch=win32\n").unwrap();
+    std::fs::write(prefix.join("user.reg"), "WINE REGISTRY Version 2\n[Software\\\\Wine\\\\DllOverrides] 0\n\"d3d9\"=\"native,builtin\"\n").unwrap();
+    for lib in ["d3d9", "d3d10core", "d3d11", "dxgi"]
+    {
+      std::fs::write(path_dir_windows.join("system32").join(format!("{}.dll", lib)), b"dxvk\0v2.3\0").unwrap();
+    } // for
+    std::fs::write(path_dir_windows.join("Fonts").join("ARIAL.TTF"), "").unwrap();
+    std::os::unix::fs::symlink("/nonexistent/Documents", prefix.join("drive_c").join("Documents")).unwrap();
+    let findings = check(&prefix, "d3d11,dxgi=n", Some(true));
+    let titles : Vec<(Severity, &str)> = findings.iter().map(|e| (e.severity, e.title.as_str())).collect();
+    assert_eq!(titles, vec![
+      (Severity::Error, "The executable is 64-bit, but the prefix is win32"),
+      (Severity::Error, "Broken links in the prefix: drive_c/Documents"),
+      (Severity::Warning, "d3d10core of DXVK is not loaded, its override is 'builtin'"),
+      (Severity::Warning, "Missing core fonts: times.ttf, cour.ttf, verdana.ttf, georgia.ttf, trebuc.ttf, comic.ttf, impact.ttf"),
+      (Severity::Info, "DXVK v2.3 is installed"),
+      (Severity::Info, "VKD3D is not installed"),
+    ]);
+    assert!(findings.iter().all(|e| e.severity == Severity::Info || e.fix.is_some()));
+    // Each library of a win64 prefix is reported once
+    std::fs::write(prefix.join("system.reg"), "WINE REGISTRY Version 2\n#arch=win64\n").unwrap();
+    for lib in ["d3d9", "d3d10core", "d3d11", "dxgi"]
+    {
+      std::fs::write(path_dir_windows.join("syswow64").join(format!("{}.dll", lib)), b"dxvk\0v2.3\0").unwrap();
+    } // for
+    let findings = check(&prefix, "d3d11,dxgi=n", Some(true));
+    let warnings : Vec<&str> = findings.iter().filter(|e| e.title.contains("is not loaded")).map(|e| e.title.as_str()).collect();
+    assert_eq!(warnings, vec!["d3d10core of DXVK is not loaded, its override is 'builtin'"]);
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
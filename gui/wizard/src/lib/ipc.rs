@@ -1,6 +1,12 @@
 use std::ffi::CString;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::os::unix::net::{UnixListener,UnixStream};
+use std::path::PathBuf;
+use std::sync::{Mutex,atomic::{AtomicBool,AtomicU64,Ordering}};
 
 use anyhow::anyhow as ah;
+use serde::{Deserialize, Serialize};
 
 use shared::std::PathBufExt;
 
@@ -16,55 +22,310 @@ struct MsgBuf
   mtext: [u8; 1024],
 } // struct MsgBuf }}}
 
-// pub struct Ipc {{{
-pub struct Ipc
+// pub enum Event {{{
+// Typed message sent by the backend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event
 {
-  msgid : i32,
-} // struct Ipc }}}
+  Progress { value: f64 },
+  Download(Download),
+  Log { line: String },
+  Item { value: String },
+  Warning { message: String },
+  Error { code: i32, message: String },
+} // }}}
 
-impl Ipc
+// pub struct Download {{{
+// Progress of a file download, the rate is in bytes per second and the eta in seconds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Download
 {
+  pub name: String,
+  pub bytes: u64,
+  pub total: u64,
+  pub rate: f64,
+  pub eta: Option<u64>,
+} // struct Download }}}
 
-// pub fn new() {{{
-pub fn new() -> anyhow::Result<Ipc>
+// impl Download {{{
+impl Download
 {
-  let msgid = match Ipc::get_msgid(libc::IPC_CREAT)
+  // pub fn percentage() {{{
+  pub fn percentage(&self) -> f64
   {
-    Ok(msgid) => msgid,
-    Err(e) => return Err(ah!("Failed to create message queue: {}", e)),
-  };
+    if self.total == 0 { return 0.0; }
+    self.bytes as f64 * 100.0 / self.total as f64
+  } // fn: percentage }}}
+
+  // fn bytes_human() {{{
+  fn bytes_human(bytes: f64) -> String
+  {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1
+    {
+      value /= 1024.0;
+      unit += 1;
+    } // while
+    format!("{:.1} {}", value, units[unit])
+  } // fn: bytes_human }}}
 
-  Ok(Ipc { msgid })
+  // fn duration_human() {{{
+  fn duration_human(secs: u64) -> String
+  {
+    match secs
+    {
+      0..=59 => format!("{}s", secs),
+      60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+      _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    } // match
+  } // fn: duration_human }}}
+} // impl Download }}}
+
+// impl std::fmt::Display for Download {{{
+impl std::fmt::Display for Download
+{
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    write!(f, "{}: {} of {} at {}/s"
+      , self.name
+      , Download::bytes_human(self.bytes as f64)
+      , Download::bytes_human(self.total as f64)
+      , Download::bytes_human(self.rate))?;
+    match self.eta
+    {
+      Some(eta) => write!(f, ", {} left", Download::duration_human(eta)),
+      None => Ok(()),
+    } // match
+  } // fn: fmt
 } // }}}
 
-// pub fn recv() {{{
-pub fn recv(&self) -> anyhow::Result<String>
+// struct Frame {{{
+// Slice of the json of an event, events larger than one message are split in multiple frames
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Frame
+{
+  id: u64,
+  part: usize,
+  parts: usize,
+  data: String,
+} // struct Frame }}}
+
+// Limits of the events being reassembled, the backend sends the frames of an event in sequence so
+// only corrupt or lost frames leave events pending
+const MAX_PARTS: usize = 4096;
+const MAX_PENDING: usize = 16;
+
+// pub struct Decoder {{{
+// Reassembles events from the received frames
+#[derive(Default)]
+pub struct Decoder
 {
-  let mut buf: MsgBuf = unsafe { std::mem::zeroed() };
+  pending: HashMap<u64, Vec<Option<String>>>,
+} // struct Decoder }}}
 
-  let ret = unsafe
+// impl Decoder {{{
+impl Decoder
+{
+  // pub fn push() {{{
+  // Returns the event once all of its frames were received
+  pub fn push(&mut self, msg: String) -> Option<Event>
   {
-    libc::msgrcv(self.msgid
-      , &mut buf as *mut MsgBuf as *mut libc::c_void
-      , buf.mtext.len() as libc::size_t
-      , 0
-      , libc::MSG_NOERROR)
-  };
+    let frame : Frame = match serde_json::from_str(&msg)
+    {
+      Ok(frame) => frame,
+      // Not framed, treat as a plain result item
+      Err(_) => return Some(Event::Item{ value: msg }),
+    }; // match
+    if frame.parts <= 1
+    {
+      return Some(Decoder::parse(frame.data));
+    } // if
+    if frame.parts > MAX_PARTS || frame.part >= frame.parts
+    {
+      self.pending.remove(&frame.id);
+      return Some(Event::Warning{ message: format!("Invalid frame {} of {} for event {}", frame.part, frame.parts, frame.id) });
+    } // if
+    // Events of older frames can not complete anymore once the limit is reached
+    if ! self.pending.contains_key(&frame.id) && self.pending.len() >= MAX_PENDING
+    && let Some(id) = self.pending.keys().min().copied()
+    {
+      self.pending.remove(&id);
+    } // if
+    let parts = self.pending.entry(frame.id).or_insert(vec![None; frame.parts]);
+    // Frames of the same event disagree on its size
+    if parts.len() != frame.parts
+    {
+      self.pending.remove(&frame.id);
+      return Some(Event::Warning{ message: format!("Inconsistent frame count for event {}", frame.id) });
+    } // if
+    parts[frame.part] = Some(frame.data);
+    if ! parts.iter().all(|e| e.is_some())
+    {
+      return None;
+    } // if
+    let data = self.pending.remove(&frame.id)?.into_iter().flatten().collect::<String>();
+    Some(Decoder::parse(data))
+  } // fn: push }}}
+
+  // fn parse() {{{
+  fn parse(data: String) -> Event
+  {
+    match serde_json::from_str(&data)
+    {
+      Ok(event) => event,
+      Err(e) => Event::Warning{ message: format!("Invalid event '{}': {}", data, e) },
+    } // match
+  } // fn: parse }}}
+} // impl Decoder }}}
+
+// pub trait Transport {{{
+// Channel the backend uses to send frames to the frontend, one per backend invocation
+pub trait Transport : Send + Sync
+{
+  // Blocks until the next frame arrives, fails once the transport is closed
+  fn recv(&self) -> anyhow::Result<String>;
+  // Releases the transport, a pending recv returns after the remaining frames are read
+  fn close(&self);
+  // Environment passed to the backend to select this transport
+  fn env(&self) -> Vec<(String,String)>;
+} // trait Transport }}}
+
+// pub fn open() {{{
+// Selects a transport for a new backend invocation, sockets are isolated per invocation and
+// preferred, the shared message queue is the fallback
+pub fn open() -> anyhow::Result<Box<dyn Transport>>
+{
+  match Socket::new()
+  {
+    Ok(socket) => return Ok(Box::new(socket)),
+    Err(e) => log!("Could not create ipc socket, falling back to message queue: {}", e),
+  } // match
+  Ok(Box::new(SysV::new()?))
+} // fn: open }}}
+
+// pub struct Socket {{{
+// Unix domain socket bound to an unique path, the backend connects to it and writes one frame per
+// line
+pub struct Socket
+{
+  path: PathBuf,
+  listener: UnixListener,
+  stream: Mutex<Option<std::io::BufReader<UnixStream>>>,
+  closed: AtomicBool,
+} // struct Socket }}}
+
+impl Socket
+{
+
+// pub fn new() {{{
+pub fn new() -> anyhow::Result<Socket>
+{
+  static COUNTER: AtomicU64 = AtomicU64::new(0);
+  let path_dir = std::env::var("XDG_RUNTIME_DIR")
+    .map(PathBuf::from)
+    .ok()
+    .filter(|e| e.is_dir())
+    .unwrap_or(std::env::temp_dir());
+  let path = path_dir.join(format!("gameimage-{}-{}.sock"
+    , std::process::id()
+    , COUNTER.fetch_add(1, Ordering::Relaxed)));
+  // Leftover from a previous process with the same pid
+  if path.exists() { std::fs::remove_file(&path)?; }
+  let listener = UnixListener::bind(&path)
+    .map_err(|e| ah!("Could not bind socket '{}': {}", path.string(), e))?;
+  log!("Frontend socket is: {}", path.string());
+  Ok(Socket { path, listener, stream: Mutex::new(None), closed: AtomicBool::new(false) })
+} // }}}
+
+} // impl Socket
 
-  if ret == -1
+// impl Transport for Socket {{{
+impl Transport for Socket
+{
+  fn recv(&self) -> anyhow::Result<String>
+  {
+    let mut guard = self.stream.lock().map_err(|_| ah!("Socket lock is poisoned"))?;
+    loop
+    {
+      if let Some(reader) = guard.as_mut()
+      {
+        let mut line = String::new();
+        match reader.read_line(&mut line)?
+        {
+          // Peer disconnected, wait for the next connection
+          0 => { *guard = None; continue; },
+          _ => return Ok(line.trim_end_matches('\n').to_string()),
+        } // match
+      } // if
+      // After close only drain the connections that are already pending
+      if self.closed.load(Ordering::SeqCst)
+      {
+        self.listener.set_nonblocking(true)?;
+      } // if
+      match self.listener.accept()
+      {
+        Ok((stream, _)) =>
+        {
+          stream.set_nonblocking(false)?;
+          *guard = Some(std::io::BufReader::new(stream));
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Err(ah!("Socket is closed")),
+        Err(e) => return Err(ah!("Could not accept connection: {}", e)),
+      } // match
+    } // loop
+  } // fn: recv
+
+  fn close(&self)
+  {
+    self.closed.store(true, Ordering::SeqCst);
+    // Wake up a blocking accept
+    if let Err(e) = UnixStream::connect(&self.path)
+    {
+      log!("Could not wake up socket '{}': {}", self.path.string(), e);
+    } // if
+    match std::fs::remove_file(&self.path)
+    {
+      Ok(()) => log!("Closed socket '{}'", self.path.string()),
+      Err(e) => log!("Could not remove socket '{}': {}", self.path.string(), e),
+    } // match
+  } // fn: close
+
+  fn env(&self) -> Vec<(String,String)>
   {
-    return Err(ah!("Could not recover message: {}", std::io::Error::last_os_error()));
-  } // if
+    vec![("GIMG_IPC_SOCKET".to_string(), self.path.string())]
+  } // fn: env
+} // impl Transport for Socket }}}
 
-  let ret = ret as usize;
-  let bytes = &buf.mtext[..ret];
+// Backend invocations that use the message queue, it is removed once the last one closes
+static SYSV_USERS: Mutex<usize> = Mutex::new(0);
 
-  let str_slice = match std::str::from_utf8(bytes) {
-    Ok(s) => s,
-    Err(e) => return Err(ah!("Received message is not valid UTF-8: {}", e)),
+// pub struct SysV {{{
+// System V message queue keyed by the backend binary, shared by all backend invocations
+pub struct SysV
+{
+  msgid : i32,
+  closed: AtomicBool,
+} // struct SysV }}}
+
+impl SysV
+{
+
+// pub fn new() {{{
+pub fn new() -> anyhow::Result<SysV>
+{
+  // Held while creating, so a closing invocation does not remove the queue in between
+  let mut users = SYSV_USERS.lock().unwrap();
+  let msgid = match SysV::get_msgid(libc::IPC_CREAT)
+  {
+    Ok(msgid) => msgid,
+    Err(e) => return Err(ah!("Failed to create message queue: {}", e)),
   };
+  *users += 1;
 
-  Ok(str_slice.to_owned())
+  Ok(SysV { msgid, closed: AtomicBool::new(false) })
 } // }}}
 
 // fn get_msgid() {{{
@@ -97,22 +358,121 @@ fn get_msgid(flags: i32) -> anyhow::Result<i32>
   }
 } // fn get_msgid() }}}
 
-// pub fn close() {{{
-pub fn close()
+} // impl SysV
+
+// impl Transport for SysV {{{
+impl Transport for SysV
 {
-  let msgid = match Ipc::get_msgid(0)
+  fn recv(&self) -> anyhow::Result<String>
   {
-    Ok(msgid) => msgid,
-    Err(e) => { log!("Could not retrieve msgid: {}", e); return; }
-  };
+    let mut buf: MsgBuf = unsafe { std::mem::zeroed() };
+
+    // Polls, the queue outlives this invocation while others use it
+    let ret = loop
+    {
+      let ret = unsafe
+      {
+        libc::msgrcv(self.msgid
+          , &mut buf as *mut MsgBuf as *mut libc::c_void
+          , buf.mtext.len() as libc::size_t
+          , 0
+          , libc::MSG_NOERROR | libc::IPC_NOWAIT)
+      };
+      if ret != -1 { break ret; } // if
+      let error = std::io::Error::last_os_error();
+      // After close only drain the messages that are already pending
+      if error.raw_os_error() != Some(libc::ENOMSG) || self.closed.load(Ordering::SeqCst)
+      {
+        return Err(ah!("Could not recover message: {}", error));
+      } // if
+      std::thread::sleep(std::time::Duration::from_millis(20));
+    }; // loop
+
+    let ret = ret as usize;
+    let bytes = &buf.mtext[..ret];
+
+    let str_slice = match std::str::from_utf8(bytes) {
+      Ok(s) => s,
+      Err(e) => return Err(ah!("Received message is not valid UTF-8: {}", e)),
+    };
+
+    Ok(str_slice.to_owned())
+  } // fn: recv
 
-  match unsafe { libc::msgctl(msgid, libc::IPC_RMID, std::ptr::null_mut()) }
+  fn close(&self)
   {
-    -1 => log!("Could not close existing message queue"),
-    _ => log!("Closed existing message queue"),
-  } // match
-} // close }}}
+    if self.closed.swap(true, Ordering::SeqCst) { return; } // if
+    // Other invocations still read from the shared queue
+    let mut users = SYSV_USERS.lock().unwrap();
+    *users = users.saturating_sub(1);
+    if *users > 0 { log!("Message queue still in use by {} invocation(s)", *users); return; } // if
+    match unsafe { libc::msgctl(self.msgid, libc::IPC_RMID, std::ptr::null_mut()) }
+    {
+      -1 => log!("Could not close existing message queue"),
+      _ => log!("Closed existing message queue"),
+    } // match
+  } // fn: close
+
+  fn env(&self) -> Vec<(String,String)>
+  {
+    vec![]
+  } // fn: env
+} // impl Transport for SysV }}}
 
-} // impl Ipc
+#[cfg(test)]
+mod tests
+{
+  use super::{Decoder, Event, MAX_PARTS, MAX_PENDING, SysV, Transport};
+  use crate::testing;
+
+  // fn frame() {{{
+  fn frame(id: u64, part: usize, parts: usize, data: &str) -> String
+  {
+    serde_json::json!({ "id": id, "part": part, "parts": parts, "data": data }).to_string()
+  } // fn: frame }}}
+
+  #[test]
+  fn decoder_reassembles_and_rejects_invalid_frames()
+  {
+    let mut decoder = Decoder::default();
+    // Frames of an event in any order
+    assert_eq!(decoder.push(frame(1, 1, 2, r#"":"a"}"#)), None);
+    assert_eq!(decoder.push(frame(1, 0, 2, r#"{"type":"item","value"#)), Some(Event::Item{ value: "a".into() }));
+    // Counts over the limit and indexes out of range are not kept
+    assert!(matches!(decoder.push(frame(2, 0, MAX_PARTS + 1, "")), Some(Event::Warning{ .. })));
+    assert!(matches!(decoder.push(frame(3, 2, 2, "")), Some(Event::Warning{ .. })));
+    assert_eq!(decoder.push(frame(4, 0, 3, "")), None);
+    assert!(matches!(decoder.push(frame(4, 1, 2, "")), Some(Event::Warning{ .. })));
+    assert!(decoder.pending.is_empty());
+    // Incomplete events are dropped, the oldest first
+    for id in 10..10 + MAX_PENDING as u64 + 4
+    {
+      assert_eq!(decoder.push(frame(id, 0, 2, "")), None);
+    } // for
+    assert_eq!(decoder.pending.len(), MAX_PENDING);
+    assert!(! decoder.pending.contains_key(&10));
+    assert!(decoder.pending.contains_key(&(13 + MAX_PENDING as u64)));
+  }
+
+  #[test]
+  fn message_queue_is_removed_by_its_last_user()
+  {
+    let _stub = testing::Stub::new();
+    let f_exists = |msgid: i32|
+    {
+      let mut stat: libc::msqid_ds = unsafe { std::mem::zeroed() };
+      unsafe { libc::msgctl(msgid, libc::IPC_STAT, &mut stat) != -1 }
+    };
+    let first = SysV::new().unwrap();
+    let second = SysV::new().unwrap();
+    assert_eq!(first.msgid, second.msgid);
+    // A finished invocation stops receiving, the other one still uses the queue
+    first.close();
+    assert!(first.recv().is_err());
+    assert!(f_exists(second.msgid));
+    second.close();
+    assert!(! f_exists(second.msgid));
+  }
+}
 
 // vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
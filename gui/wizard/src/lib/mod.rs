@@ -1 +1,8 @@
+pub mod health;
 pub mod ipc;
+pub mod pe;
+pub mod rank;
+pub mod registry;
+pub mod snapshot;
+pub mod source;
+pub mod winecfg;
@@ -0,0 +1,707 @@
+// Reader of portable executables, the format of windows binaries
+use std::collections::HashMap;
+use std::io::{Read,Seek,SeekFrom};
+use std::path::Path;
+
+use anyhow::anyhow as ah;
+
+// Resource types
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+const RT_VERSION: u32 = 16;
+// Levels of the resource tree, type, name and language
+const DEPTH_RESOURCES: usize = 3;
+// Machine types
+pub const MACHINE_I386: u16 = 0x14c;
+pub const MACHINE_AMD64: u16 = 0x8664;
+pub const MACHINE_ARM64: u16 = 0xaa64;
+
+// pub enum Subsystem {{{
+// Subsystem required to run the executable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem
+{
+  Gui,
+  Console,
+  Other(u16),
+} // enum Subsystem }}}
+
+// pub struct Header {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header
+{
+  pub machine: u16, // target architecture of the executable
+  pub subsystem: Subsystem,
+  pub timestamp: u32, // seconds since the epoch the executable was linked at
+} // struct Header }}}
+
+// pub struct Metadata {{{
+// Information embedded in the version resource of the executable
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata
+{
+  pub product_name: Option<String>,
+  pub company_name: Option<String>,
+  pub file_version: Option<String>,
+  pub timestamp: u32,
+  pub machine: u16,
+} // struct Metadata }}}
+
+impl Metadata
+{
+
+// pub fn arch() {{{
+pub fn arch(&self) -> &'static str
+{
+  match self.machine
+  {
+    MACHINE_I386  => "x86",
+    MACHINE_AMD64 => "x86_64",
+    MACHINE_ARM64 => "arm64",
+    _             => "unknown",
+  } // match
+} // fn: arch }}}
+
+// pub fn is_64bit() {{{
+pub fn is_64bit(&self) -> bool
+{
+  matches!(self.machine, MACHINE_AMD64 | MACHINE_ARM64)
+} // fn: is_64bit }}}
+
+// pub fn year() {{{
+// Release year from the build timestamp, reproducible builds store a hash or zero in it
+pub fn year(&self) -> Option<u32>
+{
+  let secs = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|e| e.as_secs())
+    .unwrap_or_default();
+  // Average length of a gregorian year in seconds
+  let year = 1970 + (self.timestamp as u64 / 31_556_952) as u32;
+  let year_current = 1970 + (secs / 31_556_952) as u32;
+  (1990..=year_current).contains(&year).then_some(year)
+} // fn: year }}}
+
+}
+
+// struct Section {{{
+struct Section
+{
+  address: u32, // relative virtual address
+  size_virtual: u32,
+  size_raw: u32,
+  offset_raw: u32,
+} // struct Section }}}
+
+// struct Image {{{
+// Headers of the executable, the sections are read on demand
+struct Image
+{
+  header: Header,
+  sections: Vec<Section>,
+  // Relative virtual address and size of the data directories, e.g., imports and resources
+  directories: Vec<(u32, u32)>,
+  file: std::fs::File,
+} // struct Image }}}
+
+// fn u16_at() {{{
+fn u16_at(data: &[u8], offset: usize) -> anyhow::Result<u16>
+{
+  data.get(offset..offset+2)
+    .map(|e| u16::from_le_bytes([e[0], e[1]]))
+    .ok_or(ah!("Unexpected end of data at offset {}", offset))
+} // fn: u16_at }}}
+
+// fn u32_at() {{{
+fn u32_at(data: &[u8], offset: usize) -> anyhow::Result<u32>
+{
+  data.get(offset..offset+4)
+    .map(|e| u32::from_le_bytes([e[0], e[1], e[2], e[3]]))
+    .ok_or(ah!("Unexpected end of data at offset {}", offset))
+} // fn: u32_at }}}
+
+// fn align() {{{
+fn align(offset: usize) -> usize
+{
+  (offset + 3) & !3
+} // fn: align }}}
+
+// fn read_at() {{{
+// Sizes come from the headers, they are checked against the file before allocating
+fn read_at(file: &mut std::fs::File, offset: u64, size: usize) -> anyhow::Result<Vec<u8>>
+{
+  let size_file = file.metadata()?.len();
+  if offset.checked_add(size as u64).is_none_or(|e| e > size_file)
+  {
+    return Err(ah!("Truncated executable at offset {}", offset));
+  } // if
+  let mut data = vec![0u8; size];
+  file.seek(SeekFrom::Start(offset))?;
+  file.read_exact(&mut data).map_err(|_| ah!("Truncated executable at offset {}", offset))?;
+  Ok(data)
+} // fn: read_at }}}
+
+// fn image() {{{
+// Reads the headers and the section table of the executable
+fn image(path_file: &Path) -> anyhow::Result<Image>
+{
+  let mut file = std::fs::File::open(path_file)?;
+  // Dos header, with the offset of the pe header at 0x3c
+  let dos = read_at(&mut file, 0, 64).map_err(|_| ah!("File is too small to be an executable"))?;
+  if &dos[0..2] != b"MZ" { return Err(ah!("Missing dos signature")); }
+  let offset_pe = u32_at(&dos, 0x3c)? as u64;
+  // Signature and coff header
+  let coff = read_at(&mut file, offset_pe, 24)?;
+  if &coff[0..4] != b"PE\0\0" { return Err(ah!("Missing pe signature")); }
+  let machine = u16_at(&coff, 4)?;
+  let count_sections = u16_at(&coff, 6)? as usize;
+  let timestamp = u32_at(&coff, 8)?;
+  let size_optional = u16_at(&coff, 20)? as usize;
+  // Optional header, the subsystem has the same offset on PE32 and PE32+
+  let optional = read_at(&mut file, offset_pe + 24, size_optional)?;
+  let subsystem = match u16_at(&optional, 68)?
+  {
+    2 => Subsystem::Gui,
+    3 => Subsystem::Console,
+    other => Subsystem::Other(other),
+  }; // match
+  // The data directories follow the fields of the optional header, which are larger on PE32+
+  let offset_directories = match u16_at(&optional, 0)?
+  {
+    0x10b => 96,
+    0x20b => 112,
+    magic => return Err(ah!("Unknown optional header magic {:#x}", magic)),
+  }; // match
+  let count_directories = u32_at(&optional, offset_directories - 4)?.min(16) as usize;
+  let directories = (0..count_directories)
+    .map_while(|i| Some((u32_at(&optional, offset_directories + i*8).ok()?, u32_at(&optional, offset_directories + i*8 + 4).ok()?)))
+    .collect();
+  // Sections, to map relative virtual addresses to file offsets
+  let table = read_at(&mut file, offset_pe + 24 + size_optional as u64, count_sections * 40)?;
+  let sections = (0..count_sections).map(|i| -> anyhow::Result<Section>
+  {
+    Ok(Section
+    {
+      size_virtual: u32_at(&table, i*40 + 8)?,
+      address: u32_at(&table, i*40 + 12)?,
+      size_raw: u32_at(&table, i*40 + 16)?,
+      offset_raw: u32_at(&table, i*40 + 20)?,
+    })
+  }).collect::<anyhow::Result<Vec<Section>>>()?;
+  Ok(Image{ header: Header{ machine, subsystem, timestamp }, sections, directories, file })
+} // fn: image }}}
+
+impl Image
+{
+
+// fn directory() {{{
+// Relative virtual address of the data directory, if present
+fn directory(&self, index: usize) -> Option<u32>
+{
+  self.directories.get(index).map(|e| e.0).filter(|e| *e != 0)
+} // fn: directory }}}
+
+// fn read() {{{
+// Reads up to size bytes at the relative virtual address, stops at the end of its section or of
+// the file
+fn read(&mut self, address: u32, size: usize) -> anyhow::Result<Vec<u8>>
+{
+  let section = self.sections.iter()
+    .find(|e| e.address <= address
+      && e.address.checked_add(e.size_virtual.max(e.size_raw)).is_some_and(|end| address < end))
+    .ok_or(ah!("Address {:#x} is outside of the sections", address))?;
+  let offset = (address - section.address) as usize;
+  let offset_raw = section.offset_raw as u64 + offset as u64;
+  let size_file = self.file.metadata()?.len();
+  let size = size
+    .min((section.size_raw as usize).saturating_sub(offset))
+    .min(size_file.saturating_sub(offset_raw) as usize);
+  read_at(&mut self.file, offset_raw, size)
+} // fn: read }}}
+
+// fn read_str() {{{
+// Reads a nul terminated ascii string at the relative virtual address
+fn read_str(&mut self, address: u32) -> anyhow::Result<String>
+{
+  let data = self.read(address, 256)?;
+  let end = data.iter().position(|e| *e == 0).unwrap_or(data.len());
+  Ok(String::from_utf8_lossy(&data[..end]).to_string())
+} // fn: read_str }}}
+
+// fn entries() {{{
+// Entries of the resource directory at offset, as (id, offset, is_directory), named entries have
+// no id
+fn entries(data: &[u8], offset: usize) -> anyhow::Result<Vec<(Option<u32>, usize, bool)>>
+{
+  let count = u16_at(data, offset + 12)? as usize + u16_at(data, offset + 14)? as usize;
+  (0..count).map(|i|
+  {
+    let name = u32_at(data, offset + 16 + i*8)?;
+    let target = u32_at(data, offset + 16 + i*8 + 4)?;
+    let id = if name & 0x8000_0000 == 0 { Some(name) } else { None };
+    Ok((id, (target & 0x7fff_ffff) as usize, target & 0x8000_0000 != 0))
+  }).collect()
+} // fn: entries }}}
+
+// fn resources() {{{
+// Resources of the type as (id, data), the first language of each resource is used
+fn resources(&mut self, kind: u32) -> anyhow::Result<Vec<(Option<u32>, Vec<u8>)>>
+{
+  let address = match self.directory(2)
+  {
+    Some(address) => address,
+    None => return Ok(vec![]),
+  }; // match
+  // Resource data is addressed by its relative virtual address, it follows the directory
+  let data = self.read(address, usize::MAX)?;
+  let data = data.as_slice();
+  let mut resources = vec![];
+  for (_, offset_names, _) in Image::entries(data, 0)?.into_iter().filter(|e| e.0 == Some(kind) && e.2)
+  {
+    for (id, mut offset, mut is_directory) in Image::entries(data, offset_names)?
+    {
+      // Descend to the first language, corrupt directories can refer to themselves
+      let mut depth = 2;
+      while is_directory
+      {
+        depth += 1;
+        if depth > DEPTH_RESOURCES { return Err(ah!("Resource directory is deeper than {} levels", DEPTH_RESOURCES)); }
+        (_, offset, is_directory) = *Image::entries(data, offset)?.first().ok_or(ah!("Empty resource directory"))?;
+      } // while
+      let address_data = u32_at(data, offset)?;
+      let size = u32_at(data, offset + 4)? as usize;
+      let start = address_data.checked_sub(address).ok_or(ah!("Resource data outside of its section"))? as usize;
+      let bytes = data.get(start..start+size).ok_or(ah!("Truncated resource data"))?;
+      resources.push((id, bytes.to_vec()));
+    } // for
+  } // for
+  Ok(resources)
+} // fn: resources }}}
+
+// fn imports() {{{
+// Names of the libraries in the import and delay import tables, in lowercase
+fn imports(&mut self) -> anyhow::Result<Vec<String>>
+{
+  let mut names = vec![];
+  // Import descriptors have 20 bytes with the name at 12, delay import descriptors have 32 bytes
+  // with the name at 4, both tables end with an empty descriptor
+  for (index, size, offset_name) in [(1, 20, 12), (13, 32, 4)]
+  {
+    let address = match self.directory(index)
+    {
+      Some(address) => address,
+      None => continue,
+    }; // match
+    let data = self.read(address, size * 4096)?;
+    for descriptor in data.chunks_exact(size)
+    {
+      if descriptor.iter().all(|e| *e == 0) { break; }
+      let address_name = u32_at(descriptor, offset_name)?;
+      names.push(self.read_str(address_name)?.to_lowercase());
+    } // for
+  } // for
+  names.sort();
+  names.dedup();
+  Ok(names)
+} // fn: imports }}}
+
+}
+
+// fn utf16() {{{
+// Reads a nul terminated utf16 string, returns it and the offset past the terminator
+fn utf16(data: &[u8], mut offset: usize, end: usize) -> (String, usize)
+{
+  let mut chars = vec![];
+  while offset + 2 <= end
+  {
+    let c = u16::from_le_bytes([data[offset], data[offset+1]]);
+    offset += 2;
+    if c == 0 { break; }
+    chars.push(c);
+  } // while
+  (String::from_utf16_lossy(&chars), offset)
+} // fn: utf16 }}}
+
+// fn version() {{{
+// Walks the nodes of a version resource, the strings of the StringFileInfo tables are collected by
+// key and the fixed file info is read from the root value
+fn version(data: &[u8], offset: usize, depth: usize, strings: &mut HashMap<String,String>, fixed: &mut Option<String>) -> anyhow::Result<usize>
+{
+  let length = u16_at(data, offset)? as usize;
+  let length_value = u16_at(data, offset + 2)? as usize;
+  let is_text = u16_at(data, offset + 4)? == 1;
+  let end = (offset + length).min(data.len());
+  if length < 6 { return Err(ah!("Invalid version node length {}", length)); }
+  let (key, offset_key_end) = utf16(data, offset + 6, end);
+  let offset_value = align(offset_key_end);
+  let size_value = if is_text { length_value * 2 } else { length_value };
+  match depth
+  {
+    // VS_VERSION_INFO with the VS_FIXEDFILEINFO structure
+    0 if size_value >= 16 && u32_at(data, offset_value)? == 0xfeef04bd =>
+    {
+      let ms = u32_at(data, offset_value + 8)?;
+      let ls = u32_at(data, offset_value + 12)?;
+      *fixed = Some(format!("{}.{}.{}.{}", ms >> 16, ms & 0xffff, ls >> 16, ls & 0xffff));
+    },
+    // String of a StringTable
+    3 if size_value > 0 =>
+    {
+      let (value, _) = utf16(data, offset_value, (offset_value + size_value).min(end));
+      strings.insert(key.clone(), value.trim().to_string());
+    },
+    _ => (),
+  } // match
+  // Only the StringFileInfo branch has strings, the VarFileInfo branch is skipped
+  if depth < 3 && (depth != 1 || key == "StringFileInfo")
+  {
+    let mut offset_child = align(offset_value + size_value);
+    while offset_child + 6 <= end
+    {
+      offset_child = align(version(data, offset_child, depth + 1, strings, fixed)?);
+    } // while
+  } // if
+  Ok(offset + length)
+} // fn: version }}}
+
+// pub fn header() {{{
+// Reads the coff and optional headers of the executable
+pub fn header(path_file: &Path) -> anyhow::Result<Header>
+{
+  Ok(image(path_file)?.header)
+} // fn: header }}}
+
+// pub fn imports() {{{
+// Libraries imported by the executable, in lowercase
+pub fn imports(path_file: &Path) -> anyhow::Result<Vec<String>>
+{
+  image(path_file)?.imports()
+} // fn: imports }}}
+
+// pub fn dependencies() {{{
+// Libraries imported by the executable and by the libraries next to it, except for the ones
+// shipped with the game
+pub fn dependencies(path_file: &Path) -> anyhow::Result<Vec<String>>
+{
+  let path_dir = path_file.parent().ok_or(ah!("Executable has no parent directory"))?;
+  let siblings : Vec<std::path::PathBuf> = std::fs::read_dir(path_dir)?
+    .filter_map(|e| e.ok().map(|e| e.path()))
+    .filter(|e| e.is_file() && e.extension().is_some_and(|e| e.eq_ignore_ascii_case("dll")))
+    .collect();
+  let shipped : Vec<String> = siblings.iter()
+    .filter_map(|e| e.file_name().map(|e| e.to_string_lossy().to_lowercase()))
+    .collect();
+  let mut names = imports(path_file)?;
+  // Broken libraries are ignored, they do not prevent the game from being configured
+  siblings.iter().filter_map(|e| imports(e).ok()).for_each(|e| names.extend(e));
+  names.retain(|e| ! shipped.contains(e));
+  names.sort();
+  names.dedup();
+  Ok(names)
+} // fn: dependencies }}}
+
+// pub fn metadata() {{{
+// Reads the version resource and the build information of the executable
+pub fn metadata(path_file: &Path) -> anyhow::Result<Metadata>
+{
+  let mut image = image(path_file)?;
+  let mut strings = HashMap::new();
+  let mut fixed = None;
+  if let Some((_, data)) = image.resources(RT_VERSION)?.first()
+  {
+    version(data, 0, 0, &mut strings, &mut fixed)?;
+  } // if
+  let mut f_string = |key: &str| strings.remove(key).filter(|e| ! e.is_empty());
+  Ok(Metadata
+  {
+    product_name: f_string("ProductName").or_else(|| f_string("FileDescription")),
+    company_name: f_string("CompanyName"),
+    file_version: f_string("FileVersion").or(fixed),
+    timestamp: image.header.timestamp,
+    machine: image.header.machine,
+  })
+} // fn: metadata }}}
+
+// pub fn icon() {{{
+// Extracts the largest image of the first icon group as png
+pub fn icon(path_file: &Path) -> anyhow::Result<Vec<u8>>
+{
+  let mut image = image(path_file)?;
+  let groups = image.resources(RT_GROUP_ICON)?;
+  let (_, group) = groups.first().ok_or(ah!("Executable has no icon"))?;
+  // Entries of the group, a width of zero means 256 pixels
+  let count = u16_at(group, 4)? as usize;
+  let entry = (0..count)
+    .map(|i| 6 + i*14)
+    .filter(|e| e + 14 <= group.len())
+    .max_by_key(|e|
+    {
+      let width = match group[*e] { 0 => 256, width => width as u32 };
+      (width, u16_at(group, e + 6).unwrap_or(0))
+    })
+    .ok_or(ah!("Icon group is empty"))?;
+  let id = u16_at(group, entry + 12)? as u32;
+  let icons = image.resources(RT_ICON)?;
+  let (_, data) = icons.iter().find(|e| e.0 == Some(id)).ok_or(ah!("Icon {} not found", id))?;
+  // Icons are stored as png or as an ico entry without its file header
+  if data.starts_with(b"\x89PNG") { return Ok(data.clone()); }
+  let mut ico = vec![0, 0, 1, 0, 1, 0];
+  ico.extend_from_slice(&group[entry..entry+8]);
+  ico.extend_from_slice(&(data.len() as u32).to_le_bytes());
+  ico.extend_from_slice(&22u32.to_le_bytes());
+  ico.extend_from_slice(data);
+  let decoded = image::load_from_memory_with_format(&ico, image::ImageFormat::Ico)?;
+  let mut png = std::io::Cursor::new(vec![]);
+  decoded.write_to(&mut png, image::ImageOutputFormat::Png)?;
+  Ok(png.into_inner())
+} // fn: icon }}}
+
+// pub fn stub() {{{
+// Minimal executable with the given machine, subsystem, resources, as (type, id, data), and imported
+// libraries
+#[cfg(test)]
+pub fn stub(machine: u16, subsystem: u16, resources: &[(u32, u32, Vec<u8>)], imports: &[&str]) -> Vec<u8>
+{
+  const OFFSET_PE: usize = 0x80;
+  const OFFSET_SECTION: usize = 0x200;
+  const ADDRESS_SECTION: u32 = 0x1000;
+  let offset_optional = OFFSET_PE + 24;
+  let offset_table = offset_optional + 224;
+  // Resource tree with a single name and language per type
+  let mut rsrc = vec![0u8; 16 + resources.len() * (8 + 64)];
+  rsrc[14..16].copy_from_slice(&(resources.len() as u16).to_le_bytes());
+  for (i, (kind, id, data)) in resources.iter().enumerate()
+  {
+    let offset_names = 16 + resources.len()*8 + i*64;
+    let offset_languages = offset_names + 24;
+    let offset_data = offset_languages + 24;
+    let address_data = ADDRESS_SECTION + rsrc.len() as u32;
+    let mut f_write = |offset: usize, value: u32| rsrc[offset..offset+4].copy_from_slice(&value.to_le_bytes());
+    f_write(16 + i*8, *kind);
+    f_write(16 + i*8 + 4, 0x8000_0000 | offset_names as u32);
+    f_write(offset_names + 12, 1 << 16);
+    f_write(offset_names + 16, *id);
+    f_write(offset_names + 20, 0x8000_0000 | offset_languages as u32);
+    f_write(offset_languages + 12, 1 << 16);
+    f_write(offset_languages + 16, 0x409);
+    f_write(offset_languages + 20, offset_data as u32);
+    f_write(offset_data, address_data);
+    f_write(offset_data + 4, data.len() as u32);
+    rsrc.extend_from_slice(data);
+    rsrc.resize(align(rsrc.len()), 0);
+  } // for
+  // Import descriptors after the resources, followed by the library names
+  let size_resources = rsrc.len();
+  let size_descriptors = (imports.len() + 1) * 20;
+  rsrc.resize(size_resources + size_descriptors, 0);
+  for (i, name) in imports.iter().enumerate()
+  {
+    let address_name = ADDRESS_SECTION + rsrc.len() as u32;
+    let offset_descriptor = size_resources + i*20;
+    rsrc[offset_descriptor+12..offset_descriptor+16].copy_from_slice(&address_name.to_le_bytes());
+    rsrc.extend_from_slice(name.as_bytes());
+    rsrc.push(0);
+  } // for
+  rsrc.resize(align(rsrc.len()), 0);
+  let mut data = vec![0u8; OFFSET_SECTION];
+  let mut f_write = |offset: usize, bytes: &[u8]| data[offset..offset+bytes.len()].copy_from_slice(bytes);
+  f_write(0, b"MZ");
+  f_write(0x3c, &(OFFSET_PE as u32).to_le_bytes());
+  f_write(OFFSET_PE, b"PE\0\0");
+  f_write(OFFSET_PE + 4, &machine.to_le_bytes());
+  f_write(OFFSET_PE + 6, &1u16.to_le_bytes());
+  f_write(OFFSET_PE + 8, &1_000_000_000u32.to_le_bytes());
+  f_write(OFFSET_PE + 20, &224u16.to_le_bytes());
+  f_write(offset_optional, &0x10bu16.to_le_bytes());
+  f_write(offset_optional + 68, &subsystem.to_le_bytes());
+  f_write(offset_optional + 92, &16u32.to_le_bytes());
+  if ! imports.is_empty()
+  {
+    f_write(offset_optional + 96 + 8, &(ADDRESS_SECTION + size_resources as u32).to_le_bytes());
+    f_write(offset_optional + 96 + 12, &(size_descriptors as u32).to_le_bytes());
+  } // if
+  if ! resources.is_empty()
+  {
+    f_write(offset_optional + 96 + 16, &ADDRESS_SECTION.to_le_bytes());
+    f_write(offset_optional + 96 + 20, &(size_resources as u32).to_le_bytes());
+  } // if
+  f_write(offset_table, b".rsrc");
+  f_write(offset_table + 8, &(rsrc.len() as u32).to_le_bytes());
+  f_write(offset_table + 12, &ADDRESS_SECTION.to_le_bytes());
+  f_write(offset_table + 16, &(rsrc.len() as u32).to_le_bytes());
+  f_write(offset_table + 20, &(OFFSET_SECTION as u32).to_le_bytes());
+  data.extend_from_slice(&rsrc);
+  data
+} // fn: stub }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{dependencies, header, icon, imports, metadata, read_at, stub, Header, Subsystem, RT_ICON, RT_GROUP_ICON, RT_VERSION};
+  use crate::testing::TempDir;
+
+  // fn node() {{{
+  // Node of a version resource
+  fn node(key: &str, is_text: bool, value: &[u8], children: Vec<Vec<u8>>) -> Vec<u8>
+  {
+    let mut data = vec![0u8; 6];
+    key.encode_utf16().chain([0]).for_each(|e| data.extend_from_slice(&e.to_le_bytes()));
+    data.resize(super::align(data.len()), 0);
+    data.extend_from_slice(value);
+    for child in children
+    {
+      data.resize(super::align(data.len()), 0);
+      data.extend_from_slice(&child);
+    } // for
+    let length_value = if is_text { value.len() / 2 } else { value.len() };
+    let length = data.len() as u16;
+    data[0..2].copy_from_slice(&length.to_le_bytes());
+    data[2..4].copy_from_slice(&(length_value as u16).to_le_bytes());
+    data[4..6].copy_from_slice(&(is_text as u16).to_le_bytes());
+    data
+  } // fn: node }}}
+
+  // fn string() {{{
+  fn string(key: &str, value: &str) -> Vec<u8>
+  {
+    let value : Vec<u8> = value.encode_utf16().chain([0]).flat_map(|e| e.to_le_bytes()).collect();
+    node(key, true, &value, vec![])
+  } // fn: string }}}
+
+  // fn version() {{{
+  fn version() -> Vec<u8>
+  {
+    let mut fixed = vec![0u8; 52];
+    fixed[0..4].copy_from_slice(&0xfeef04bdu32.to_le_bytes());
+    fixed[8..12].copy_from_slice(&((1u32 << 16) | 2).to_le_bytes());
+    fixed[12..16].copy_from_slice(&((3u32 << 16) | 4).to_le_bytes());
+    node("VS_VERSION_INFO", false, &fixed, vec![
+      node("VarFileInfo", true, &[], vec![node("Translation", false, &[9, 4, 176, 4], vec![])]),
+      node("StringFileInfo", true, &[], vec![
+        node("040904b0", true, &[], vec![
+          string("CompanyName", "Id Software"),
+          string("ProductName", "Quake III Arena"),
+        ]),
+      ]),
+    ])
+  } // fn: version }}}
+
+  #[test]
+  fn header_reads_machine_and_subsystem()
+  {
+    let dir = TempDir::new();
+    let path_file_gui = dir.path.join("game.exe");
+    let path_file_console = dir.path.join("server.exe");
+    std::fs::write(&path_file_gui, stub(0x8664, 2, &[], &[])).unwrap();
+    std::fs::write(&path_file_console, stub(0x14c, 3, &[], &[])).unwrap();
+    assert_eq!(header(&path_file_gui).unwrap(), Header{ machine: 0x8664, subsystem: Subsystem::Gui, timestamp: 1_000_000_000 });
+    assert_eq!(header(&path_file_console).unwrap().subsystem, Subsystem::Console);
+  }
+
+  #[test]
+  fn header_rejects_other_files()
+  {
+    let dir = TempDir::new();
+    let path_file_text = dir.path.join("readme.exe");
+    std::fs::write(&path_file_text, "This is not an executable, but it is long enough to have a dos header").unwrap();
+    assert!(header(&path_file_text).is_err());
+    let mut data = stub(0x14c, 2, &[], &[]);
+    data.truncate(0x90);
+    std::fs::write(&path_file_text, data).unwrap();
+    assert!(header(&path_file_text).is_err());
+  }
+
+  #[test]
+  fn metadata_reads_version_resource()
+  {
+    let dir = TempDir::new();
+    let path_file = dir.path.join("quake3.exe");
+    std::fs::write(&path_file, stub(0x14c, 2, &[(RT_VERSION, 1, version())], &[])).unwrap();
+    let info = metadata(&path_file).unwrap();
+    assert_eq!(info.product_name.as_deref(), Some("Quake III Arena"));
+    assert_eq!(info.company_name.as_deref(), Some("Id Software"));
+    assert_eq!(info.file_version.as_deref(), Some("1.2.3.4"));
+    assert_eq!(info.arch(), "x86");
+    assert_eq!(info.year(), Some(2001));
+    // Without resources
+    std::fs::write(&path_file, stub(0x8664, 2, &[], &[])).unwrap();
+    let info = metadata(&path_file).unwrap();
+    assert_eq!(info.product_name, None);
+    assert!(info.is_64bit());
+  }
+
+  #[test]
+  fn icon_is_converted_to_png()
+  {
+    let dir = TempDir::new();
+    // Icon stored as a bitmap, 2x2 pixels with 32 bits per pixel and an empty mask
+    let mut bitmap = vec![0u8; 40];
+    bitmap[0..4].copy_from_slice(&40u32.to_le_bytes());
+    bitmap[4..8].copy_from_slice(&2u32.to_le_bytes());
+    bitmap[8..12].copy_from_slice(&4u32.to_le_bytes());
+    bitmap[12..14].copy_from_slice(&1u16.to_le_bytes());
+    bitmap[14..16].copy_from_slice(&32u16.to_le_bytes());
+    bitmap.extend_from_slice(&[0, 0, 255, 255].repeat(4));
+    bitmap.extend_from_slice(&[0u8; 8]);
+    // Group with a smaller entry first, the largest one is extracted
+    let mut group = vec![0, 0, 1, 0, 2, 0];
+    group.extend_from_slice(&[1, 1, 0, 0, 1, 0, 32, 0, 0, 0, 0, 0, 9, 0]);
+    group.extend_from_slice(&[2, 2, 0, 0, 1, 0, 32, 0]);
+    group.extend_from_slice(&(bitmap.len() as u32).to_le_bytes());
+    group.extend_from_slice(&[7, 0]);
+    let path_file = dir.path.join("game.exe");
+    std::fs::write(&path_file, stub(0x14c, 2, &[(RT_ICON, 7, bitmap), (RT_GROUP_ICON, 1, group)], &[])).unwrap();
+    let png = icon(&path_file).unwrap();
+    let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap().to_rgba8();
+    assert_eq!(decoded.dimensions(), (2, 2));
+    assert_eq!(decoded.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    // Without icons
+    std::fs::write(&path_file, stub(0x14c, 2, &[], &[])).unwrap();
+    assert!(icon(&path_file).is_err());
+  }
+
+  #[test]
+  fn imports_skip_shipped_libraries()
+  {
+    let dir = TempDir::new();
+    let path_file = dir.path.join("Game.exe");
+    std::fs::write(&path_file, stub(0x14c, 2, &[(RT_VERSION, 1, version())], &["KERNEL32.dll", "d3dx9_43.dll", "Engine.dll"])).unwrap();
+    std::fs::write(dir.path.join("engine.DLL"), stub(0x14c, 2, &[], &["MSVCP140.dll", "xinput1_3.dll"])).unwrap();
+    std::fs::write(dir.path.join("xinput1_3.dll"), stub(0x14c, 2, &[], &[])).unwrap();
+    std::fs::write(dir.path.join("broken.dll"), "not a library").unwrap();
+    assert_eq!(imports(&path_file).unwrap(), vec!["d3dx9_43.dll", "engine.dll", "kernel32.dll"]);
+    assert_eq!(dependencies(&path_file).unwrap(), vec!["d3dx9_43.dll", "kernel32.dll", "msvcp140.dll"]);
+  }
+
+  #[test]
+  fn corrupt_headers_fail_without_hanging_or_allocating()
+  {
+    let dir = TempDir::new();
+    let path_file = dir.path.join("game.exe");
+    let data = stub(0x14c, 2, &[(RT_VERSION, 1, version())], &[]);
+    // Offsets of the section table and of the resource tree in the file
+    let offset_table = 0x80 + 24 + 224;
+    let offset_rsrc = 0x200;
+    let f_write = |offset: usize, value: u32|
+    {
+      let mut data = data.clone();
+      data[offset..offset+4].copy_from_slice(&value.to_le_bytes());
+      std::fs::write(&path_file, data).unwrap();
+    };
+    // Language directory which refers to itself
+    f_write(offset_rsrc + 48 + 20, 0x8000_0000 | 48);
+    let e = metadata(&path_file).unwrap_err();
+    assert!(e.to_string().contains("deeper"), "{}", e);
+    // Section which ends past the address space
+    f_write(offset_table + 12, 0xffff_ff00);
+    assert!(metadata(&path_file).is_err());
+    // Section larger than the file
+    f_write(offset_table + 16, 0x7fff_fff0);
+    assert_eq!(metadata(&path_file).unwrap().product_name.as_deref(), Some("Quake III Arena"));
+    let mut file = std::fs::File::open(&path_file).unwrap();
+    assert!(read_at(&mut file, 0, usize::MAX).is_err());
+    assert!(read_at(&mut file, u64::MAX, 16).is_err());
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,271 @@
+// Ranking of the executables of a wine project, the most likely main binary comes first
+use std::path::{Path,PathBuf};
+
+use shared::std::PathBufExt;
+
+use crate::lib::pe;
+
+// pub enum Category {{{
+// Kind of executable guessed from its name and location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category
+{
+  Main,
+  Installer,
+  Uninstaller,
+  Redist,
+  Launcher,
+  CrashReporter,
+} // enum Category }}}
+
+// impl Category {{{
+impl Category
+{
+  pub fn as_str(&self) -> &'static str
+  {
+    match self
+    {
+      Category::Main          => "Main",
+      Category::Installer     => "Installer",
+      Category::Uninstaller   => "Uninstaller",
+      Category::Redist        => "Redistributable",
+      Category::Launcher      => "Launcher or tool",
+      Category::CrashReporter => "Crash reporter",
+    } // match
+  } // fn: as_str
+
+  // Categories which can be hidden from the list of executables
+  pub fn noise() -> [Category; 5]
+  {
+    [Category::Installer, Category::Uninstaller, Category::Redist, Category::Launcher, Category::CrashReporter]
+  } // fn: noise
+
+  // Score added to executables of the category
+  fn weight(&self) -> i64
+  {
+    match self
+    {
+      Category::Main          => 0,
+      Category::Launcher      => -20,
+      Category::Installer     => -60,
+      Category::Redist        => -80,
+      Category::CrashReporter => -80,
+      Category::Uninstaller   => -100,
+    } // match
+  } // fn: weight
+} // impl Category }}}
+
+// pub struct Candidate {{{
+#[derive(Debug, Clone)]
+pub struct Candidate
+{
+  pub path: PathBuf,
+  pub category: Category,
+  pub score: i64,
+} // struct Candidate }}}
+
+// Name patterns of each category, matched against whole words of the file stem, a pattern also
+// matches consecutive words, e.g., 'vcredist' matches 'VC_redist' and 'crashhandler' matches
+// 'UnityCrashHandler64', but 'crashhandler' does not match 'CrashBandicoot'
+const PATTERNS_UNINSTALLER: [&str; 4] = ["unins", "uninst", "uninstall", "remove"];
+const PATTERNS_REDIST: [&str; 12] = ["vcredist", "dxsetup", "dxwebsetup", "dotnetfx", "ndp", "physx"
+  , "oalinst", "xnafx", "prereq", "prerequisites", "windowsdesktopruntime", "redist"];
+const PATTERNS_CRASH: [&str; 11] = ["crashhandler", "crashreporter", "crashreport", "crashpad"
+  , "crashsender", "bugreport", "bugreporter", "bugsplat", "errorreporter", "sentry", "minidump"];
+const PATTERNS_INSTALLER: [&str; 4] = ["setup", "install", "installer", "patch"];
+const PATTERNS_LAUNCHER: [&str; 8] = ["launcher", "updater", "update", "config", "settings"
+  , "editor", "benchmark", "touchup"];
+// Directories which only contain redistributables
+const PATTERNS_DIR_REDIST: [&str; 5] = ["redist", "_commonredist", "directx", "vcredist", "support"];
+
+// fn normalize() {{{
+// Lowercase alphanumeric characters of the string
+fn normalize(value: &str) -> String
+{
+  value.chars().filter(|e| e.is_alphanumeric()).flat_map(|e| e.to_lowercase()).collect()
+} // fn: normalize }}}
+
+// fn words() {{{
+// Lowercase words of the string, split at separators, case changes and digits, e.g.,
+// 'DXSetup_v2' is 'dx', 'setup', 'v', '2'
+fn words(value: &str) -> Vec<String>
+{
+  let chars : Vec<char> = value.chars().collect();
+  let mut words : Vec<String> = vec![];
+  let mut word = String::new();
+  for (i, c) in chars.iter().copied().enumerate()
+  {
+    if ! c.is_alphanumeric()
+    {
+      if ! word.is_empty() { words.push(std::mem::take(&mut word)); } // if
+      continue;
+    } // if
+    let is_boundary = match i.checked_sub(1).map(|i| chars[i]).filter(|e| e.is_alphanumeric())
+    {
+      Some(prev) =>
+        (prev.is_lowercase() && c.is_uppercase())
+        || (prev.is_numeric() != c.is_numeric())
+        // End of an acronym, e.g., the 'S' in 'DXSetup'
+        || (prev.is_uppercase() && c.is_uppercase() && chars.get(i+1).is_some_and(|e| e.is_lowercase())),
+      None => false,
+    }; // match
+    if is_boundary && ! word.is_empty() { words.push(std::mem::take(&mut word)); } // if
+    word.extend(c.to_lowercase());
+  } // for
+  if ! word.is_empty() { words.push(word); } // if
+  words
+} // fn: words }}}
+
+// pub fn classify() {{{
+// Guesses the category of the executable from its file name and parent directories
+pub fn classify(path_file: &Path) -> Category
+{
+  let words = words(&path_file.file_stem().map(|e| e.to_string_lossy().to_string()).unwrap_or_default());
+  // Any run of consecutive words equal to a pattern
+  let f_matches = |patterns: &[&str]| (0..words.len()).any(|i| (i+1..=words.len())
+    .any(|j| patterns.contains(&words[i..j].concat().as_str())));
+  let is_dir_redist = path_file.parent()
+    .map(|e| e.components().any(|e| PATTERNS_DIR_REDIST.contains(&e.as_os_str().to_string_lossy().to_lowercase().as_str())))
+    .unwrap_or(false);
+  // Order matters, e.g., 'dxsetup' also matches the installer patterns
+  if f_matches(&PATTERNS_UNINSTALLER) { Category::Uninstaller }
+  else if is_dir_redist || f_matches(&PATTERNS_REDIST) { Category::Redist }
+  else if f_matches(&PATTERNS_CRASH) { Category::CrashReporter }
+  else if f_matches(&PATTERNS_INSTALLER) { Category::Installer }
+  else if f_matches(&PATTERNS_LAUNCHER) { Category::Launcher }
+  else { Category::Main }
+} // fn: classify }}}
+
+// fn similarity() {{{
+// Score from 0 to 100 of how close the file stem is to the project name
+fn similarity(name: &str, stem: &str) -> i64
+{
+  let name = normalize(name);
+  let stem = normalize(stem);
+  if name.is_empty() || stem.is_empty() { return 0; }
+  if name == stem { return 100; }
+  if name.contains(&stem) || stem.contains(&name)
+  {
+    let len_min = name.len().min(stem.len()) as i64;
+    let len_max = name.len().max(stem.len()) as i64;
+    return 50 + 40 * len_min / len_max;
+  } // if
+  // Length of the longest common prefix, catches abbreviated names, e.g., 'halflife' and 'hl'
+  let len_prefix = name.chars().zip(stem.chars()).take_while(|(a,b)| a == b).count() as i64;
+  40 * len_prefix / name.len().min(stem.len()) as i64
+} // fn: similarity }}}
+
+// fn score() {{{
+fn score(name: &str, path_dir_root: &Path, path_file: &Path, category: Category) -> i64
+{
+  let stem = path_file.file_stem().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+  let path_file_absolute = path_dir_root.join(path_file);
+  let mut score = category.weight() + similarity(name, &stem);
+  // Main binaries tend to be the largest ones, two points per doubling of the size up to 1GiB
+  let size = std::fs::metadata(&path_file_absolute).map(|e| e.len()).unwrap_or(0);
+  score += 2 * (64 - size.max(1).leading_zeros() as i64).min(30);
+  // Games are gui applications, scripts and other non-executables are unlikely to be the main one
+  score += match pe::header(&path_file_absolute).map(|e| e.subsystem)
+  {
+    Ok(pe::Subsystem::Gui) => 20,
+    Ok(pe::Subsystem::Console) => -10,
+    Ok(pe::Subsystem::Other(_)) => -20,
+    Err(_) => -30,
+  }; // match
+  // Binaries closer to the root are preferred
+  score -= 2 * path_file.components().count() as i64;
+  score
+} // fn: score }}}
+
+// pub fn rank() {{{
+// Scores the executables, paths are relative to path_dir_root, the best candidate comes first
+pub fn rank(name: &str, path_dir_root: &Path, paths: Vec<PathBuf>) -> Vec<Candidate>
+{
+  let mut candidates : Vec<Candidate> = paths.into_iter().map(|path|
+  {
+    let category = classify(&path);
+    let score = score(name, path_dir_root, &path, category);
+    Candidate{ path, category, score }
+  }).collect();
+  // Ties keep the shorter paths first
+  candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.string().len().cmp(&b.path.string().len())));
+  candidates
+} // fn: rank }}}
+
+#[cfg(test)]
+mod tests
+{
+  use std::path::{Path,PathBuf};
+
+  use super::{classify, rank, Category};
+  use crate::lib::pe;
+  use crate::testing::TempDir;
+
+  // fn write() {{{
+  fn write(path_dir_root: &Path, path_file: &str, subsystem: u16, size: usize)
+  {
+    let path_file = path_dir_root.join(path_file);
+    std::fs::create_dir_all(path_file.parent().unwrap()).unwrap();
+    let mut data = pe::stub(0x14c, subsystem, &[], &[]);
+    data.resize(data.len().max(size), 0);
+    std::fs::write(path_file, data).unwrap();
+  } // fn: write }}}
+
+  #[test]
+  fn classify_known_patterns()
+  {
+    assert_eq!(classify(Path::new("Game/unins000.exe")), Category::Uninstaller);
+    assert_eq!(classify(Path::new("Game/_CommonRedist/DirectX/DXSETUP.exe")), Category::Redist);
+    assert_eq!(classify(Path::new("Game/redist/something.exe")), Category::Redist);
+    assert_eq!(classify(Path::new("Game/vcredist_x86.exe")), Category::Redist);
+    assert_eq!(classify(Path::new("Game/UnityCrashHandler64.exe")), Category::CrashReporter);
+    assert_eq!(classify(Path::new("setup.exe")), Category::Installer);
+    assert_eq!(classify(Path::new("Game/Launcher.exe")), Category::Launcher);
+    assert_eq!(classify(Path::new("Game/Game.exe")), Category::Main);
+    // Whole words only
+    assert_eq!(classify(Path::new("Game/UNINS000.exe")), Category::Uninstaller);
+    assert_eq!(classify(Path::new("Game/dotNetFx40_Full_setup.exe")), Category::Redist);
+    assert_eq!(classify(Path::new("Game/VC_redist.x64.exe")), Category::Redist);
+    assert_eq!(classify(Path::new("Game/CrashBandicoot.exe")), Category::Main);
+    assert_eq!(classify(Path::new("Game/Patchwork.exe")), Category::Main);
+    assert_eq!(classify(Path::new("Game/Dumpy.exe")), Category::Main);
+  }
+
+  #[test]
+  fn rank_prefers_main_binary()
+  {
+    let dir = TempDir::new();
+    write(&dir.path, "drive_c/Games/Quake/unins000.exe", 2, 1 << 20);
+    write(&dir.path, "drive_c/Games/Quake/QuakeCrashReporter.exe", 2, 1 << 16);
+    write(&dir.path, "drive_c/Games/Quake/Quake.exe", 2, 1 << 22);
+    write(&dir.path, "drive_c/Games/Quake/tool.exe", 3, 1 << 22);
+    write(&dir.path, "drive_c/Games/Quake/QuakeLauncher.exe", 2, 1 << 18);
+    write(&dir.path, "drive_c/Games/Quake/redist/vcredist_x64.exe", 2, 1 << 24);
+    let paths = ["unins000.exe", "QuakeCrashReporter.exe", "tool.exe", "QuakeLauncher.exe", "redist/vcredist_x64.exe", "Quake.exe"]
+      .iter()
+      .map(|e| PathBuf::from("drive_c/Games/Quake").join(e))
+      .collect();
+    let candidates = rank("quake", &dir.path, paths);
+    let names : Vec<String> = candidates.iter()
+      .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+      .collect();
+    assert_eq!(names[0], "Quake.exe");
+    assert_eq!(names[1], "QuakeLauncher.exe");
+    assert_eq!(names.last().unwrap(), "unins000.exe");
+    assert_eq!(candidates[0].category, Category::Main);
+  }
+
+  #[test]
+  fn rank_gui_over_console()
+  {
+    let dir = TempDir::new();
+    write(&dir.path, "a/game.exe", 3, 1 << 20);
+    write(&dir.path, "a/play.exe", 2, 1 << 20);
+    let candidates = rank("Other Name", &dir.path, vec!["a/game.exe".into(), "a/play.exe".into(), "a/missing.exe".into()]);
+    assert_eq!(candidates.iter().map(|e| e.path.clone()).collect::<Vec<_>>()
+      , vec![PathBuf::from("a/play.exe"), PathBuf::from("a/game.exe"), PathBuf::from("a/missing.exe")]
+    );
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
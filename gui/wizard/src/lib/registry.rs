@@ -0,0 +1,295 @@
+// Reader of the registry files of a wine prefix and writer of the fragments imported with regedit
+use std::collections::HashMap;
+use std::path::Path;
+
+// pub enum Value {{{
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value
+{
+  Str(String),
+  Dword(u32),
+  Other(String), // binary and multi-string data, kept as written in the file
+} // enum Value }}}
+
+// pub struct Hive {{{
+// Values of a registry file, e.g., user.reg, keys and value names are compared in lowercase
+#[derive(Debug, Clone, Default)]
+pub struct Hive
+{
+  keys: HashMap<String, HashMap<String, Value>>,
+} // struct Hive }}}
+
+// fn unescape() {{{
+// Contents of a quoted string with the escape sequences replaced
+fn unescape(src: &str) -> String
+{
+  let mut dst = String::new();
+  let mut chars = src.chars();
+  while let Some(c) = chars.next()
+  {
+    if c != '\\' { dst.push(c); continue; } // if
+    match chars.next()
+    {
+      Some('n') => dst.push('\n'),
+      Some('r') => dst.push('\r'),
+      Some('t') => dst.push('\t'),
+      Some('0') => dst.push('\0'),
+      Some(c) => dst.push(c),
+      None => dst.push('\\'),
+    } // match
+  } // while
+  dst
+} // fn: unescape }}}
+
+// fn escape() {{{
+fn escape(src: &str) -> String
+{
+  src.replace('\\', "\\\\").replace('"', "\\\"")
+} // fn: escape }}}
+
+// fn split_quoted() {{{
+// Splits '"name"=rest' into the unescaped name and rest
+fn split_quoted(src: &str) -> Option<(String, &str)>
+{
+  let src = src.strip_prefix('"')?;
+  let mut is_escaped = false;
+  for (i, c) in src.char_indices()
+  {
+    match c
+    {
+      '\\' if ! is_escaped => is_escaped = true,
+      '"' if ! is_escaped => return Some((unescape(&src[..i]), &src[i+1..])),
+      _ => is_escaped = false,
+    } // match
+  } // for
+  None
+} // fn: split_quoted }}}
+
+// fn parse_value() {{{
+fn parse_value(data: &str) -> Value
+{
+  if let Some(hex) = data.strip_prefix("dword:")
+    && let Ok(dword) = u32::from_str_radix(hex.trim(), 16)
+  {
+    return Value::Dword(dword);
+  } // if
+  match split_quoted(data.strip_prefix("str(2):").unwrap_or(data))
+  {
+    Some((value, _)) => Value::Str(value),
+    None => Value::Other(data.to_string()),
+  } // match
+} // fn: parse_value }}}
+
+impl Hive
+{
+
+// parse() {{{
+pub fn parse(src: &str) -> Hive
+{
+  let mut hive = Hive::default();
+  let mut key : Option<String> = None;
+  let mut lines = src.lines();
+  while let Some(line) = lines.next()
+  {
+    let mut line = line.trim().to_string();
+    // Binary data continues in the next lines
+    while line.ends_with('\\') && ! line.contains("=\"")
+      && let Some(next) = lines.next()
+    {
+      line.pop();
+      line.push_str(next.trim());
+    } // while
+    if let Some(section) = line.strip_prefix('[')
+    {
+      // Key names have their backslashes doubled, e.g., '[Software\\Wine] 1700000000'
+      key = section.rfind(']').map(|end| section[..end].replace("\\\\", "\\").to_lowercase());
+      if let Some(key) = key.as_ref() { hive.keys.entry(key.clone()).or_default(); } // if
+      continue;
+    } // if
+    let (name, data) = match line.strip_prefix("@=")
+    {
+      Some(data) => (String::new(), data.to_string()),
+      None => match split_quoted(&line).and_then(|(name, rest)| rest.strip_prefix('=').map(|e| (name, e.to_string())))
+      {
+        Some(entry) => entry,
+        None => continue,
+      }, // match
+    }; // match
+    if let Some(key) = key.as_ref()
+    {
+      hive.keys.entry(key.clone()).or_default().insert(name.to_lowercase(), parse_value(&data));
+    } // if
+  } // while
+  hive
+} // parse() }}}
+
+// read() {{{
+pub fn read(path_file_reg: &Path) -> anyhow::Result<Hive>
+{
+  Ok(Hive::parse(&std::fs::read_to_string(path_file_reg)?))
+} // read() }}}
+
+// get() {{{
+pub fn get(&self, key: &str, name: &str) -> Option<&Value>
+{
+  self.keys.get(&key.to_lowercase()).and_then(|e| e.get(&name.to_lowercase()))
+} // get() }}}
+
+// get_str() {{{
+pub fn get_str(&self, key: &str, name: &str) -> Option<&str>
+{
+  match self.get(key, name)
+  {
+    Some(Value::Str(value)) => Some(value.as_str()),
+    _ => None,
+  } // match
+} // get_str() }}}
+
+// values() {{{
+// Values of the key, with their names in lowercase
+pub fn values(&self, key: &str) -> Vec<(String, Value)>
+{
+  self.keys.get(&key.to_lowercase())
+    .map(|e| e.iter().map(|(k,v)| (k.clone(), v.clone())).collect())
+    .unwrap_or_default()
+} // values() }}}
+
+}
+
+// pub struct Fragment {{{
+// Values to set or delete, in the format imported by regedit
+#[derive(Debug, Clone, Default)]
+pub struct Fragment
+{
+  entries: Vec<(String, String, Option<Value>)>, // full key, value name, value or none to delete it
+} // struct Fragment }}}
+
+impl Fragment
+{
+
+// set() {{{
+// Sets the value of a full key, e.g., 'HKEY_CURRENT_USER\Software\Wine'
+pub fn set(&mut self, key: &str, name: &str, value: Value)
+{
+  self.entries.push((key.to_string(), name.to_string(), Some(value)));
+} // set() }}}
+
+// delete() {{{
+pub fn delete(&mut self, key: &str, name: &str)
+{
+  self.entries.push((key.to_string(), name.to_string(), None));
+} // delete() }}}
+
+// is_empty() {{{
+pub fn is_empty(&self) -> bool
+{
+  self.entries.is_empty()
+} // is_empty() }}}
+
+// is_applied() {{{
+// Checks if the hive of the root key, e.g., 'HKEY_CURRENT_USER', has the values of the fragment.
+// Values under other root keys are not checked
+pub fn is_applied(&self, root: &str, hive: &Hive) -> bool
+{
+  self.entries.iter()
+    .filter_map(|(key, name, value)| Some((key.strip_prefix(root)?.strip_prefix('\\')?, name, value)))
+    .all(|(key, name, value)| hive.get(key, name) == value.as_ref())
+} // is_applied() }}}
+
+// to_reg() {{{
+// Contents of the .reg file, values are grouped by key in the order the keys were first used
+pub fn to_reg(&self) -> String
+{
+  let mut keys : Vec<&str> = vec![];
+  for (key, _, _) in self.entries.iter()
+  {
+    if ! keys.contains(&key.as_str()) { keys.push(key); } // if
+  } // for
+  let mut reg = String::from("Windows Registry Editor Version 5.00\n");
+  for key in keys
+  {
+    reg.push_str(&format!("\n[{}]\n", key));
+    for (_, name, value) in self.entries.iter().filter(|e| e.0 == key)
+    {
+      let data = match value
+      {
+        Some(Value::Str(value)) => format!("\"{}\"", escape(value)),
+        Some(Value::Dword(value)) => format!("dword:{:08x}", value),
+        Some(Value::Other(value)) => value.clone(),
+        None => "-".to_string(),
+      }; // match
+      reg.push_str(&format!("\"{}\"={}\n", escape(name), data));
+    } // for
+  } // for
+  reg
+} // to_reg() }}}
+
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{Fragment, Hive, Value};
+
+  const USER_REG: &str = r#"WINE REGISTRY Version 2
+;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+
+#arch=win64
+
+[Software\\Wine\\Direct3D] 1700000000
+#time=1da0000000000
+"csmt"=dword:00000000
+"renderer"="vulkan"
+
+[Software\\Wine\\DllOverrides] 1700000000
+"*d3d9"="native,builtin"
+"Quoted \"name\""="C:\\Games\\"
+"Binary"=hex:01,02,\
+  03,04
+@="default"
+"#;
+
+  #[test]
+  fn parse_wine_registry()
+  {
+    let hive = Hive::parse(USER_REG);
+    assert_eq!(hive.get("Software\\Wine\\Direct3D", "CSMT"), Some(&Value::Dword(0)));
+    assert_eq!(hive.get_str("software\\wine\\direct3d", "renderer"), Some("vulkan"));
+    assert_eq!(hive.get_str("Software\\Wine\\DllOverrides", "*d3d9"), Some("native,builtin"));
+    assert_eq!(hive.get_str("Software\\Wine\\DllOverrides", "quoted \"name\""), Some("C:\\Games\\"));
+    assert_eq!(hive.get("Software\\Wine\\DllOverrides", "binary"), Some(&Value::Other("hex:01,02,03,04".into())));
+    assert_eq!(hive.get_str("Software\\Wine\\DllOverrides", ""), Some("default"));
+    assert_eq!(hive.values("Software\\Wine\\DllOverrides").len(), 4);
+    assert!(hive.get("Software\\Wine", "Version").is_none());
+  }
+
+  #[test]
+  fn fragment_groups_values_by_key()
+  {
+    let mut fragment = Fragment::default();
+    assert!(fragment.is_empty());
+    fragment.set("HKEY_CURRENT_USER\\Software\\Wine", "Version", Value::Str("win7".into()));
+    fragment.set("HKEY_CURRENT_USER\\Software\\Wine\\Direct3D", "csmt", Value::Dword(1));
+    fragment.delete("HKEY_CURRENT_USER\\Software\\Wine", "Path \"x\"");
+    assert_eq!(fragment.to_reg(), concat!(
+      "Windows Registry Editor Version 5.00\n",
+      "\n[HKEY_CURRENT_USER\\Software\\Wine]\n",
+      "\"Version\"=\"win7\"\n",
+      "\"Path \\\"x\\\"\"=-\n",
+      "\n[HKEY_CURRENT_USER\\Software\\Wine\\Direct3D]\n",
+      "\"csmt\"=dword:00000001\n",
+    ));
+    // Values under other root keys are not checked
+    fragment.set("HKEY_LOCAL_MACHINE\\Software\\Wine", "Version", Value::Str("win7".into()));
+    assert!(! fragment.is_applied("HKEY_CURRENT_USER", &Hive::default()));
+    assert!(fragment.is_applied("HKEY_CURRENT_USER", &Hive::parse(concat!(
+      "WINE REGISTRY Version 2\n",
+      "[Software\\\\Wine] 1700000000\n",
+      "\"Version\"=\"win7\"\n",
+      "[Software\\\\Wine\\\\Direct3D] 1700000000\n",
+      "\"csmt\"=dword:00000001\n",
+    ))));
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -0,0 +1,311 @@
+// Snapshots of the wine prefix, taken before the actions which modify it
+use std::fs::File;
+use std::path::{Path,PathBuf};
+
+use anyhow::anyhow as ah;
+use serde::{Deserialize, Serialize};
+
+use crate::log;
+use crate::common;
+
+// Snapshots kept per project, the oldest ones are removed first
+pub const COUNT_MAX: usize = 3;
+
+// Names of a snapshot of the prefix, a copy with reflink support or a compressed archive otherwise
+const DIR_PREFIX: &str = "prefix";
+const FILE_ARCHIVE: &str = "prefix.tar.zst";
+
+// pub struct Snapshot {{{
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot
+{
+  pub id: u64, // unique in the directory, also the name of the snapshot sub-directory
+  pub label: String, // action the snapshot was taken before
+  pub created: u64, // seconds since the epoch
+} // struct Snapshot }}}
+
+// fn now() {{{
+fn now() -> u64
+{
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|e| e.as_secs())
+    .unwrap_or_default()
+} // fn: now }}}
+
+// fn path_file_index() {{{
+fn path_file_index(path_dir_snapshots: &Path) -> PathBuf
+{
+  path_dir_snapshots.join("snapshots.json")
+} // fn: path_file_index }}}
+
+// fn write() {{{
+fn write(path_dir_snapshots: &Path, snapshots: &[Snapshot]) -> anyhow::Result<()>
+{
+  std::fs::create_dir_all(path_dir_snapshots)?;
+  let file = File::create(path_file_index(path_dir_snapshots))?;
+  serde_json::to_writer_pretty(file, snapshots)?;
+  Ok(())
+} // fn: write }}}
+
+// fn copy() {{{
+// Copies the directory preserving links and permissions, file systems with reflink support share
+// the data blocks until they are modified. Hard links are not used since wine modifies the files
+// of the prefix in place, which would also modify the snapshot
+fn copy(path_dir_src: &Path, path_dir_dst: &Path) -> anyhow::Result<()>
+{
+  let output = std::process::Command::new("cp")
+    .args(["-a", "--reflink=auto"])
+    .arg(path_dir_src)
+    .arg(path_dir_dst)
+    .output()?;
+  if ! output.status.success()
+  {
+    let _ = std::fs::remove_dir_all(path_dir_dst);
+    return Err(ah!("Could not copy '{}': {}", path_dir_src.display(), String::from_utf8_lossy(&output.stderr).trim()));
+  } // if
+  Ok(())
+} // fn: copy }}}
+
+// fn run() {{{
+fn run(command: &mut std::process::Command) -> anyhow::Result<()>
+{
+  let output = command.output()?;
+  if ! output.status.success()
+  {
+    return Err(ah!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+  } // if
+  Ok(())
+} // fn: run }}}
+
+// fn archive() {{{
+// Compresses the directory, used on file systems without reflink support where a full copy of the
+// prefix is too slow and large
+fn archive(path_dir_src: &Path, path_file_dst: &Path) -> anyhow::Result<()>
+{
+  run(std::process::Command::new("tar")
+    .args(["--zstd", "-cf"])
+    .arg(path_file_dst)
+    .arg("-C")
+    .arg(path_dir_src)
+    .arg(".")
+  ).map_err(|e|
+  {
+    let _ = std::fs::remove_file(path_file_dst);
+    ah!("Could not archive '{}': {}", path_dir_src.display(), e)
+  })
+} // fn: archive }}}
+
+// fn extract() {{{
+fn extract(path_file_src: &Path, path_dir_dst: &Path) -> anyhow::Result<()>
+{
+  std::fs::create_dir_all(path_dir_dst)?;
+  run(std::process::Command::new("tar")
+    .args(["--zstd", "-xf"])
+    .arg(path_file_src)
+    .arg("-C")
+    .arg(path_dir_dst)
+  ).map_err(|e|
+  {
+    let _ = std::fs::remove_dir_all(path_dir_dst);
+    ah!("Could not extract '{}': {}", path_file_src.display(), e)
+  })
+} // fn: extract }}}
+
+// pub fn is_reflink() {{{
+// Checks if copies in the directory share the data blocks, which makes a snapshot cheap
+pub fn is_reflink(path_dir: &Path) -> bool
+{
+  let path_file_src = path_dir.join(".reflink");
+  let path_file_dst = path_dir.join(".reflink.copy");
+  let status = std::fs::create_dir_all(path_dir)
+    .and_then(|_| std::fs::write(&path_file_src, "reflink"))
+    .and_then(|_| std::process::Command::new("cp")
+      .arg("--reflink=always")
+      .arg(&path_file_src)
+      .arg(&path_file_dst)
+      .stderr(std::process::Stdio::null())
+      .status()
+    );
+  let _ = std::fs::remove_file(&path_file_src);
+  let _ = std::fs::remove_file(&path_file_dst);
+  status.is_ok_and(|e| e.success())
+} // fn: is_reflink }}}
+
+// pub fn list() {{{
+// Snapshots of the directory, the newest one comes first
+pub fn list(path_dir_snapshots: &Path) -> anyhow::Result<Vec<Snapshot>>
+{
+  let path_file_index = path_file_index(path_dir_snapshots);
+  if ! path_file_index.exists() { return Ok(vec![]); } // if
+  let mut snapshots : Vec<Snapshot> = serde_json::from_reader(File::open(&path_file_index)?)?;
+  snapshots.sort_by_key(|e| std::cmp::Reverse(e.id));
+  Ok(snapshots)
+} // fn: list }}}
+
+// pub fn create() {{{
+// Copies or archives the prefix into a new snapshot, the oldest snapshots above count_max are
+// removed
+pub fn create(path_dir_prefix: &Path, path_dir_snapshots: &Path, label: &str, count_max: usize) -> anyhow::Result<Snapshot>
+{
+  if ! path_dir_prefix.exists() { return Err(ah!("Prefix '{}' does not exist", path_dir_prefix.display())); } // if
+  let mut snapshots = list(path_dir_snapshots)?;
+  let id = snapshots.iter().map(|e| e.id + 1).chain([now()]).max().unwrap_or_default();
+  let snapshot = Snapshot{ id, label: label.to_string(), created: now() };
+  let path_dir_snapshot = path_dir_snapshots.join(id.to_string());
+  if is_reflink(path_dir_snapshots)
+  {
+    std::fs::create_dir_all(&path_dir_snapshot)?;
+    copy(path_dir_prefix, &path_dir_snapshot.join(DIR_PREFIX))?;
+  } // if
+  else
+  {
+    log!("'{}' does not support reflink copies, compressing snapshot '{}'", path_dir_snapshots.display(), label);
+    std::fs::create_dir_all(&path_dir_snapshot)?;
+    archive(path_dir_prefix, &path_dir_snapshot.join(FILE_ARCHIVE))?;
+  } // else
+  snapshots.insert(0, snapshot.clone());
+  // Remove the oldest snapshots
+  for snapshot in snapshots.split_off(count_max.max(1).min(snapshots.len()))
+  {
+    log!("Removing snapshot '{}'", snapshot.label);
+    let _ = std::fs::remove_dir_all(path_dir_snapshots.join(snapshot.id.to_string()));
+  } // for
+  write(path_dir_snapshots, &snapshots)?;
+  log!("Created snapshot '{}' of '{}'", label, path_dir_prefix.display());
+  Ok(snapshot)
+} // fn: create }}}
+
+// pub fn restore() {{{
+// Replaces the prefix with the snapshot, the current prefix is kept if the copy fails
+pub fn restore(path_dir_prefix: &Path, path_dir_snapshots: &Path, id: u64) -> anyhow::Result<()>
+{
+  let path_dir_snapshot = path_dir_snapshots.join(id.to_string());
+  let path_file_archive = path_dir_snapshot.join(FILE_ARCHIVE);
+  let path_dir_snapshot = path_dir_snapshot.join(DIR_PREFIX);
+  if ! path_dir_snapshot.exists() && ! path_file_archive.exists()
+  {
+    return Err(ah!("Snapshot {} does not exist", id));
+  } // if
+  let path_dir_backup = path_dir_prefix.with_extension("restore");
+  let _ = std::fs::remove_dir_all(&path_dir_backup);
+  if path_dir_prefix.exists() { std::fs::rename(path_dir_prefix, &path_dir_backup)?; } // if
+  let result = match path_dir_snapshot.exists()
+  {
+    true => copy(&path_dir_snapshot, path_dir_prefix),
+    false => extract(&path_file_archive, path_dir_prefix),
+  }; // match
+  match result
+  {
+    Ok(()) => { let _ = std::fs::remove_dir_all(&path_dir_backup); Ok(()) },
+    Err(e) =>
+    {
+      if path_dir_backup.exists() { std::fs::rename(&path_dir_backup, path_dir_prefix)?; } // if
+      Err(e)
+    },
+  } // match
+} // fn: restore }}}
+
+// pub fn remove() {{{
+pub fn remove(path_dir_snapshots: &Path, id: u64) -> anyhow::Result<()>
+{
+  let mut snapshots = list(path_dir_snapshots)?;
+  snapshots.retain(|e| e.id != id);
+  let _ = std::fs::remove_dir_all(path_dir_snapshots.join(id.to_string()));
+  write(path_dir_snapshots, &snapshots)
+} // fn: remove }}}
+
+// pub fn age() {{{
+// Time since the snapshot was created, e.g., '5 minutes ago'
+pub fn age(snapshot: &Snapshot) -> String
+{
+  let secs = now().saturating_sub(snapshot.created);
+  let (value, unit) = match secs
+  {
+    0..60 => return "just now".to_string(),
+    60..3600 => (secs / 60, "minute"),
+    3600..86400 => (secs / 3600, "hour"),
+    _ => (secs / 86400, "day"),
+  }; // match
+  format!("{} {}{} ago", value, unit, if value == 1 { "" } else { "s" })
+} // fn: age }}}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{age, create, is_reflink, list, remove, restore, Snapshot};
+  use crate::testing::TempDir;
+
+  #[test]
+  fn snapshot_and_restore_prefix()
+  {
+    let dir = TempDir::new();
+    let path_dir_prefix = dir.path.join("wine");
+    let path_dir_snapshots = dir.path.join("snapshots");
+    std::fs::create_dir_all(path_dir_prefix.join("drive_c")).unwrap();
+    std::fs::write(path_dir_prefix.join("user.reg"), "first").unwrap();
+    std::os::unix::fs::symlink("drive_c", path_dir_prefix.join("c:")).unwrap();
+    let first = create(&path_dir_prefix, &path_dir_snapshots, "Before regedit", 10).unwrap();
+    std::fs::write(path_dir_prefix.join("user.reg"), "second").unwrap();
+    std::fs::write(path_dir_prefix.join("drive_c").join("broken"), "").unwrap();
+    let second = create(&path_dir_prefix, &path_dir_snapshots, "Before winetricks", 10).unwrap();
+    assert_eq!(list(&path_dir_snapshots).unwrap(), vec![second.clone(), first.clone()]);
+    // Restore the first snapshot
+    restore(&path_dir_prefix, &path_dir_snapshots, first.id).unwrap();
+    assert_eq!(std::fs::read_to_string(path_dir_prefix.join("user.reg")).unwrap(), "first");
+    assert!(! path_dir_prefix.join("drive_c").join("broken").exists());
+    assert!(std::fs::symlink_metadata(path_dir_prefix.join("c:")).unwrap().file_type().is_symlink());
+    assert!(! path_dir_prefix.with_extension("restore").exists());
+    // Snapshots are kept after a restore
+    assert_eq!(list(&path_dir_snapshots).unwrap().len(), 2);
+    remove(&path_dir_snapshots, second.id).unwrap();
+    assert_eq!(list(&path_dir_snapshots).unwrap(), vec![first.clone()]);
+    assert!(! path_dir_snapshots.join(second.id.to_string()).exists());
+    assert!(restore(&path_dir_prefix, &path_dir_snapshots, second.id).is_err());
+    assert_eq!(age(&first), "just now");
+    assert_eq!(age(&Snapshot{ created: first.created - 7200, ..first }), "2 hours ago");
+  }
+
+  #[test]
+  fn oldest_snapshots_are_removed()
+  {
+    let dir = TempDir::new();
+    let path_dir_prefix = dir.path.join("wine");
+    let path_dir_snapshots = dir.path.join("snapshots");
+    std::fs::create_dir_all(&path_dir_prefix).unwrap();
+    let ids : Vec<u64> = (0..3).map(|i| create(&path_dir_prefix, &path_dir_snapshots, &i.to_string(), 2).unwrap().id).collect();
+    let snapshots = list(&path_dir_snapshots).unwrap();
+    assert_eq!(snapshots.iter().map(|e| e.label.as_str()).collect::<Vec<_>>(), vec!["2", "1"]);
+    assert!(! path_dir_snapshots.join(ids[0].to_string()).exists());
+    assert!(create(&dir.path.join("missing"), &path_dir_snapshots, "none", 2).is_err());
+  }
+
+  #[test]
+  fn snapshot_is_archived_without_reflink()
+  {
+    let dir = TempDir::new();
+    let path_dir_prefix = dir.path.join("wine");
+    let path_dir_snapshots = dir.path.join("snapshots");
+    std::fs::create_dir_all(path_dir_prefix.join("drive_c")).unwrap();
+    std::fs::write(path_dir_prefix.join("drive_c").join("game.exe"), "game").unwrap();
+    let snapshot = create(&path_dir_prefix, &path_dir_snapshots, "Before regedit", 10).unwrap();
+    let path_dir_snapshot = path_dir_snapshots.join(snapshot.id.to_string());
+    let is_reflink = is_reflink(&path_dir_snapshots);
+    assert_eq!(path_dir_snapshot.join(super::DIR_PREFIX).exists(), is_reflink);
+    assert_eq!(path_dir_snapshot.join(super::FILE_ARCHIVE).exists(), ! is_reflink);
+    std::fs::remove_dir_all(&path_dir_prefix).unwrap();
+    restore(&path_dir_prefix, &path_dir_snapshots, snapshot.id).unwrap();
+    assert_eq!(std::fs::read_to_string(path_dir_prefix.join("drive_c").join("game.exe")).unwrap(), "game");
+  }
+
+  #[test]
+  fn reflink_probe_leaves_no_files()
+  {
+    let dir = TempDir::new();
+    let path_dir_snapshots = dir.path.join("snapshots");
+    is_reflink(&path_dir_snapshots);
+    assert_eq!(std::fs::read_dir(&path_dir_snapshots).unwrap().count(), 0);
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
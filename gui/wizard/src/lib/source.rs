@@ -0,0 +1,345 @@
+// Installation sources of a game, e.g., installers split in parts, disc images and archives
+use std::path::{Path,PathBuf};
+
+use anyhow::anyhow as ah;
+
+use shared::std::PathBufExt;
+
+// pub enum Kind {{{
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind
+{
+  Installer, // executed in place, with its parts next to it
+  Disc, // disc image, extracted and inserted as a drive
+  Archive, // extracted into the project
+} // enum Kind }}}
+
+// pub struct Source {{{
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source
+{
+  pub path: PathBuf, // file selected by the user
+  pub kind: Kind,
+  pub parts: Vec<PathBuf>, // files the source reads, e.g., 'setup-1.bin' or the tracks of a cue sheet
+} // struct Source }}}
+
+// Extensions of archives, the compressed tar ones are matched before the single extensions
+const EXT_TAR: [&str; 9] = ["tar", "tar.gz", "tgz", "tar.xz", "txz", "tar.bz2", "tbz2", "tar.zst", "tzst"];
+const EXT_ARCHIVE: [&str; 3] = ["zip", "7z", "rar"];
+const EXT_DISC: [&str; 3] = ["iso", "cue", "img"];
+
+// fn name_lower() {{{
+fn name_lower(path: &Path) -> String
+{
+  path.file_name().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default()
+} // fn: name_lower }}}
+
+// fn is_tar() {{{
+fn is_tar(path: &Path) -> bool
+{
+  let name = name_lower(path);
+  EXT_TAR.iter().any(|ext| name.ends_with(&format!(".{}", ext)))
+} // fn: is_tar }}}
+
+// fn extension() {{{
+fn extension(path: &Path) -> String
+{
+  path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default()
+} // fn: extension }}}
+
+// pub fn kind() {{{
+pub fn kind(path: &Path) -> Kind
+{
+  let ext = extension(path);
+  if is_tar(path) || EXT_ARCHIVE.contains(&ext.as_str()) { return Kind::Archive; } // if
+  if EXT_DISC.contains(&ext.as_str()) { return Kind::Disc; } // if
+  Kind::Installer
+} // fn: kind }}}
+
+// pub fn cue_files() {{{
+// Files referenced by a cue sheet, e.g., 'FILE "Game (Track 1).bin" BINARY'
+pub fn cue_files(src: &str) -> Vec<String>
+{
+  src.lines()
+    .map(|e| e.trim())
+    .filter(|e| e.len() > 5 && e[..5].eq_ignore_ascii_case("file "))
+    .filter_map(|e|
+    {
+      let e = e[5..].trim();
+      match e.strip_prefix('"')
+      {
+        Some(quoted) => quoted.split_once('"').map(|(name, _)| name.to_string()),
+        None => e.split_whitespace().next().map(|name| name.to_string()),
+      } // match
+    })
+    .collect()
+} // fn: cue_files }}}
+
+// fn installer_parts() {{{
+// Data files of a multi-part installer, e.g., 'setup.exe' reads 'setup-1.bin' and 'setup-2.bin'
+fn installer_parts(path_file_installer: &Path) -> Vec<PathBuf>
+{
+  let stem = match path_file_installer.file_stem()
+  {
+    Some(stem) => stem.to_string_lossy().to_lowercase(),
+    None => return vec![],
+  }; // match
+  let mut parts : Vec<(u32, PathBuf)> = path_file_installer.parent()
+    .and_then(|e| std::fs::read_dir(e).ok())
+    .map(|e| e.filter_map(|e| e.ok()).map(|e| e.path()).collect::<Vec<_>>())
+    .unwrap_or_default()
+    .into_iter()
+    .filter_map(|path|
+    {
+      let name = name_lower(&path);
+      let index = name.strip_prefix(&format!("{}-", stem))?.strip_suffix(".bin")?.parse().ok()?;
+      Some((index, path))
+    })
+    .collect();
+  parts.sort();
+  parts.into_iter().map(|(_, path)| path).collect()
+} // fn: installer_parts }}}
+
+// pub fn sources() {{{
+// Classifies the selected files, the parts of other sources are not sources themselves and the
+// discs are sorted by name so the first disc is inserted first
+pub fn sources(paths: &[PathBuf]) -> Vec<Source>
+{
+  let mut sources : Vec<Source> = paths.iter().map(|path|
+  {
+    let kind = kind(path);
+    let parts = match kind
+    {
+      Kind::Installer => installer_parts(path),
+      Kind::Disc if extension(path) == "cue" =>
+      {
+        let parent = path.parent().map(|e| e.to_path_buf()).unwrap_or_default();
+        cue_files(&std::fs::read_to_string(path).unwrap_or_default()).iter().map(|e| parent.join(e)).collect()
+      },
+      _ => vec![],
+    }; // match
+    Source{ path: path.clone(), kind, parts }
+  }).collect();
+  let parts : Vec<PathBuf> = sources.iter().flat_map(|e| e.parts.clone()).collect();
+  sources.retain(|e| ! parts.contains(&e.path));
+  sources.sort_by_key(|e| match e.kind { Kind::Disc => name_lower(&e.path), _ => String::new() });
+  sources
+} // fn: sources }}}
+
+impl Source
+{
+
+// missing() {{{
+// Parts which the source requires and are not next to it
+pub fn missing(&self) -> Vec<String>
+{
+  let mut missing : Vec<String> = self.parts.iter()
+    .filter(|e| ! e.exists())
+    .map(|e| e.file_name().map(|e| e.to_string_lossy().to_string()).unwrap_or_default())
+    .collect();
+  // Installer parts are numbered from one without gaps
+  if self.kind == Kind::Installer && let Some(stem) = self.path.file_stem()
+  {
+    let names : Vec<String> = self.parts.iter().map(|e| name_lower(e)).collect();
+    let count = names.iter()
+      .filter_map(|e| e.rsplit_once('-').and_then(|(_, index)| index.strip_suffix(".bin")?.parse::<usize>().ok()))
+      .max()
+      .unwrap_or_default();
+    for index in 1..=count
+    {
+      let name = format!("{}-{}.bin", stem.to_string_lossy(), index);
+      if ! names.contains(&name.to_lowercase()) { missing.push(name); } // if
+    } // for
+  } // if
+  missing
+} // missing() }}}
+
+// name() {{{
+pub fn name(&self) -> String
+{
+  self.path.file_name().map(|e| e.to_string_lossy().to_string()).unwrap_or_default()
+} // name() }}}
+
+// stem() {{{
+// Name without the extensions, used for the directory the source is extracted to
+pub fn stem(&self) -> String
+{
+  let name = self.name();
+  let lower = name.to_lowercase();
+  match EXT_TAR.iter().chain(EXT_ARCHIVE.iter()).chain(EXT_DISC.iter())
+    .find(|ext| lower.ends_with(&format!(".{}", ext)))
+  {
+    Some(ext) => name[..name.len() - ext.len() - 1].to_string(),
+    None => name,
+  } // match
+} // stem() }}}
+
+// tools() {{{
+// Programs of the system required to extract the source
+pub fn tools(&self) -> Vec<&'static str>
+{
+  if self.kind == Kind::Installer { return vec![]; } // if
+  if is_tar(&self.path) { return vec!["tar"]; } // if
+  match extension(&self.path).as_str()
+  {
+    "zip" => vec!["unzip"],
+    "cue" => vec!["bchunk", "7z"],
+    _ => vec!["7z"],
+  } // match
+} // tools() }}}
+
+// tools_missing() {{{
+// Tools required to extract the source which are not installed
+pub fn tools_missing(&self) -> Vec<&'static str>
+{
+  self.tools().into_iter().filter(|e| which::which(e).is_err()).collect()
+} // tools_missing() }}}
+
+// extract() {{{
+// Extracts an archive or disc image into the directory, with the tools installed in the system
+pub fn extract(&self, path_dir_dst: &Path) -> anyhow::Result<()>
+{
+  let missing = self.tools_missing();
+  if ! missing.is_empty()
+  {
+    return Err(ah!("Could not find '{}' in the system, it is required to extract '{}'", missing.join("', '"), self.name()));
+  } // if
+  std::fs::create_dir_all(path_dir_dst)?;
+  let path_file_src = self.path.string();
+  let path_dir_dst_str = path_dir_dst.to_path_buf().string();
+  if is_tar(&self.path) { return run("tar", &["-xf", &path_file_src, "-C", &path_dir_dst_str]); } // if
+  match extension(&self.path).as_str()
+  {
+    "zip" => run("unzip", &["-o", "-q", &path_file_src, "-d", &path_dir_dst_str]),
+    "cue" =>
+    {
+      // Convert the data track to an iso next to the destination, then extract it
+      let path_file_bin = self.parts.first().ok_or(ah!("No track in cue sheet '{}'", self.name()))?;
+      let path_dir_tracks = path_dir_dst.with_extension("tracks");
+      std::fs::create_dir_all(&path_dir_tracks)?;
+      let result = run("bchunk", &[&path_file_bin.string(), &path_file_src, &path_dir_tracks.join("track").string()])
+        .and_then(|_| run("7z", &["x", "-y", &format!("-o{}", path_dir_dst_str), &path_dir_tracks.join("track01.iso").string()]));
+      let _ = std::fs::remove_dir_all(&path_dir_tracks);
+      result
+    },
+    _ => run("7z", &["x", "-y", &format!("-o{}", path_dir_dst_str), &path_file_src]),
+  } // match
+} // extract() }}}
+
+}
+
+// fn run() {{{
+fn run(program: &str, args: &[&str]) -> anyhow::Result<()>
+{
+  let output = match std::process::Command::new(program).args(args).output()
+  {
+    Ok(output) => output,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(ah!("Could not find '{}', it is required to extract this source", program)),
+    Err(e) => return Err(ah!("Could not run '{}': {}", program, e)),
+  }; // match
+  if ! output.status.success()
+  {
+    return Err(ah!("'{}' failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+  } // if
+  Ok(())
+} // fn: run }}}
+
+// fn find_nocase() {{{
+// Path of a file relative to the directory, matching each component regardless of case like on
+// windows, e.g., 'Install\Setup.exe' finds 'INSTALL/setup.exe'
+fn find_nocase(path_dir: &Path, path_relative: &str) -> Option<PathBuf>
+{
+  let mut path = path_dir.to_path_buf();
+  for component in path_relative.split(['\\', '/']).filter(|e| ! e.is_empty())
+  {
+    path = std::fs::read_dir(&path).ok()?
+      .filter_map(|e| e.ok())
+      .map(|e| e.path())
+      .find(|e| name_lower(e) == component.to_lowercase())?;
+  } // for
+  Some(path)
+} // fn: find_nocase }}}
+
+// pub fn autorun() {{{
+// Installer of an extracted disc, from the 'open' entry of autorun.inf or a setup executable in
+// the root directory
+pub fn autorun(path_dir_disc: &Path) -> Option<PathBuf>
+{
+  let from_autorun = find_nocase(path_dir_disc, "autorun.inf")
+    .and_then(|e| std::fs::read(e).ok())
+    .and_then(|e| String::from_utf8_lossy(&e).lines()
+      .filter_map(|e| e.trim().split_once('='))
+      .find(|(key, _)| key.trim().eq_ignore_ascii_case("open"))
+      .and_then(|(_, value)| value.trim().trim_matches('"').split_whitespace().next().map(|e| e.to_string()))
+    )
+    .and_then(|e| find_nocase(path_dir_disc, &e))
+    .filter(|e| e.is_file());
+  from_autorun.or_else(|| ["setup.exe", "install.exe", "autorun.exe"].iter().find_map(|e| find_nocase(path_dir_disc, e)))
+} // fn: autorun }}}
+
+#[cfg(test)]
+mod tests
+{
+  use std::path::PathBuf;
+
+  use super::{autorun, cue_files, kind, sources, Kind, Source};
+  use crate::testing::TempDir;
+
+  #[test]
+  fn classify_sources()
+  {
+    let dir = TempDir::new();
+    for name in ["setup.exe", "setup-1.bin", "setup-3.bin", "Disc 2.iso", "Disc 1.cue", "Disc 1.bin", "data.tar.gz"]
+    {
+      std::fs::write(dir.path.join(name), "").unwrap();
+    } // for
+    std::fs::write(dir.path.join("Disc 1.cue"), "FILE \"Disc 1.bin\" BINARY\n  TRACK 01 MODE1/2352\n").unwrap();
+    let paths : Vec<_> = ["setup.exe", "setup-1.bin", "Disc 2.iso", "Disc 1.bin", "Disc 1.cue", "data.tar.gz"]
+      .iter().map(|e| dir.path.join(e)).collect();
+    let sources = sources(&paths);
+    assert_eq!(sources.iter().map(|e| (e.name(), e.kind)).collect::<Vec<_>>(), vec![
+      ("setup.exe".to_string(), Kind::Installer),
+      ("data.tar.gz".to_string(), Kind::Archive),
+      ("Disc 1.cue".to_string(), Kind::Disc),
+      ("Disc 2.iso".to_string(), Kind::Disc),
+    ]);
+    assert_eq!(sources[0].parts, vec![dir.path.join("setup-1.bin"), dir.path.join("setup-3.bin")]);
+    assert_eq!(sources[0].missing(), vec!["setup-2.bin"]);
+    assert_eq!(sources[1].stem(), "data");
+    assert_eq!(sources[2].parts, vec![dir.path.join("Disc 1.bin")]);
+    assert!(sources[2].missing().is_empty());
+    assert_eq!(kind(&dir.path.join("GAME.ZIP")), Kind::Archive);
+    assert_eq!(sources.iter().map(|e| e.tools()).collect::<Vec<_>>(), vec![vec![], vec!["tar"], vec!["bchunk", "7z"], vec!["7z"]]);
+    let source = Source{ path: PathBuf::from("game.zip"), kind: Kind::Archive, parts: vec![] };
+    assert_eq!(source.tools(), ["unzip"]);
+    assert_eq!(cue_files("file game.bin binary\nFILE \"a b.bin\" BINARY\nREM FILE"), vec!["game.bin", "a b.bin"]);
+  }
+
+  #[test]
+  fn extract_archive_and_find_installer()
+  {
+    let dir = TempDir::new();
+    let path_dir_src = dir.path.join("src");
+    std::fs::create_dir_all(&path_dir_src).unwrap();
+    std::fs::write(path_dir_src.join("AUTORUN.INF"), "[autorun]\r\nopen=Install\\Setup.exe /auto\r\n").unwrap();
+    std::fs::create_dir_all(path_dir_src.join("INSTALL")).unwrap();
+    std::fs::write(path_dir_src.join("INSTALL").join("setup.exe"), "").unwrap();
+    std::fs::write(path_dir_src.join("setup.exe"), "").unwrap();
+    let path_file_archive = dir.path.join("game.tar");
+    let status = std::process::Command::new("tar")
+      .args(["-cf", &path_file_archive.to_string_lossy(), "-C", &path_dir_src.to_string_lossy(), "."])
+      .status()
+      .unwrap();
+    assert!(status.success());
+    let source = Source{ path: path_file_archive, kind: Kind::Archive, parts: vec![] };
+    let path_dir_dst = dir.path.join("dst");
+    source.extract(&path_dir_dst).unwrap();
+    assert!(path_dir_dst.join("setup.exe").exists());
+    // The entry of autorun.inf is preferred and matched regardless of case
+    assert_eq!(autorun(&path_dir_dst), Some(path_dir_dst.join("INSTALL").join("setup.exe")));
+    std::fs::remove_file(path_dir_dst.join("AUTORUN.INF")).unwrap();
+    assert_eq!(autorun(&path_dir_dst), Some(path_dir_dst.join("setup.exe")));
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
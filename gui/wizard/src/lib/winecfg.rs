@@ -0,0 +1,297 @@
+// Common wine settings, read from the registry of the prefix and applied as registry fragments
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::lib::registry::{Fragment, Hive, Value};
+
+// Windows versions reported by wine, paired with their labels
+pub const VERSIONS: [(&str, &str); 9] = [
+  ("win11", "Windows 11"),
+  ("win10", "Windows 10"),
+  ("win81", "Windows 8.1"),
+  ("win8", "Windows 8"),
+  ("win7", "Windows 7"),
+  ("vista", "Windows Vista"),
+  ("winxp", "Windows XP"),
+  ("win2k", "Windows 2000"),
+  ("win98", "Windows 98"),
+];
+// Audio drivers paired with their labels, an empty driver disables audio
+pub const AUDIO_DRIVERS: [(&str, &str); 4] = [("pulse", "PulseAudio"), ("alsa", "ALSA"), ("oss", "OSS"), ("", "Disabled")];
+pub const MOUSE_WARP: [(&str, &str); 3] = [("enable", "Enable"), ("disable", "Disable"), ("force", "Force")];
+pub const RENDERERS: [(&str, &str); 3] = [("gl", "OpenGL"), ("vulkan", "Vulkan"), ("no3d", "No 3D")];
+
+// Keys relative to HKEY_CURRENT_USER, stored in user.reg
+const ROOT_USER: &str = "HKEY_CURRENT_USER";
+const KEY_WINE: &str = "Software\\Wine";
+const KEY_EXPLORER: &str = "Software\\Wine\\Explorer";
+const KEY_DESKTOPS: &str = "Software\\Wine\\Explorer\\Desktops";
+const KEY_DLL_OVERRIDES: &str = "Software\\Wine\\DllOverrides";
+const KEY_DRIVERS: &str = "Software\\Wine\\Drivers";
+const KEY_DIRECT_INPUT: &str = "Software\\Wine\\DirectInput";
+const KEY_DIRECT3D: &str = "Software\\Wine\\Direct3D";
+// Key relative to HKEY_LOCAL_MACHINE, stored in system.reg
+const KEY_CURRENT_VERSION: &str = "Software\\Microsoft\\Windows NT\\CurrentVersion";
+// Name of the virtual desktop
+const DESKTOP: &str = "Default";
+
+// pub struct Settings {{{
+// Settings of the prefix, none keeps the default of wine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings
+{
+  pub version: Option<String>, // reported windows version, e.g., 'win10'
+  pub desktop: Option<(u32, u32)>, // resolution of the virtual desktop
+  pub dll_overrides: BTreeMap<String, String>, // library to load order, e.g., 'native,builtin'
+  pub audio: Option<String>, // audio driver
+  pub mouse_warp: Option<String>, // mouse warp override of direct input
+  pub csmt: Option<bool>, // command stream multi-threading
+  pub renderer: Option<String>, // renderer of wined3d
+} // struct Settings }}}
+
+// fn version_from_build() {{{
+// Windows version of the build number in system.reg, for prefixes created by winecfg
+fn version_from_build(build: u32) -> &'static str
+{
+  match build
+  {
+    22000.. => "win11",
+    10240.. => "win10",
+    9600.. => "win81",
+    9200.. => "win8",
+    7600.. => "win7",
+    6000.. => "vista",
+    2600.. => "winxp",
+    2195.. => "win2k",
+    _ => "win98",
+  } // match
+} // fn: version_from_build }}}
+
+// pub fn dll_overrides_from_str() {{{
+// Parses overrides in the syntax of WINEDLLOVERRIDES, e.g., 'd3d9,d3d11=n,b;dinput8=n;mscoree='
+pub fn dll_overrides_from_str(src: &str) -> BTreeMap<String, String>
+{
+  let mut dll_overrides = BTreeMap::new();
+  for entry in src.split(';').map(|e| e.trim()).filter(|e| ! e.is_empty())
+  {
+    let (dlls, order) = entry.split_once('=').unwrap_or((entry, ""));
+    let order = order.split(',')
+      .map(|e| match e.trim() { "n" => "native", "b" => "builtin", e => e })
+      .filter(|e| ! e.is_empty())
+      .collect::<Vec<_>>()
+      .join(",");
+    for dll in dlls.split(',').map(|e| e.trim().to_lowercase()).filter(|e| ! e.is_empty())
+    {
+      dll_overrides.insert(dll, order.clone());
+    } // for
+  } // for
+  dll_overrides
+} // fn: dll_overrides_from_str }}}
+
+// pub fn dll_overrides_to_str() {{{
+pub fn dll_overrides_to_str(dll_overrides: &BTreeMap<String, String>) -> String
+{
+  dll_overrides.iter()
+    .map(|(dll, order)| format!("{}={}", dll, order.replace("native", "n").replace("builtin", "b")))
+    .collect::<Vec<_>>()
+    .join(";")
+} // fn: dll_overrides_to_str }}}
+
+impl Settings
+{
+
+// from_hives() {{{
+pub fn from_hives(user: &Hive, system: &Hive) -> Settings
+{
+  let f_str = |key: &str, name: &str| user.get_str(key, name).map(|e| e.to_string());
+  // Version set for the whole prefix, or the one of the registry of windows
+  let version = f_str(KEY_WINE, "Version").or_else(||
+  {
+    system.get_str(KEY_CURRENT_VERSION, "CurrentBuild")
+      .or_else(|| system.get_str(KEY_CURRENT_VERSION, "CurrentBuildNumber"))
+      .and_then(|e| e.parse().ok())
+      .map(|e| version_from_build(e).to_string())
+  });
+  let desktop = user.get_str(KEY_EXPLORER, "Desktop")
+    .and_then(|name| user.get_str(KEY_DESKTOPS, name))
+    .and_then(|e| e.split_once('x'))
+    .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+  let dll_overrides = user.values(KEY_DLL_OVERRIDES).into_iter()
+    .filter_map(|(dll, value)| match value { Value::Str(order) => Some((dll, order)), _ => None })
+    .collect();
+  let csmt = match user.get(KEY_DIRECT3D, "csmt")
+  {
+    Some(Value::Dword(value)) => Some(*value != 0),
+    _ => None,
+  }; // match
+  Settings
+  {
+    version,
+    desktop,
+    dll_overrides,
+    audio: f_str(KEY_DRIVERS, "Audio"),
+    mouse_warp: f_str(KEY_DIRECT_INPUT, "MouseWarpOverride"),
+    csmt,
+    renderer: f_str(KEY_DIRECT3D, "renderer"),
+  }
+} // from_hives() }}}
+
+// read() {{{
+// Reads the settings from the user.reg and system.reg files of the prefix
+pub fn read(path_dir_prefix: &Path) -> anyhow::Result<Settings>
+{
+  let user = Hive::read(&path_dir_prefix.join("user.reg"))?;
+  let system = Hive::read(&path_dir_prefix.join("system.reg")).unwrap_or_default();
+  Ok(Settings::from_hives(&user, &system))
+} // read() }}}
+
+// is_saved() {{{
+// Checks if the user hive has the values the fragment writes. The settings read back may still
+// differ, e.g., the default version is the one of system.reg
+pub fn is_saved(fragment: &Fragment, user: &Hive) -> bool
+{
+  fragment.is_applied(ROOT_USER, user)
+} // is_saved() }}}
+
+// fragment() {{{
+// Registry values that change the current settings to these ones
+pub fn fragment(&self, current: &Settings) -> Fragment
+{
+  let mut fragment = Fragment::default();
+  let key = |key: &str| format!("{}\\{}", ROOT_USER, key);
+  let f_str = |fragment: &mut Fragment, path: &str, name: &str, new: &Option<String>, old: &Option<String>|
+  {
+    if new == old { return; } // if
+    match new
+    {
+      Some(value) => fragment.set(&key(path), name, Value::Str(value.clone())),
+      None => fragment.delete(&key(path), name),
+    } // match
+  };
+  f_str(&mut fragment, KEY_WINE, "Version", &self.version, &current.version);
+  if self.desktop != current.desktop
+  {
+    match self.desktop
+    {
+      Some((w, h)) =>
+      {
+        fragment.set(&key(KEY_EXPLORER), "Desktop", Value::Str(DESKTOP.to_string()));
+        fragment.set(&key(KEY_DESKTOPS), DESKTOP, Value::Str(format!("{}x{}", w, h)));
+      },
+      None => fragment.delete(&key(KEY_EXPLORER), "Desktop"),
+    } // match
+  } // if
+  for (dll, order) in self.dll_overrides.iter().filter(|(dll, order)| current.dll_overrides.get(*dll) != Some(order))
+  {
+    fragment.set(&key(KEY_DLL_OVERRIDES), dll, Value::Str(order.clone()));
+  } // for
+  for dll in current.dll_overrides.keys().filter(|dll| ! self.dll_overrides.contains_key(*dll))
+  {
+    fragment.delete(&key(KEY_DLL_OVERRIDES), dll);
+  } // for
+  f_str(&mut fragment, KEY_DRIVERS, "Audio", &self.audio, &current.audio);
+  f_str(&mut fragment, KEY_DIRECT_INPUT, "MouseWarpOverride", &self.mouse_warp, &current.mouse_warp);
+  if self.csmt != current.csmt
+  {
+    match self.csmt
+    {
+      Some(csmt) => fragment.set(&key(KEY_DIRECT3D), "csmt", Value::Dword(u32::from(csmt))),
+      None => fragment.delete(&key(KEY_DIRECT3D), "csmt"),
+    } // match
+  } // if
+  f_str(&mut fragment, KEY_DIRECT3D, "renderer", &self.renderer, &current.renderer);
+  fragment
+} // fragment() }}}
+
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::{dll_overrides_from_str, dll_overrides_to_str, Settings};
+  use crate::lib::registry::Hive;
+
+  const USER_REG: &str = r#"WINE REGISTRY Version 2
+[Software\\Wine\\Direct3D] 1700000000
+"csmt"=dword:00000001
+"renderer"="vulkan"
+
+[Software\\Wine\\DllOverrides] 1700000000
+"d3d9"="native,builtin"
+"mscoree"=""
+
+[Software\\Wine\\Explorer] 1700000000
+"Desktop"="Default"
+
+[Software\\Wine\\Explorer\\Desktops] 1700000000
+"Default"="1280x720"
+"#;
+
+  const SYSTEM_REG: &str = r#"WINE REGISTRY Version 2
+[Software\\Microsoft\\Windows NT\\CurrentVersion] 1700000000
+"CurrentBuild"="7601"
+"#;
+
+  #[test]
+  fn read_settings_from_registry()
+  {
+    let settings = Settings::from_hives(&Hive::parse(USER_REG), &Hive::parse(SYSTEM_REG));
+    assert_eq!(settings.version.as_deref(), Some("win7"));
+    assert_eq!(settings.desktop, Some((1280, 720)));
+    assert_eq!(dll_overrides_to_str(&settings.dll_overrides), "d3d9=n,b;mscoree=");
+    assert_eq!(settings.csmt, Some(true));
+    assert_eq!(settings.renderer.as_deref(), Some("vulkan"));
+    assert_eq!(settings.audio, None);
+    // Without registry files every setting keeps the default of wine
+    assert_eq!(Settings::from_hives(&Hive::default(), &Hive::default()), Settings::default());
+  }
+
+  #[test]
+  fn fragment_only_contains_changes()
+  {
+    let current = Settings::from_hives(&Hive::parse(USER_REG), &Hive::parse(SYSTEM_REG));
+    assert!(current.fragment(&current).is_empty());
+    let mut settings = current.clone();
+    settings.version = Some("win10".into());
+    settings.desktop = None;
+    settings.dll_overrides = dll_overrides_from_str("d3d9=b ; dinput8,xinput1_3=n");
+    settings.csmt = Some(false);
+    settings.renderer = None;
+    assert_eq!(settings.fragment(&current).to_reg(), concat!(
+      "Windows Registry Editor Version 5.00\n",
+      "\n[HKEY_CURRENT_USER\\Software\\Wine]\n",
+      "\"Version\"=\"win10\"\n",
+      "\n[HKEY_CURRENT_USER\\Software\\Wine\\Explorer]\n",
+      "\"Desktop\"=-\n",
+      "\n[HKEY_CURRENT_USER\\Software\\Wine\\DllOverrides]\n",
+      "\"d3d9\"=\"builtin\"\n",
+      "\"dinput8\"=\"native\"\n",
+      "\"xinput1_3\"=\"native\"\n",
+      "\"mscoree\"=-\n",
+      "\n[HKEY_CURRENT_USER\\Software\\Wine\\Direct3D]\n",
+      "\"csmt\"=dword:00000000\n",
+      "\"renderer\"=-\n",
+    ));
+  }
+
+  #[test]
+  fn default_version_is_saved_without_user_override()
+  {
+    let user_reg = USER_REG.to_string() + r#"
+[Software\\Wine] 1700000000
+"Version"="win10"
+"#;
+    let current = Settings::from_hives(&Hive::parse(&user_reg), &Hive::parse(SYSTEM_REG));
+    assert_eq!(current.version.as_deref(), Some("win10"));
+    let mut settings = current.clone();
+    settings.version = None;
+    let fragment = settings.fragment(&current);
+    assert!(! Settings::is_saved(&fragment, &Hive::parse(&user_reg)));
+    // Without the override the version of system.reg is read back, the settings are still saved
+    let saved = Settings::from_hives(&Hive::parse(USER_REG), &Hive::parse(SYSTEM_REG));
+    assert_eq!(saved.version.as_deref(), Some("win7"));
+    assert!(Settings::is_saved(&fragment, &Hive::parse(USER_REG)));
+  }
+}
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -2,6 +2,7 @@
 #![allow(special_module_name)]
 
 use std::sync::{Mutex,LazyLock};
+use std::path::PathBuf;
 
 use fltk::{
   app,
@@ -20,6 +21,8 @@ use shared::dimm;
 use shared::svg;
 use shared::fltk::SenderExt;
 
+use clap::{Parser,Subcommand};
+
 // Modules {{{
 mod common;
 mod frame;
@@ -27,6 +30,9 @@ mod lib;
 mod db;
 mod wizard;
 mod gameimage;
+mod headless;
+#[cfg(test)]
+mod testing;
 // }}}
 
 use common::Msg;
@@ -50,7 +56,10 @@ struct Gui
   wind_main : Window,
   tx        : Sender<Msg>,
   rx        : Receiver<Msg>,
-  ui        : fn(&str) -> Ui
+  ui        : fn(&str) -> Ui,
+  // Last drawn frame, and if it waits for the queued jobs
+  frame     : Option<Msg>,
+  is_blocked: bool,
 } // struct: Gui }}}
 
 // impl: Gui {{{
@@ -104,7 +113,7 @@ impl Gui
       ui
     };
 
-    Gui { app, wind_main, tx, rx, ui }
+    Gui { app, wind_main, tx, rx, ui, frame: None, is_blocked: false }
   } // fn: new }}}
 
 // fn redraw() {{{
@@ -121,6 +130,7 @@ fn redraw(&mut self, msg : Msg)
     Msg::DrawWelcome => frame::welcome::welcome(self.tx, "Welcome to GameImage"),
     Msg::DrawPlatform => frame::platform::platform(self.tx, "Select a Platform"),
     Msg::DrawCreator => frame::creator::creator(self.tx, "Create Packages to Include in the Image"),
+    Msg::DrawHooks => frame::hooks::hooks(self.tx, "Configure the Launch Hooks"),
     Msg::DrawDesktop => frame::desktop::desktop(self.tx, "Select the Desktop Icon"),
     // Linux
     Msg::DrawLinuxName => wizard::linux::name(self.tx, "Select the Application Name"),
@@ -135,6 +145,8 @@ fn redraw(&mut self, msg : Msg)
     Msg::DrawWineConfigure => wizard::wine::configure(self.tx, "Configure Wine"),
     Msg::DrawWineTricks => wizard::wine::winetricks(self.tx, "Install Libraries"),
     Msg::DrawWineEnvironment => wizard::wine::environment(self.tx, "Configure the Environment"),
+    Msg::DrawWineEnvironmentExecutable => wizard::wine::environment_executable(self.tx, "Configure the Executable Environment"),
+    Msg::DrawWineSettings => wizard::wine::settings(self.tx, "Configure Wine Settings"),
     Msg::DrawWineRom => wizard::wine::rom(self.tx, "Install/Test the Application(s)"),
     Msg::DrawWineCompress => wizard::wine::compress(self.tx, "Compress the Created Package"),
     // Retroarch
@@ -169,10 +181,50 @@ fn redraw(&mut self, msg : Msg)
   } // match
 
   content.end();
+  if msg != Msg::Quit { self.frame = Some(msg); }
+  self.block();
   content.redraw();
   app::awake();
 } // }}}
 
+// fn block() {{{
+// Deactivates the content of the current frame while it waits for the queued jobs
+fn block(&mut self)
+{
+  self.is_blocked = self.frame.is_some_and(gameimage::queue::is_blocked);
+  if ! self.is_blocked { return; }
+  let content: Flex = fltk::app::widget_from_id("content").unwrap();
+  shared::fltk::set_active(content, false);
+  // Some frames start their operation with the next button
+  let mut btn_next: Button = fltk::app::widget_from_id("footer_next").unwrap();
+  btn_next.deactivate();
+  let mut status: Output = fltk::app::widget_from_id("footer_status").unwrap();
+  status.set_value(&format!("Waiting for {} queued job(s) to finish", gameimage::queue::count_pending()));
+} // fn: block }}}
+
+// fn jobs() {{{
+// Refreshes the job panel, and draws again the current frame if it waited for the finished jobs or
+// displays their data
+fn jobs(&mut self)
+{
+  if ! gameimage::queue::take_changed() { return; }
+  frame::jobs::refresh();
+  let finished = gameimage::queue::take_finished();
+  let frame = match self.frame
+  {
+    Some(frame) => frame,
+    None => return,
+  }; // match
+  if gameimage::queue::is_blocked(frame)
+  {
+    self.block();
+  } // if
+  else if self.is_blocked || finished.contains(&frame)
+  {
+    self.redraw(frame);
+  } // else if
+} // fn: jobs }}}
+
 // init() {{{
 fn init(&mut self)
 {
@@ -224,6 +276,7 @@ fn init(&mut self)
     {
       Some(common::Msg::WindUpdate) =>
       {
+        self.jobs();
         app::flush();
         app::awake();
       }
@@ -231,6 +284,8 @@ fn init(&mut self)
       {
         shared::fltk::set_active(content, true);
         shared::fltk::set_active(footer, true);
+        frame::common::cancel_set_active(false);
+        self.block();
         app::flush();
         app::awake();
       }
@@ -238,6 +293,7 @@ fn init(&mut self)
       {
         shared::fltk::set_active(content, false);
         shared::fltk::set_active(footer, false);
+        frame::common::cancel_set_active(true);
         app::flush();
         app::awake();
       }
@@ -249,8 +305,42 @@ fn init(&mut self)
 
 } // }}}
 
+// struct Cli {{{
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli
+{
+  #[command(subcommand)]
+  cmd: Option<Cmd>,
+} // struct Cli }}}
+
+// enum Cmd {{{
+#[derive(Subcommand)]
+enum Cmd
+{
+  /// Build a package from a recipe file without opening a window
+  Build
+  {
+    #[arg(long, value_name = "FILE")]
+    recipe: PathBuf,
+  },
+} // enum Cmd }}}
+
 // fn: main {{{
 fn main() {
+  let args = Cli::parse();
+
+  // Build without the gui
+  if let Some(Cmd::Build{ recipe }) = args.cmd
+  {
+    if let Err(e) = headless::build(&recipe)
+    {
+      eprintln!("Build failed: {}", e);
+      std::process::exit(1);
+    } // if
+    return;
+  } // if
+
   let mut gui = GUI.lock().unwrap().clone();
   gui.init();
 } // fn: main }}}
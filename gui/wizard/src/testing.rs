@@ -0,0 +1,173 @@
+// Helpers of the test suites, the backend is replaced by the gameimage-cli-stub binary of the stub
+// crate, which records the requests it receives and replies with scripted events and exit codes
+
+use std::
+{
+  path::{Path,PathBuf},
+  sync::{Mutex,MutexGuard,OnceLock,atomic::Ordering},
+};
+
+use crate::common;
+use crate::lib::ipc::Event;
+
+// The environment is shared by the whole process, tests that change it hold this lock
+static LOCK: Mutex<()> = Mutex::new(());
+
+// pub fn lock() {{{
+pub fn lock() -> MutexGuard<'static, ()>
+{
+  // A failed test must not fail the other ones
+  let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  // Logs go to stderr only, there is no terminal widget in tests
+  common::HEADLESS.store(true, Ordering::Relaxed);
+  guard
+} // fn: lock }}}
+
+pub use shared::testing::TempDir;
+
+// fn copy() {{{
+// Copies the fixture, the '{{dir}}' placeholder in json files is replaced by the destination
+pub fn copy(path_src: &Path, path_dst: &Path, path_root: &Path)
+{
+  for entry in std::fs::read_dir(path_src).expect("Could not read fixture directory")
+  {
+    let path_entry = entry.expect("Could not read fixture entry").path();
+    let path_target = path_dst.join(path_entry.file_name().unwrap_or_default());
+    if path_entry.is_dir()
+    {
+      std::fs::create_dir_all(&path_target).expect("Could not create fixture directory");
+      copy(&path_entry, &path_target, path_root);
+    } // if
+    else if path_entry.extension().is_some_and(|e| e == "json")
+    {
+      let data = std::fs::read_to_string(&path_entry).expect("Could not read fixture file")
+        .replace("{{dir}}", &path_root.to_string_lossy());
+      std::fs::write(&path_target, data).expect("Could not write fixture file");
+    } // else if
+    else
+    {
+      std::fs::copy(&path_entry, &path_target).expect("Could not copy fixture file");
+    } // else
+  } // for
+} // fn: copy }}}
+
+// pub fn fixture() {{{
+// Copies the fixture in tests/fixtures/<name> to a temporary directory
+pub fn fixture(name: &str) -> TempDir
+{
+  let dir = TempDir::new();
+  copy(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name), &dir.path, &dir.path);
+  dir
+} // fn: fixture }}}
+
+// fn stub() {{{
+// The stub is built next to the test binary, in target/<profile>. A test run which did not build the
+// stub crate builds it on the first use
+fn stub() -> PathBuf
+{
+  static PATH: OnceLock<PathBuf> = OnceLock::new();
+  PATH.get_or_init(||
+  {
+    if let Ok(path_file_stub) = std::env::var("GIMG_STUB_BIN") { return PathBuf::from(path_file_stub); } // if
+    let path_dir_profile = std::env::current_exe().expect("Could not get path of the test binary")
+      .parent()
+      .and_then(|e| e.parent())
+      .expect("Could not get target directory")
+      .to_path_buf();
+    let path_file_stub = path_dir_profile.join("gameimage-cli-stub");
+    if path_file_stub.exists() { return path_file_stub; } // if
+    let mut command = std::process::Command::new(std::env::var("CARGO").unwrap_or("cargo".into()));
+    command.args(["build", "-p", "stub"]).current_dir(env!("CARGO_MANIFEST_DIR"));
+    if let Some(path_dir_target) = path_dir_profile.parent() { command.env("CARGO_TARGET_DIR", path_dir_target); } // if
+    if path_dir_profile.ends_with("release") { command.arg("--release"); } // if
+    let is_built = command.status().is_ok_and(|e| e.success());
+    assert!(is_built && path_file_stub.exists()
+      , "Stub '{}' not found and could not be built, build it with 'cargo build -p stub'"
+      , path_file_stub.display()
+    );
+    path_file_stub
+  }).clone()
+} // fn: stub }}}
+
+// pub struct Stub {{{
+// Backend replaced by the stub while alive, its directory is also the build directory
+pub struct Stub
+{
+  pub dir: TempDir,
+  _guard: MutexGuard<'static, ()>,
+} // struct Stub }}}
+
+impl Stub
+{
+
+// pub fn new() {{{
+pub fn new() -> Stub
+{
+  let guard = lock();
+  let path_file_stub = stub();
+  let dir = TempDir::new();
+  // Expose the stub as gameimage-cli
+  let path_dir_bin = dir.path.join("bin");
+  std::fs::create_dir_all(&path_dir_bin).expect("Could not create stub bin directory");
+  std::os::unix::fs::symlink(&path_file_stub, path_dir_bin.join("gameimage-cli")).expect("Could not link stub");
+  static PATH: OnceLock<String> = OnceLock::new();
+  let path = PATH.get_or_init(|| std::env::var("PATH").unwrap_or_default());
+  std::env::set_var("PATH", format!("{}:{}", path_dir_bin.display(), path));
+  std::env::set_var("GIMG_DIR", &dir.path);
+  std::env::set_var("GIMG_STUB_DIR", &dir.path);
+  let stub = Stub{ dir, _guard: guard };
+  stub.reply("version", vec![Event::Item{ value: crate::gameimage::protocol::VERSION.to_string() }], 0);
+  stub
+} // fn: new }}}
+
+// pub fn reply() {{{
+// Scripts the events and exit code of an operation, e.g., 'fetch' or 'fetch.installed'
+pub fn reply(&self, op: &str, events: Vec<Event>, code: i32)
+{
+  let path_file_script = self.dir.path.join("script.json");
+  let mut script : serde_json::Value = std::fs::read_to_string(&path_file_script)
+    .ok()
+    .and_then(|e| serde_json::from_str(&e).ok())
+    .unwrap_or(serde_json::json!({}));
+  script[op] = serde_json::json!({ "events": events, "code": code });
+  std::fs::write(&path_file_script, script.to_string()).expect("Could not write stub script");
+} // fn: reply }}}
+
+// pub fn run() {{{
+// Scripts a shell command the stub runs for the operation before it exits
+pub fn run(&self, op: &str, command: &str)
+{
+  let path_file_script = self.dir.path.join("script.json");
+  let mut script : serde_json::Value = std::fs::read_to_string(&path_file_script)
+    .ok()
+    .and_then(|e| serde_json::from_str(&e).ok())
+    .unwrap_or(serde_json::json!({}));
+  script[op] = serde_json::json!({ "command": command, "code": 0 });
+  std::fs::write(&path_file_script, script.to_string()).expect("Could not write stub script");
+} // fn: run }}}
+
+// pub fn requests() {{{
+// Requests received by the stub, except for the version queries
+pub fn requests(&self) -> Vec<serde_json::Value>
+{
+  std::fs::read_to_string(self.dir.path.join("requests.jsonl"))
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|e| serde_json::from_str::<Vec<String>>(e).ok())
+    .filter_map(|e| e.first().and_then(|e| serde_json::from_str::<serde_json::Value>(e).ok()))
+    .filter(|e| e["op"] != "version")
+    .collect()
+} // fn: requests }}}
+
+// pub fn request() {{{
+// The single request received by the stub
+pub fn request(&self) -> serde_json::Value
+{
+  let requests = self.requests();
+  assert_eq!(requests.len(), 1, "Expected a single request, got {:?}", requests);
+  requests[0].clone()
+} // fn: request }}}
+
+} // impl Stub
+
+// vim: set expandtab fdm=marker ts=2 sw=2 tw=100 et :
@@ -32,10 +32,16 @@ pub fn compress_next(tx: Sender<common::Msg>, term: frame::term::Term)
   let mut term = term.clone();
   std::thread::spawn(move ||
   {
-    let handle = term.dispatch(vec![&backend.string(), r#"{ "op": "compress" }"#], |_| {});
+    let request = gameimage::protocol::Request::new(gameimage::protocol::Command::Compress);
+    let handle = term.dispatch(vec![&backend.string(), &request.to_json()], |_| {});
     match handle
     {
-      Ok(handle) => log_err!(handle.lock().unwrap().wait().map(|_|{})),
+      // Stay in the frame to start again
+      Ok((handle, job)) =>
+      {
+        log_err!(handle.lock().unwrap().wait().map(|_|{}));
+        if job.is_cancelled() { tx.send_awake(common::Msg::WindActivate); return; }
+      },
       Err(e) => log!("{}", e),
     };
     tx.send_activate(common::Msg::DrawCreator);
@@ -61,6 +67,7 @@ pub fn compress(tx: Sender<common::Msg>
         .with_align(fltk::enums::Align::Inside | fltk::enums::Align::Center)
         .with_label("Select the compression level before clicking on start")
       );
+      row.fixed(&frame::common::cancel(), dimm::width_button_wide());
     );
     col.fixed(&row, dimm::height_button_wide());
   );
@@ -8,7 +8,6 @@ use fltk::{
 };
 
 use shared::fltk::WidgetExtExtra;
-use shared::fltk::SenderExt;
 use shared::{hover_blink,column,row,add,fixed};
 
 use crate::dimm;
@@ -56,7 +55,6 @@ pub fn install(tx: Sender<common::Msg>
     Err(e) => log_status!("Could not get items to insert: {}", e),
   }; // match
   // Add new item
-  let clone_tx = tx.clone();
   let clone_label : String = label.to_string();
   let _ = btn_add.clone()
     .with_color(Color::Green)
@@ -77,21 +75,17 @@ pub fn install(tx: Sender<common::Msg>
         log_status!("No file selected");
         return;
       } // if
-      // Install files
-      clone_tx.send_awake(common::Msg::WindDeactivate);
+      // Install files in the background, the list is refreshed once they are installed
       let count = chooser.count()+1;
       let clone_label = clone_label.clone();
       let mut vec_entries : Vec<String> = vec![];
       (1..count).into_iter().for_each(|idx| { vec_entries.push(chooser.value(idx).unwrap()); });
-      std::thread::spawn(move ||
+      let label_job = format!("Install {} {} file(s)", vec_entries.len(), clone_label);
+      gameimage::queue::push(&label_job, vec![msg_curr], move ||
       {
-        match gameimage::install::install(&clone_label, vec_entries.clone())
-        {
-          Ok(_) => log_status!("Installed selected files"),
-          Err(e) => log_status!("Failed to install files: {}", e),
-        }; // match
-        clone_tx.send_activate(msg_curr);
+        gameimage::install::install(&clone_label, vec_entries.clone())
       });
+      log_status!("Queued installation of the selected files");
     });
   // Erase package
   let mut btn_del = btn_del.clone()
@@ -99,34 +93,24 @@ pub fn install(tx: Sender<common::Msg>
   let mut clone_output_status = ui.status.clone();
   let clone_label = label.to_string();
   let clone_frame_list = list.clone();
-  let clone_tx = tx.clone();
   btn_del.set_callback(move |_|
   {
-    clone_tx.send_awake(common::Msg::WindDeactivate);
     let vec_indices = clone_frame_list.selected_items();
     if vec_indices.len() == 0
     {
       clone_output_status.set_value("No item selected for deletion");
-      clone_tx.send_awake(common::Msg::WindActivate);
       return;
     } // if
-    // Remove
-    let clone_tx = clone_tx.clone();
+    // Get items
+    let vec_items : Vec<String> = vec_indices.into_iter().filter_map(|e| clone_frame_list.text(e)).collect();
+    // Remove in the background
     let clone_label = clone_label.clone();
-    let clone_frame_list = clone_frame_list.clone();
-    std::thread::spawn(move ||
+    let label_job = format!("Remove {} {} file(s)", vec_items.len(), clone_label);
+    gameimage::queue::push(&label_job, vec![msg_curr], move ||
     {
-      // Get items
-      let vec_items : Vec<String> = vec_indices.into_iter().map(|e|{ clone_frame_list.text(e).unwrap() }).collect();
-      // Run backend
-      match gameimage::install::remove(&clone_label, vec_items.clone())
-      {
-        Ok(_) => log_status!("Successfully removed files"),
-        Err(e) => log_status!("Failed to remove files: {}", e),
-      }; // match
-      // Redraw GUI
-      clone_tx.send_activate(msg_curr);
-    }); // std::thread
+      gameimage::install::remove(&clone_label, vec_items.clone())
+    });
+    clone_output_status.set_value("Queued removal of the selected files");
   }); // set_callback
   ui
 }
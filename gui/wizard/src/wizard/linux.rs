@@ -5,7 +5,6 @@ use std::
   os::unix::fs::PermissionsExt,
 };
 
-use serde_json::json;
 
 use std::io::Write;
 
@@ -35,6 +34,7 @@ use crate::db;
 use crate::frame;
 use crate::wizard;
 use crate::gameimage;
+use crate::lib;
 
 // pub fn name() {{{
 pub fn name(tx: Sender<common::Msg>, title: &str)
@@ -185,7 +185,7 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
     let term = frame::term::Term::default();
     col.add(&term.group);
     col.fixed(&Frame::default()
-        .with_label("Click on this field to search for a file to execute")
+        .with_label("Click on this field to search for a file to execute, archives and disc images are extracted")
         .with_align(Align::Left | Align::Inside)
       , dimm::height_text()
     );
@@ -252,12 +252,29 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
   let clone_input_cmd = input_cmd.clone();
   input_script.set_callback(move |e|
   {
-    let str_choice = match fltk::dialog::file_chooser("Select the script to execute", "*.{sh}", ".", false)
+    let str_choice = match fltk::dialog::file_chooser("Select the script to execute or the archive to extract"
+      , "*.{sh,run,bin,zip,7z,rar,tar,gz,tgz,xz,txz,bz2,tbz2,zst,tzst,iso,cue,img}"
+      , "."
+      , false)
     {
       Some(str_choice) => str_choice,
       None => { log_status!("No file selected"); return; },
     }; // if
 
+    // Extract archives and disc images into the application directory
+    if let Some(source) = lib::source::sources(&[PathBuf::from(&str_choice)]).pop()
+      && source.kind != lib::source::Kind::Installer
+    {
+      let path_dir_dst = match db::global::get_current_project()
+      {
+        Ok(project) => project.path_dir_project.join("linux").join(source.stem()),
+        Err(e) => { log_status!("Error to get current project '{}'", e); return; },
+      }; // match
+      gameimage::queue::push(&format!("Extract '{}'", source.name()), vec![], move || source.extract(&path_dir_dst));
+      log_status!("Queued extraction of '{}', see the job panel for progress", str_choice);
+      return;
+    } // if
+
     // Set displayed path
     e.set_value("");
     let _ = e.insert(&str_choice);
@@ -272,16 +289,20 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
     // The dispatch command kills the previous process in the terminal
     let mut clone_input_cmd = clone_input_cmd.clone();
 
-    let mut json_args = json!({});
-    json_args["op"] = "install".into();
-    json_args["install"]["op"] = "install".into();
-    json_args["install"]["sub_op"] = "rom".into();
-    json_args["install"]["args"] = vec![str_choice.clone()].into();
+    let request = gameimage::protocol::Request::new(gameimage::protocol::Command::Install
+    {
+      install: gameimage::protocol::Install
+      {
+        op: gameimage::protocol::InstallOp::Install,
+        sub_op: gameimage::protocol::SubOp::Rom,
+        args: vec![str_choice.clone()],
+      }
+    });
     match clone_term.dispatch(vec![&crate::gameimage::gameimage::binary().unwrap_or_default().string()
-      , &json_args.to_string()]
+      , &request.to_json()]
       , |_| {})
     {
-      Ok(arc_child) =>
+      Ok((arc_child, _)) =>
       {
         // Make the global process available to both callbacks
         *clone_arc_process.lock().unwrap() = Some(arc_child.clone());
@@ -1,19 +1,24 @@
 use std::env;
+use std::path::PathBuf;
 
 // Gui
 use fltk::prelude::*;
 use fltk::{
   app::Sender,
   input::Input,
+  output::Output,
   frame::Frame,
-  enums::{Align,FrameType},
+  dialog::file_chooser,
+  enums::{Align,FrameType,Color},
 };
 
 use anyhow::anyhow as ah;
 
 use shared::fltk::SenderExt;
 use shared::svg;
-use shared::{column,row,fixed};
+use shared::fltk::WidgetExtExtra;
+use shared::std::PathBufExt;
+use shared::{column,row,add,fixed,hover_blink};
 
 use crate::dimm;
 use crate::frame;
@@ -46,6 +51,9 @@ fn name_next() -> anyhow::Result<()>
     Ok(_) => (),
     Err(e) => return Err(ah!("Could not init project: {}", e)),
   } // match
+  // Selections of the previous project do not carry over to the new one
+  frame::icon::reset();
+  crate::wizard::wine::reset();
 
   Ok(())
 } // fn name_next() }}}
@@ -57,6 +65,7 @@ pub fn name(tx: Sender<common::Msg>
   , msg_next: common::Msg)
 {
   let ui = crate::GUI.lock().unwrap().ui.clone()(title);
+  let is_wine = *frame::platform::PLATFORM.lock().unwrap() == Some(common::Platform::Wine);
   // Layout
   column!(col,
     col.add(&Frame::default());
@@ -68,6 +77,13 @@ pub fn name(tx: Sender<common::Msg>
     col.fixed(&row_icon, 150);
     col.add(&Frame::default());
     fixed!(col, input_name, Input::default(), dimm::height_button_wide());
+    // Windows executables have the game name in their version resource
+    row!(row_executable,
+      add!(row_executable, output_executable, Output::default());
+      fixed!(row_executable, btn_executable, shared::fltk::button::rect::search(), dimm::width_button_rec());
+    );
+    col.fixed(&row_executable, dimm::height_button_wide());
+    if ! is_wine { row_executable.clone().hide(); } // if
   );
   // Configure icon box
   let mut frame_icon = frame_icon.clone();
@@ -108,6 +124,36 @@ pub fn name(tx: Sender<common::Msg>
     } // if
     return false;
   });
+  // Suggest the name from the executable of the game
+  let mut output_executable = output_executable.clone();
+  output_executable.set_tooltip("Executable to read the name, icon and release year from");
+  if let Some((path_file_executable, _)) = crate::wizard::wine::EXECUTABLE.lock().unwrap().as_ref()
+  {
+    output_executable.set_value(&path_file_executable.string());
+  } // if
+  hover_blink!(btn_executable);
+  let mut clone_input_name = input_name.clone();
+  btn_executable.clone().with_color(Color::Green).set_callback(move |_|
+  {
+    let str_choice = match file_chooser("Select the game executable", "*.{exe,EXE}", ".", false)
+    {
+      Some(str_choice) => str_choice,
+      None => { log_status!("No file selected"); return; }
+    }; // match
+    let metadata = match crate::wizard::wine::executable(&PathBuf::from(&str_choice))
+    {
+      Ok(metadata) => metadata,
+      Err(e) => { log_status!("Could not read executable: {}", e); return; }
+    }; // match
+    output_executable.set_value(&str_choice);
+    let name = metadata.product_name.unwrap_or_else(||
+    {
+      PathBuf::from(&str_choice).file_stem().map(|e| e.to_string_lossy().to_string()).unwrap_or_default()
+    });
+    let name = f_sanitize(name);
+    clone_input_name.set_value(&name);
+    env::set_var("GIMG_NAME", name);
+  });
   // Callback to previous
   ui.btn_prev.clone().emit(tx, msg_prev);
   // Callback to Next
@@ -45,7 +45,7 @@ pub fn icon(tx: Sender<common::Msg>, title: &str)
 } // }}}
 
 // fn rom_callback_add() {{{
-fn rom_callback_add(tx: Sender<common::Msg>, label: String)
+fn rom_callback_add(label: String)
 {
   // Pick files to install
   let mut chooser = dialog::FileChooser::new("."
@@ -62,20 +62,15 @@ fn rom_callback_add(tx: Sender<common::Msg>, label: String)
     log_status!("No file selected");
     return;
   } // if
-  // Install files
-  tx.send_awake(common::Msg::WindDeactivate);
+  // Install files in the background
   let mut vec_entries : Vec<String> = vec![];
   (1..chooser.count()+1).into_iter().for_each(|idx| { vec_entries.push(chooser.value(idx).unwrap()); });
-  let clone_tx = tx.clone();
-  std::thread::spawn(move ||
+  let label_job = format!("Install {} {} file(s)", vec_entries.len(), label);
+  gameimage::queue::push(&label_job, vec![common::Msg::DrawRetroarchRom], move ||
   {
-    match gameimage::install::install(&label, vec_entries.clone())
-    {
-      Ok(_) => log_status!("Installed selected files"),
-      Err(e) => log_status!("Failed to install files: {}", e),
-    }; // match
-    clone_tx.send_activate(common::Msg::DrawRetroarchRom);
+    gameimage::install::install(&label, vec_entries.clone())
   });
+  log_status!("Queued installation of the selected files");
 } // fn rom_callback_add() }}}
 
 // fn rom_callback_del() {{{
@@ -188,7 +183,7 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
   // Add new item
   btn_add.clone()
     .with_color(Color::Green)
-    .with_callback(#[clown] move |_| { rom_callback_add(honk!(tx), LABEL.into()); });
+    .with_callback(#[clown] move |_| { rom_callback_add(LABEL.into()); });
   // Erase package
   btn_del.clone()
     .with_color(Color::Red)
@@ -91,22 +91,14 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
       log_status!("No file selected");
       return;
     } // if
-    // Deactivate window
-    clone_tx.send_awake(common::Msg::WindDeactivate);
     // Fetch choice
     let str_choice = chooser.value(1).unwrap();
-    // Install
-    let clone_tx = clone_tx.clone();
-    std::thread::spawn(move ||
+    // Install directory with backend in the background
+    gameimage::queue::push(&format!("Install rom '{}'", str_choice), vec![common::Msg::DrawRpcs3Rom], move ||
     {
-      // Install directory with backend
-      match gameimage::install::install("rom", vec![str_choice])
-      {
-        Ok(_) => log_status!("Successfully installed rom"),
-        Err(e) => log_status!("Failed to install rom: {}", e),
-      } // match
-      clone_tx.send_activate(common::Msg::DrawRpcs3Rom);
+      gameimage::install::install("rom", vec![str_choice.clone()])
     });
+    log_status!("Queued rom installation");
   });
   // Erase package
   let mut btn_del = btn_del.clone()
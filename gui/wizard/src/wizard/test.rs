@@ -5,8 +5,6 @@ use fltk::{
   enums::Color,
 };
 
-use serde_json::json;
-
 use shared::fltk::SenderExt;
 use shared::fltk::WidgetExtExtra;
 use shared::std::PathBufExt;
@@ -32,6 +30,7 @@ pub fn test(tx: Sender<common::Msg>
     row!(row,
       row.add(&fltk::frame::Frame::default());
       fixed!(row, btn_test, shared::fltk::button::wide::default(), dimm::width_button_wide());
+      row.fixed(&frame::common::cancel(), dimm::width_button_wide());
       row.add(&fltk::frame::Frame::default());
     );
     col.fixed(&row, dimm::height_button_wide());
@@ -55,9 +54,8 @@ pub fn test(tx: Sender<common::Msg>
       Ok(backend) => backend,
       Err(e) => { log_alert!("Error to execute backend: {}", e); return; }
     };
-    let mut json_args = json!({});
-    json_args["op"] = "test".into();
-    let _ = term.dispatch(vec![&backend.string(), &json_args.to_string()], move |_|
+    let request = gameimage::protocol::Request::new(gameimage::protocol::Command::Test);
+    let _ = term.dispatch(vec![&backend.string(), &request.to_json()], move |_|
     {
       clone_tx.send_awake(common::Msg::WindActivate);
     });
@@ -3,6 +3,7 @@ use std::
 {
   path,
   path::PathBuf,
+  collections::BTreeSet,
   sync::{Arc,Mutex,LazyLock}
 };
 
@@ -18,7 +19,6 @@ use fltk::{
 
 use clown::clown;
 use anyhow::anyhow as ah;
-use serde_json::json;
 
 use shared::fltk::WidgetExtExtra;
 use shared::fltk::SenderExt;
@@ -37,89 +37,45 @@ use crate::common;
 use crate::frame;
 use crate::wizard;
 use crate::gameimage;
+use crate::lib;
 
-// fn library_common() {{{
-fn library_common() -> Vec<&'static str>
-{
-  vec![
-    "xact",
-    "xact_x64",
-    "xinput",
-    "binkw32",
-    "xaudio29",
-    "openal",
-  ]
-} // fn library_common() }}}
-
-// fn library_vcrun() {{{
-fn library_vcrun(year: u32) -> Vec<&'static str>
-{
-  match year
-  {
-    val if val < 2003 => vec!["vcrun6"],
-    2003..=2008       => vec!["vcrun2003", "vcrun2005", "vcrun2008"],
-    2009..=2011       => vec!["vcrun2005", "vcrun2008", "vcrun6sp6", "vcrun2010",],
-    2012..=2015       => vec!["vcrun2008", "vcrun2012", "vcrun2013",],
-    2016..=2019       => vec!["vcrun2013", "vcrun2015", "vcrun2017",],
-    _                 => vec!["vcrun2017", "vcrun2019", "vcrun2022",],
-  }
-} // fn library_vcrun() }}}
-
-// fn library_vbrun() {{{
-fn library_vbrun(year: u32) -> Vec<&'static str>
-{
-  match year
-  {
-    val if val <= 1993 => vec!["vb2run"],
-    1994..=1998 => vec!["vb2run","vb3run", "vb4run"],
-    1999..=2001 => vec!["vb3run", "vb4run", "dx8vb", "vb5run"],
-    _ => vec!["vb6run", "dx8vb"],
-  }
-} // fn library_vbrun() }}}
-
-// fn library_dotnet() {{{
-fn library_dotnet(year: u32) -> Vec<&'static str>
-{
-  match year
-  {
-    var if var <= 2004  => vec!["dotnet11","dotnet11sp1",],
-    2005..=2006         => vec!["dotnet11sp1","dotnet20","dotnet30",],
-    2007                => vec!["dotnet20","dotnet30sp1","dotnet35",],
-    2008                => vec!["dotnet20sp1","dotnet35sp1",],
-    2009..=2011         => vec!["dotnet20sp2","dotnet40","dotnet35sp1",],
-    2012                => vec!["dotnet45","dotnet452","dotnet35sp1",],
-    2013..=2015         => vec!["dotnet35sp1","dotnet461","dotnet46",],
-    2016                => vec!["dotnet35sp1","dotnet46","dotnet462",],
-    2017..=2018         => vec!["dotnet35sp1", "dotnet46", "dotnet471","dotnet472",],
-    2019                => vec!["dotnet471","dotnet472","dotnet48",],
-    2020                => vec!["dotnet471","dotnet472","dotnet48","dotnetcore2","dotnetcore3",],
-    2023                => vec!["dotnet48","dotnetcore2","dotnetcore3","dotnet6","dotnet7",],
-    _                   => vec!["dotnetcore2","dotnetcore3","dotnet6","dotnet7","dotnet8",],
-  }
-} // fn library_dotnet() }}}
-
-// fn library_wmp() {{{
-#[allow(dead_code)] fn library_wmp(year: u32) -> Vec<&'static str>
-{
-  match year
-  {
-    val if val < 2006 => vec!["wmp9"],
-    val if val < 2007 => vec!["wmp10"],
-    _                 => vec!["wmp11"],
-  }
-} // fn library_wmp() }}}
-
-// fn get_recomends_winetricks() {{{
-fn get_recomends_winetricks(year: u32) -> Vec<&'static str>
-{
-  let mut libraries: Vec<&'static str> = vec![];
-  libraries.append(&mut library_common());
-  libraries.append(&mut library_vcrun(year));
-  libraries.append(&mut library_vbrun(year));
-  libraries.append(&mut library_dotnet(year));
-  // libraries.append(&mut library_wmp(year));
-  libraries
-} // fn get_recomends_winetricks() }}}
+// Executable picked to read the game metadata from, with its version information
+pub static EXECUTABLE: LazyLock<Mutex<Option<(PathBuf, lib::pe::Metadata)>>> = LazyLock::new(|| Mutex::new(None));
+
+// pub fn executable() {{{
+// Reads the metadata of the executable, later frames use it to suggest the icon and release year
+pub fn executable(path_file_executable: &path::Path) -> anyhow::Result<lib::pe::Metadata>
+{
+  let metadata = lib::pe::metadata(path_file_executable)?;
+  log_status!("Read '{}' by '{}', version '{}', built in {}, {}"
+    , metadata.product_name.as_deref().unwrap_or("unknown")
+    , metadata.company_name.as_deref().unwrap_or("unknown")
+    , metadata.file_version.as_deref().unwrap_or("unknown")
+    , metadata.year().map(|e| e.to_string()).unwrap_or("unknown".into())
+    , metadata.arch()
+  );
+  *EXECUTABLE.lock().unwrap() = Some((path_file_executable.to_path_buf(), metadata.clone()));
+  Ok(metadata)
+} // fn: executable }}}
+
+// pub fn reset() {{{
+// Forgets the executable of the previous project
+pub fn reset()
+{
+  *EXECUTABLE.lock().unwrap() = None;
+} // fn: reset }}}
+
+// fn executable_icon() {{{
+// Extracts the icon of the executable into the project directory
+fn executable_icon(path_file_executable: &path::Path) -> anyhow::Result<PathBuf>
+{
+  let png = lib::pe::icon(path_file_executable)?;
+  let path_dir_icon = get_path_db()?.join("icon");
+  std::fs::create_dir_all(&path_dir_icon)?;
+  let path_file_icon = path_dir_icon.join("icon.executable.png");
+  std::fs::write(&path_file_icon, png)?;
+  Ok(path_file_icon)
+} // fn: executable_icon }}}
 
 // pub fn name() {{{
 pub fn name(tx: Sender<common::Msg>, title: &str)
@@ -133,6 +89,19 @@ pub fn name(tx: Sender<common::Msg>, title: &str)
 // pub fn icon() {{{
 pub fn icon(tx: Sender<common::Msg>, title: &str)
 {
+  // Offer the icon embedded in the executable
+  let path_file_executable = EXECUTABLE.lock().unwrap().as_ref().map(|e| e.0.clone());
+  if let Some(path_file_executable) = path_file_executable
+  {
+    match executable_icon(&path_file_executable)
+    {
+      Ok(path_file_icon) => if frame::icon::suggest(path_file_icon)
+      {
+        log_status!("Using the icon of '{}'", path_file_executable.string());
+      },
+      Err(e) => log!("Could not extract icon from '{}': {}", path_file_executable.string(), e),
+    } // match
+  } // if
   frame::icon::project(tx.clone()
     , title
     , common::Msg::DrawWineName
@@ -164,6 +133,14 @@ fn get_path_db_env() -> anyhow::Result<PathBuf>
   Ok(path_file_db)
 } // get_path_db_env() }}}
 
+// get_path_db_env_executable() {{{
+fn get_path_db_env_executable() -> anyhow::Result<PathBuf>
+{
+  let mut path_file_db = get_path_db()?;
+  path_file_db.push("gameimage.env.executable.json");
+  Ok(path_file_db)
+} // get_path_db_env_executable() }}}
+
 // get_path_db_args() {{{
 fn get_path_db_args() -> anyhow::Result<PathBuf>
 {
@@ -180,16 +157,132 @@ fn get_path_db_alias() -> anyhow::Result<PathBuf>
   Ok(path_file_db)
 } // get_path_db_alias() }}}
 
+// get_path_dir_prefix() {{{
+fn get_path_dir_prefix() -> anyhow::Result<PathBuf>
+{
+  Ok(db::project::current()?.get_dir_self()?.join("wine"))
+} // get_path_dir_prefix() }}}
+
+// get_path_dir_snapshots() {{{
+// Snapshots live in the build directory, outside of the project which is packaged
+fn get_path_dir_snapshots() -> anyhow::Result<PathBuf>
+{
+  let global = db::global::read()?;
+  Ok(global.path_dir_build.join("snapshots").join(&global.project))
+} // get_path_dir_snapshots() }}}
+
+// snapshot() {{{
+// Takes a snapshot of the prefix before an action which modifies it, no-op without a prefix
+fn snapshot(label: &str) -> anyhow::Result<()>
+{
+  let path_dir_prefix = get_path_dir_prefix()?;
+  if ! path_dir_prefix.exists()
+  {
+    log!("Skipping snapshot '{}', the wine prefix '{}' does not exist yet", label, path_dir_prefix.string());
+    return Ok(());
+  } // if
+  log_status!("Taking snapshot of the wine prefix...");
+  lib::snapshot::create(&path_dir_prefix, &get_path_dir_snapshots()?, label, lib::snapshot::COUNT_MAX)?;
+  log_status!("Took snapshot '{}'", label);
+  Ok(())
+} // snapshot() }}}
+
+// Executable of the environment frame, relative to the project directory
+static ENVIRONMENT_EXECUTABLE : LazyLock<Mutex<Option<String>>> = LazyLock::new(|| Mutex::new(None));
+
+// env_read() {{{
+// Variables of the executable, or of the project without an executable
+fn env_read(executable: &Option<String>) -> std::collections::HashMap<String, String>
+{
+  match executable
+  {
+    Some(executable) => shared::db::env::read_executable(&get_path_db_env_executable().unwrap_or_default(), executable),
+    None => shared::db::kv::read(&get_path_db_env().unwrap_or_default()).unwrap_or_default(),
+  } // match
+} // env_read() }}}
+
+// env_write() {{{
+fn env_write(executable: &Option<String>, key: &str, value: &str) -> anyhow::Result<()>
+{
+  match executable
+  {
+    Some(executable) => shared::db::env::write(&get_path_db_env_executable()?, executable, key, value),
+    None => shared::db::kv::write(&get_path_db_env()?, &key.to_string(), &value.to_string()),
+  } // match
+} // env_write() }}}
+
+// env_erase() {{{
+fn env_erase(executable: &Option<String>, key: &str) -> anyhow::Result<()>
+{
+  match executable
+  {
+    Some(executable) => shared::db::env::erase(&get_path_db_env_executable()?, executable, key),
+    None => shared::db::kv::erase(&get_path_db_env()?, key.to_string()),
+  } // match
+} // env_erase() }}}
+
 // pub fn environment() {{{
 pub fn environment(tx: Sender<common::Msg>, title: &str)
 {
+  environment_frame(tx, title, None);
+} // }}}
+
+// pub fn environment_executable() {{{
+// Environment of the executable selected in the rom frame, applied over the one of the project
+pub fn environment_executable(tx: Sender<common::Msg>, title: &str)
+{
+  match ENVIRONMENT_EXECUTABLE.lock().unwrap().clone()
+  {
+    Some(executable) => environment_frame(tx, title, Some(executable)),
+    None => { log_status!("No executable selected"); tx.send_awake(common::Msg::DrawWineRom); },
+  } // match
+} // }}}
+
+// fn environment_frame() {{{
+fn environment_frame(tx: Sender<common::Msg>, title: &str, executable: Option<String>)
+{
+  // Message to redraw this frame
+  let msg_draw = if executable.is_some() { common::Msg::DrawWineEnvironmentExecutable } else { common::Msg::DrawWineEnvironment };
   // Fetch ui
   let ui = crate::GUI.lock().unwrap().ui.clone()(title);
   // Layout
   row!(row,
-    scroll!(scroll,
-      hpack!(col_content,);
+    column!(col,
+      col.set_spacing(dimm::border_half());
+      // Executable and its dll overrides
+      if let Some(executable) = executable.clone()
+      {
+        let mut output_executable = output::Output::default();
+        col.fixed(&output_executable, dimm::height_button_wide());
+        output_executable.set_value(&executable);
+        col.fixed(&Frame::default()
+          .with_align(Align::Inside | Align::Left)
+          .with_label("DLL overrides, e.g., d3d9=n,b;dinput8=n"), dimm::height_text()
+        );
+        let mut input_overrides : fltk_evented::Listener<_> = fltk::input::Input::default().into();
+        col.fixed(&input_overrides.as_base_widget(), dimm::height_button_wide());
+        input_overrides.set_value(env_read(&Some(executable.clone()))
+          .get(shared::db::env::DLL_OVERRIDES)
+          .map(|e| e.as_str())
+          .unwrap_or_default()
+        );
+        input_overrides.on_keyup(move |e|
+        {
+          let executable = Some(executable.clone());
+          let result = match e.value().trim()
+          {
+            "" => env_erase(&executable, shared::db::env::DLL_OVERRIDES),
+            value => env_write(&executable, shared::db::env::DLL_OVERRIDES, value),
+          }; // match
+          log_err_status!(result);
+        });
+        col.fixed(&shared::fltk::separator::horizontal(col.w()), dimm::height_sep());
+      } // if
+      scroll!(scroll,
+        hpack!(col_content,);
+      );
     );
+    row.add(&col);
     column!(col_btn,
       fixed!(col_btn, btn_add, shared::fltk::button::rect::add(), dimm::height_button_rec());
       col_btn.add(&Frame::default());
@@ -200,7 +293,7 @@ pub fn environment(tx: Sender<common::Msg>, title: &str)
   hover_blink!(btn_add);
   // Configure footer
   ui.btn_next.clone().deactivate();
-  ui.btn_prev.clone().emit(tx, common::Msg::DrawWineConfigure);
+  ui.btn_prev.clone().emit(tx, if executable.is_some() { common::Msg::DrawWineRom } else { common::Msg::DrawWineConfigure });
   // Resize content to scroll width
   scroll.set_type(fltk::group::ScrollType::VerticalAlways);
   scroll.resize_callback({
@@ -212,6 +305,7 @@ pub fn environment(tx: Sender<common::Msg>, title: &str)
   });
   // Create entries
   let clone_tx = tx.clone();
+  let clone_executable = executable.clone();
   let f_make_entry = move |key : String, val : String| -> fltk::group::Pack
   {
     hpack!(col,
@@ -238,32 +332,32 @@ pub fn environment(tx: Sender<common::Msg>, title: &str)
     // Erase button
     let clone_key = key.clone();
     let clone_tx = clone_tx.clone();
+    let clone_executable = clone_executable.clone();
     let mut btn_del = btn_del.clone();
     btn_del.set_color(Color::Red);
     btn_del.set_callback(move |_|
     {
-      let path_file_db = get_path_db_env().unwrap_or_default();
-      match shared::db::kv::erase(&path_file_db, clone_key.clone())
+      match env_erase(&clone_executable, &clone_key)
       {
         Ok(_) => log_status!("Erased key '{}'", clone_key),
         Err(e) => log_status!("Failed to erase key '{}' with error '{}'", clone_key, e.to_string()),
       } // if
-      clone_tx.send_awake(common::Msg::DrawWineEnvironment);
+      clone_tx.send_awake(msg_draw);
     });
     col
   };
 
-  // Get current database entries
-  let path_file_db = get_path_db_env().unwrap_or_default();
+  // Get current database entries, the dll overrides of the executable have their own input
   rescope!(col_content,
-    if let Ok(entries) = shared::db::kv::read(&path_file_db)
+    let mut entries : Vec<(String, String)> = env_read(&executable).into_iter()
+      .filter(|(key, _)| executable.is_none() || key != shared::db::env::DLL_OVERRIDES)
+      .collect();
+    entries.sort();
+    for (key, val) in entries
     {
-      for (key, val) in entries
-      {
-        let group = f_make_entry(key, val);
-        col_content.add(&group);
-      } // for
-    } // if
+      let group = f_make_entry(key, val);
+      col_content.add(&group);
+    } // for
   );
 
   // Add var button
@@ -274,23 +368,23 @@ pub fn environment(tx: Sender<common::Msg>, title: &str)
     let dialog = shared::fltk::dialog::key_value();
     let clone_dialog = dialog.clone();
     let clone_tx = clone_tx.clone();
-    let clone_path_file_db = path_file_db.clone();
+    let clone_executable = executable.clone();
     dialog.btn_ok.clone().set_callback(move |_|
     {
       clone_dialog.wind.clone().hide();
       let key = clone_dialog.input_key.value();
       let value = clone_dialog.input_value.value();
       if key.is_empty() { return; }
-      match shared::db::kv::write(&clone_path_file_db, &key, &value)
+      match env_write(&clone_executable, &key, &value)
       {
         Ok(_) => log_status!("Set key '{}' with value '{}'", key.clone(), value.clone()),
         Err(e) => log_status!("Failed to set key '{}' with error '{}'", key, e.to_string()),
       } // if
-      clone_tx.send_awake(common::Msg::DrawWineEnvironment);
+      clone_tx.send_awake(msg_draw);
     });
     dialog.wind.clone().show();
   });
-} // }}}
+} // fn: environment_frame }}}
 
 // fn configure_entry() {{{
 fn configure_entry(tx: Sender<common::Msg>
@@ -324,22 +418,333 @@ fn configure_entry(tx: Sender<common::Msg>
     tx.send_awake(common::Msg::WindDeactivate);
     std::thread::spawn(move ||
     {
-      let mut json_args = json!({});
-      json_args["op"] = "install".into();
-      json_args["install"]["op"] = "install".into();
-      json_args["install"]["sub_op"] = args_owned.get(0).map(|e| e.clone()).unwrap_or_default().into();
-      json_args["install"]["args"] = args_owned.get(1..).into();
-      if gameimage::gameimage::gameimage_sync(vec![&json_args.to_string()]) != 0
+      if let Err(e) = snapshot(&format!("Before '{}'", args_owned.join(" ")))
       {
-        log_status!("Command exited with non-zero status");
-      } // else
-      tx.send_awake(common::Msg::WindActivate);
+        log_status!("Could not take snapshot of the wine prefix: {}", e);
+      } // if
+      let str_sub_op = args_owned.get(0).map(|e| e.clone()).unwrap_or_default();
+      let args = args_owned.get(1..).map(|e| e.to_vec()).unwrap_or_default();
+      match gameimage::install::install(&str_sub_op, args)
+      {
+        Ok(()) => (),
+        Err(e) if gameimage::job::is_cancelled(&e) => log_status!("Cancelled"),
+        Err(e) => log_status!("Command exited with non-zero status: {}", e),
+      } // match
+      tx.send_activate(common::Msg::DrawWineConfigure);
     });
   });
   // Return row with label and button
   btn
 } // fn configure_entry() }}}
 
+// fn configure_snapshots() {{{
+// Snapshots of the wine prefix, any of them can be restored
+fn configure_snapshots(tx: Sender<common::Msg>)
+{
+  let path_dir_snapshots = match get_path_dir_snapshots()
+  {
+    Ok(path_dir_snapshots) => path_dir_snapshots,
+    Err(e) => log_return_void!("Could not get snapshots directory: {}", e),
+  }; // match
+  let snapshots = match lib::snapshot::list(&path_dir_snapshots)
+  {
+    Ok(snapshots) => snapshots,
+    Err(e) => log_return_void!("Could not list snapshots: {}", e),
+  }; // match
+  // Header with the button to take a snapshot on demand
+  row!(row_header,
+    row_header.add(&Frame::default()
+      .with_align(Align::Inside | Align::Left)
+      .with_label(&format!("Snapshots of the wine prefix ({}/{})", snapshots.len(), lib::snapshot::COUNT_MAX)));
+    fixed!(row_header, btn_take, shared::fltk::button::rect::save(), dimm::width_button_rec());
+  );
+  row_header.resize(row_header.x(), row_header.y(), row_header.w(), dimm::height_button_rec());
+  hover_blink!(btn_take);
+  let mut btn_take = btn_take.clone().with_color(Color::Green);
+  btn_take.set_tooltip("Take a snapshot of the wine prefix");
+  btn_take.set_callback(move |_|
+  {
+    tx.send_awake(common::Msg::WindDeactivate);
+    std::thread::spawn(move ||
+    {
+      log_err_status!(snapshot("Taken manually"));
+      tx.send_activate(common::Msg::DrawWineConfigure);
+    });
+  });
+  for entry in snapshots
+  {
+    row!(row,
+      row.add(&shared::fltk::frame::bordered()
+        .with_align(Align::Inside | Align::Left)
+        .with_label(&format!(" {} ({})", entry.label, lib::snapshot::age(&entry))));
+      fixed!(row, btn_restore, shared::fltk::button::rect::arrow_backward(), dimm::width_button_rec());
+      fixed!(row, btn_del, shared::fltk::button::rect::del(), dimm::width_button_rec());
+    );
+    row.resize(row.x(), row.y(), row.w(), dimm::height_button_rec());
+    hover_blink!(btn_restore);
+    hover_blink!(btn_del);
+    // Restore the snapshot, the current state of the prefix is lost
+    let label = entry.label.clone();
+    let clone_path_dir_snapshots = path_dir_snapshots.clone();
+    let mut btn_restore = btn_restore.clone().with_color(Color::Blue);
+    btn_restore.set_tooltip("Restore this snapshot");
+    btn_restore.set_callback(move |_|
+    {
+      if dialog::choice2_default(&format!("Restore snapshot '{}'? Changes made after it are lost.", label), "No", "Yes", "") != Some(1)
+      {
+        return;
+      } // if
+      let path_dir_snapshots = clone_path_dir_snapshots.clone();
+      tx.send_awake(common::Msg::WindDeactivate);
+      std::thread::spawn(move ||
+      {
+        log_status!("Restoring snapshot...");
+        match get_path_dir_prefix().and_then(|e| lib::snapshot::restore(&e, &path_dir_snapshots, entry.id))
+        {
+          Ok(()) => log_status!("Restored snapshot"),
+          Err(e) => log_status!("Could not restore snapshot: {}", e),
+        } // match
+        tx.send_activate(common::Msg::DrawWineConfigure);
+      });
+    });
+    // Remove the snapshot
+    let clone_path_dir_snapshots = path_dir_snapshots.clone();
+    let mut btn_del = btn_del.clone().with_color(Color::Red);
+    btn_del.set_tooltip("Remove this snapshot");
+    btn_del.set_callback(move |_|
+    {
+      log_err_status!(lib::snapshot::remove(&clone_path_dir_snapshots, entry.id));
+      tx.send_awake(common::Msg::DrawWineConfigure);
+    });
+  } // for
+} // fn: configure_snapshots }}}
+
+// Findings of the last check of the prefix
+static HEALTH : LazyLock<Mutex<Option<Vec<lib::health::Finding>>>> = LazyLock::new(|| Mutex::new(None));
+
+// fn configure_health() {{{
+// Findings of the last check of the prefix, with their suggested fixes
+fn configure_health()
+{
+  let findings = match HEALTH.lock().unwrap().clone()
+  {
+    Some(findings) => findings,
+    None => return,
+  }; // match
+  let frame_header = Frame::default()
+    .with_align(Align::Inside | Align::Left)
+    .with_label("Prefix check");
+  frame_header.clone().resize(frame_header.x(), frame_header.y(), frame_header.w(), dimm::height_text());
+  for entry in findings
+  {
+    let mut frame_title = shared::fltk::frame::bordered()
+      .with_align(Align::Inside | Align::Left)
+      .with_label(&format!(" {}: {}", entry.severity.as_str(), entry.title));
+    frame_title.resize(frame_title.x(), frame_title.y(), frame_title.w(), dimm::height_button_rec());
+    match entry.severity
+    {
+      lib::health::Severity::Error => frame_title.set_label_color(Color::Red),
+      lib::health::Severity::Warning => frame_title.set_label_color(Color::Yellow),
+      lib::health::Severity::Info => (),
+    } // match
+    if let Some(fix) = entry.fix
+    {
+      let mut frame_fix = Frame::default()
+        .with_align(Align::Inside | Align::Left | Align::Wrap)
+        .with_label(&format!(" Fix: {}", fix));
+      frame_fix.resize(frame_fix.x(), frame_fix.y(), frame_fix.w(), dimm::height_text());
+      frame_fix.set_tooltip(&fix);
+    } // if
+  } // for
+} // fn: configure_health }}}
+
+// fn settings_menu() {{{
+// Row with the label of a setting and a menu of its values, none keeps the default of wine
+fn settings_menu(label: &str
+  , current: Option<String>
+  , values: &[(&str, &str)]
+  , f_set: impl Fn(Option<String>) + 'static)
+{
+  let values : Vec<(Option<String>, String)> = [(None, "Default".to_string())].into_iter()
+    .chain(values.iter().map(|(value, label)| (Some(value.to_string()), label.to_string())))
+    .collect();
+  row!(row,
+    row.fixed(&Frame::default().with_align(Align::Inside | Align::Left).with_label(label), dimm::width_button_wide() * 2);
+    add!(row, menu, fltk::menu::MenuButton::default());
+  );
+  row.resize(row.x(), row.y(), row.w(), dimm::height_button_wide());
+  let mut menu = menu.clone();
+  for (_, label) in values.iter() { menu.add_choice(label); } // for
+  // Values unknown to the form are shown as they are in the registry
+  match values.iter().find(|e| e.0 == current)
+  {
+    Some((_, label)) => menu.set_label(label),
+    None => menu.set_label(current.as_deref().unwrap_or_default()),
+  } // match
+  menu.set_callback(move |e|
+  {
+    if let Some((value, label)) = values.get(e.value() as usize)
+    {
+      e.set_label(label);
+      f_set(value.clone());
+    } // if
+  });
+} // fn: settings_menu }}}
+
+// fn regedit() {{{
+// Imports the registry fragment into the prefix
+fn regedit(fragment: &lib::registry::Fragment, name: &str) -> anyhow::Result<()>
+{
+  let global = db::global::read()?;
+  let path_file_reg = global.path_dir_build.join(format!("{}.{}.reg", global.project, name));
+  std::fs::write(&path_file_reg, fragment.to_reg())?;
+  log!("Registry fragment:\n{}", fragment.to_reg());
+  // Wine maps the root directory to the drive 'Z:'
+  let result = gameimage::install::wine(vec!["regedit".into(), "/S".into(), format!("Z:{}", path_file_reg.string().replace('/', "\\"))]);
+  let _ = std::fs::remove_file(&path_file_reg);
+  result
+} // fn: regedit }}}
+
+// fn settings_apply() {{{
+// Imports the changed settings with regedit, then waits for wine to save them in the prefix
+fn settings_apply(settings: &lib::winecfg::Settings) -> anyhow::Result<()>
+{
+  let path_dir_prefix = get_path_dir_prefix()?;
+  let fragment = settings.fragment(&lib::winecfg::Settings::read(&path_dir_prefix)?);
+  if fragment.is_empty() { return Err(ah!("No settings were changed")); } // if
+  snapshot("Before wine settings")?;
+  regedit(&fragment, "settings")?;
+  // Only the values of the fragment are compared, settings left to wine are read from other keys
+  let path_file_user = path_dir_prefix.join("user.reg");
+  for _ in 0..20
+  {
+    if lib::registry::Hive::read(&path_file_user).is_ok_and(|e| lib::winecfg::Settings::is_saved(&fragment, &e)) { return Ok(()); } // if
+    std::thread::sleep(std::time::Duration::from_millis(500));
+  } // for
+  Err(ah!("The settings were imported, but are not saved in the prefix yet"))
+} // fn: settings_apply }}}
+
+// pub fn settings() {{{
+// Typed form of the registry settings which are commonly changed with winecfg
+pub fn settings(tx: Sender<common::Msg>, title: &str)
+{
+  let ui = crate::GUI.lock().unwrap().ui.clone()(title);
+  // Current settings of the prefix
+  let current = match get_path_dir_prefix().and_then(|e| lib::winecfg::Settings::read(&e))
+  {
+    Ok(current) => current,
+    Err(e) => { log_status!("Could not read the registry of the prefix: {}", e); lib::winecfg::Settings::default() },
+  }; // match
+  let settings = Arc::new(Mutex::new(current.clone()));
+  // Layout
+  row!(row,
+    scroll!(scroll,
+      hpack!(col_content, col_content.set_spacing(dimm::border_half()););
+    );
+    column!(col_btn,
+      fixed!(col_btn, btn_apply, shared::fltk::button::rect::save(), dimm::height_button_rec());
+      col_btn.add(&Frame::default());
+    );
+    row.fixed(&col_btn, dimm::width_button_rec());
+  );
+  // Configure footer
+  ui.btn_next.clone().deactivate();
+  ui.btn_prev.clone().emit(tx, common::Msg::DrawWineConfigure);
+  // Resize content to scroll width
+  scroll.set_type(fltk::group::ScrollType::VerticalAlways);
+  scroll.resize_callback({
+    let mut col_content = col_content.clone();
+    move |_,x,y,w,_|
+    {
+      col_content.resize(x,y,w-dimm::border_half()*3,col_content.h());
+    }
+  });
+  rescope!(col_content,
+    let versions : Vec<(&str, &str)> = lib::winecfg::VERSIONS.to_vec();
+    settings_menu("Windows version", current.version.clone(), &versions, #[clown] move |e|
+    {
+      honk!(settings).lock().unwrap().version = e;
+    });
+    // Virtual desktop, enabled with its resolution
+    row!(row_desktop,
+      row_desktop.fixed(&Frame::default().with_align(Align::Inside | Align::Left).with_label("Virtual desktop"), dimm::width_button_wide() * 2);
+      let mut btn_desktop = shared::fltk::button::rect::checkbutton();
+      row_desktop.fixed(&btn_desktop, dimm::width_checkbutton());
+      let mut input_desktop : fltk_evented::Listener<_> = fltk::input::Input::default().into();
+      row_desktop.add(&input_desktop.as_base_widget());
+    );
+    row_desktop.resize(row_desktop.x(), row_desktop.y(), row_desktop.w(), dimm::height_button_wide());
+    let (w, h) = current.desktop.unwrap_or((1280, 720));
+    btn_desktop.set_value(current.desktop.is_some());
+    input_desktop.set_value(&format!("{}x{}", w, h));
+    let f_desktop = #[clown] move |is_enabled: bool, resolution: String|
+    {
+      let resolution = resolution.split_once('x')
+        .and_then(|(w, h)| Some((w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?)));
+      if is_enabled && resolution.is_none() { log_status!("Invalid resolution, expected e.g. 1280x720"); } // if
+      honk!(settings).lock().unwrap().desktop = resolution.filter(|_| is_enabled);
+    };
+    let f_desktop = Arc::new(f_desktop);
+    btn_desktop.set_callback(#[clown] move |e|
+    {
+      honk!(f_desktop)(e.is_checked(), honk!(input_desktop).value());
+    });
+    input_desktop.on_keyup(#[clown] move |e|
+    {
+      honk!(f_desktop)(honk!(btn_desktop).is_checked(), e.value());
+    });
+    // Dll overrides in the syntax of WINEDLLOVERRIDES
+    row!(row_overrides,
+      row_overrides.fixed(&Frame::default().with_align(Align::Inside | Align::Left).with_label("DLL overrides"), dimm::width_button_wide() * 2);
+      let mut input_overrides : fltk_evented::Listener<_> = fltk::input::Input::default().into();
+      row_overrides.add(&input_overrides.as_base_widget());
+    );
+    row_overrides.resize(row_overrides.x(), row_overrides.y(), row_overrides.w(), dimm::height_button_wide());
+    input_overrides.set_value(&lib::winecfg::dll_overrides_to_str(&current.dll_overrides));
+    input_overrides.set_tooltip("e.g., d3d9,d3d11=n,b;dinput8=n");
+    input_overrides.on_keyup(#[clown] move |e|
+    {
+      honk!(settings).lock().unwrap().dll_overrides = lib::winecfg::dll_overrides_from_str(&e.value());
+    });
+    settings_menu("Audio driver", current.audio.clone(), &lib::winecfg::AUDIO_DRIVERS, #[clown] move |e|
+    {
+      honk!(settings).lock().unwrap().audio = e;
+    });
+    settings_menu("Mouse warp", current.mouse_warp.clone(), &lib::winecfg::MOUSE_WARP, #[clown] move |e|
+    {
+      honk!(settings).lock().unwrap().mouse_warp = e;
+    });
+    settings_menu("CSMT", current.csmt.map(|e| if e { "1" } else { "0" }.to_string()), &[("1", "Enabled"), ("0", "Disabled")], #[clown] move |e|
+    {
+      honk!(settings).lock().unwrap().csmt = e.map(|e| e == "1");
+    });
+    settings_menu("Renderer", current.renderer.clone(), &lib::winecfg::RENDERERS, #[clown] move |e|
+    {
+      honk!(settings).lock().unwrap().renderer = e;
+    });
+  );
+  // Apply the changed settings
+  hover_blink!(btn_apply);
+  let mut btn_apply = btn_apply.clone().with_color(Color::Green);
+  btn_apply.set_tooltip("Apply the settings to the wine prefix");
+  btn_apply.set_callback(move |_|
+  {
+    let settings = settings.lock().unwrap().clone();
+    tx.send_awake(common::Msg::WindDeactivate);
+    std::thread::spawn(move ||
+    {
+      log_status!("Applying wine settings...");
+      match settings_apply(&settings)
+      {
+        Ok(()) => log_status!("Applied wine settings"),
+        Err(e) if gameimage::job::is_cancelled(&e) => log_status!("Cancelled"),
+        Err(e) => log_status!("{}", e),
+      } // match
+      tx.send_activate(common::Msg::DrawWineSettings);
+    });
+  });
+} // fn: settings }}}
+
 // pub fn configure() {{{
 pub fn configure(tx: Sender<common::Msg>, title: &str)
 {
@@ -349,6 +754,11 @@ pub fn configure(tx: Sender<common::Msg>, title: &str)
     scroll!(scroll,
       hpack!(col_content,);
     );
+    row!(row_cancel,
+      row_cancel.add(&Frame::default());
+      row_cancel.fixed(&frame::common::cancel(), dimm::width_button_wide());
+    );
+    col.fixed(&row_cancel, dimm::height_button_wide());
   );
   // Configure scroll
   scroll.set_type(fltk::group::ScrollType::VerticalAlways);
@@ -384,6 +794,7 @@ pub fn configure(tx: Sender<common::Msg>, title: &str)
         match gameimage::install::winetricks(vec!["fontsmooth=rgb".into()])
         {
           Ok(_) => log_status!("Created wine prefix"),
+          Err(e) if gameimage::job::is_cancelled(&e) => log_status!("Cancelled"),
           Err(e) => log_status!("{}", e),
         } // else
 
@@ -411,23 +822,145 @@ pub fn configure(tx: Sender<common::Msg>, title: &str)
     let _ = configure_entry(tx.clone(),  "Run a custom wine command" , ||
       dialog::input_default("Enter the wine command to execute", "").map(|e| vec!["wine".into(), e])
     );
+    let mut btn = configure_entry(tx.clone(),  "Configure wine settings", || None);
+    btn.emit(tx, common::Msg::DrawWineSettings);
     let mut btn = configure_entry(tx.clone(),  "Configure environment", || None);
     btn.emit(tx, common::Msg::DrawWineEnvironment);
+    let mut btn = configure_entry(tx.clone(),  "Check prefix", || None);
+    btn.set_callback(move |_|
+    {
+      tx.send_awake(common::Msg::WindDeactivate);
+      std::thread::spawn(move ||
+      {
+        log_status!("Checking the wine prefix...");
+        // Project overrides are applied over the ones of the registry
+        let env_dll_overrides = env_read(&None).remove(shared::db::env::DLL_OVERRIDES).unwrap_or_default();
+        let is_64bit_executable = EXECUTABLE.lock().unwrap().as_ref().map(|(_, metadata)| metadata.is_64bit());
+        match get_path_dir_prefix()
+        {
+          Ok(path_dir_prefix) =>
+          {
+            let findings = lib::health::check(&path_dir_prefix, &env_dll_overrides, is_64bit_executable);
+            let count = findings.iter().filter(|e| e.severity != lib::health::Severity::Info).count();
+            log_status!("Found {} problem(s) in the wine prefix", count);
+            *HEALTH.lock().unwrap() = Some(findings);
+          },
+          Err(e) => log_status!("Could not get the wine prefix: {}", e),
+        } // match
+        tx.send_activate(common::Msg::DrawWineConfigure);
+      });
+    });
+    configure_health();
+    configure_snapshots(tx);
   );
 } // fn: configure }}}
 
+// fn winetricks_entry() {{{
+// Row with a check button for the verb and its description, applied verbs are marked as installed
+fn winetricks_entry(verb: &str, description: &str, is_installed: bool) -> (fltk::group::Flex, fltk::button::CheckButton)
+{
+  row!(row_verb,
+    let btn_check = shared::fltk::button::rect::checkbutton().with_label(verb);
+    row_verb.fixed(&btn_check, dimm::width_button_wide()*2);
+    let mut frame_description = Frame::default()
+      .with_align(Align::Left | Align::Inside)
+      .with_label(&match is_installed
+      {
+        true => format!("{} (installed)", description),
+        false => description.to_string(),
+      });
+    if is_installed { frame_description.set_tooltip("Already applied to the prefix, listed in winetricks.log"); } // if
+    row_verb.add(&frame_description);
+  );
+  (row_verb.with_size(0, dimm::height_button_rec()), btn_check)
+} // fn: winetricks_entry }}}
+
 // pub fn winetricks() {{{
 pub fn winetricks(tx: Sender<common::Msg>, title: &str)
 {
   static YEAR: LazyLock<Mutex<u32>> = LazyLock::new(|| Mutex::new(2024));
+  static TAG: LazyLock<Mutex<Option<String>>> = LazyLock::new(|| Mutex::new(None));
+  static ARCH: LazyLock<Mutex<db::winetricks::Arch>> = LazyLock::new(|| Mutex::new(db::winetricks::Arch::Any));
+  // Libraries imported by the executable and by the libraries next to it
+  static IMPORTS: LazyLock<Mutex<Vec<String>>> = LazyLock::new(|| Mutex::new(vec![]));
+  // Executable the year, architecture and imports were last read from
+  static EXECUTABLE_APPLIED: LazyLock<Mutex<Option<PathBuf>>> = LazyLock::new(|| Mutex::new(None));
+  // List every verb of winetricks instead of the recommended ones
+  static SHOW_ALL: LazyLock<Mutex<bool>> = LazyLock::new(|| Mutex::new(false));
+  // Verbs listed by winetricks, read from the backend the first time they are shown
+  static AVAILABLE: LazyLock<Mutex<Option<Vec<db::winetricks::Available>>>> = LazyLock::new(|| Mutex::new(None));
+  static QUERY: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new(String::new()));
+  // Verbs checked in the list of every verb, kept across searches
+  static SELECTED: LazyLock<Mutex<BTreeSet<String>>> = LazyLock::new(|| Mutex::new(BTreeSet::new()));
   let ui = crate::GUI.lock().unwrap().ui.clone()(title);
+  // Preselect the release year and architecture of a newly picked executable
+  if let Some((path_file_executable, metadata)) = EXECUTABLE.lock().unwrap().as_ref()
+    && EXECUTABLE_APPLIED.lock().unwrap().as_ref() != Some(path_file_executable)
+  {
+    if let Some(year) = metadata.year() { *YEAR.lock().unwrap() = year; } // if
+    *ARCH.lock().unwrap() = if metadata.is_64bit() { db::winetricks::Arch::Win64 } else { db::winetricks::Arch::Win32 };
+    *IMPORTS.lock().unwrap() = match lib::pe::dependencies(path_file_executable)
+    {
+      Ok(imports) => imports,
+      Err(e) => { log!("Could not read imports of '{}': {}", path_file_executable.string(), e); vec![] },
+    }; // match
+    *EXECUTABLE_APPLIED.lock().unwrap() = Some(path_file_executable.clone());
+  } // if
+  // Read the verbs of winetricks in the background, the frame is drawn again once they are available
+  let show_all = *SHOW_ALL.lock().unwrap();
+  if show_all && AVAILABLE.lock().unwrap().is_none()
+  {
+    tx.send_awake(common::Msg::WindDeactivate);
+    std::thread::spawn(move ||
+    {
+      log_status!("Reading the list of winetricks verbs...");
+      let lines : Vec<String> = match gameimage::search::search_remote("winetricks")
+      {
+        Ok(lines) => lines.iter().map(|e| e.string()).collect(),
+        Err(e) => { log_status!("Could not list winetricks verbs: {}", e); vec![] },
+      }; // match
+      *AVAILABLE.lock().unwrap() = Some(db::winetricks::parse_list(&lines));
+      tx.send_activate(common::Msg::DrawWineTricks);
+    });
+  } // if
+  // Verbs already applied to the prefix
+  let installed = get_path_dir_prefix().map(|e| db::winetricks::installed(&e)).unwrap_or_default();
+  // Recommendations from the shipped and user catalogs
+  let catalog = match db::winetricks::read()
+  {
+    Ok(catalog) => catalog,
+    Err(e) => { log_status!("Could not read winetricks catalog: {}", e); db::winetricks::Catalog{ version: 0, verbs: vec![] } },
+  }; // match
   // Layout
   row!(row,
     column!(col,
-      col.fixed(&fltk::frame::Frame::default().with_label("Select the Game Release Year"), dimm::height_text());
-      fixed!(col, menu_year, fltk::menu::MenuButton::default(), dimm::height_button_wide());
-      col.fixed(&fltk::frame::Frame::default().with_label("Recommended Libraries"), dimm::height_text());
-      add!(col, browser, fltk::browser::CheckBrowser::default());
+      row!(row_labels,
+        row_labels.add(&Frame::default().with_label("Release Year"));
+        row_labels.add(&Frame::default().with_label("Engine"));
+        row_labels.add(&Frame::default().with_label("Architecture"));
+        row_labels.add(&Frame::default().with_label("Verbs"));
+      );
+      col.fixed(&row_labels, dimm::height_text());
+      row!(row_menus,
+        add!(row_menus, menu_year, fltk::menu::MenuButton::default());
+        add!(row_menus, menu_tag, fltk::menu::MenuButton::default());
+        add!(row_menus, menu_arch, fltk::menu::MenuButton::default());
+        add!(row_menus, menu_view, fltk::menu::MenuButton::default());
+      );
+      col.fixed(&row_menus, dimm::height_button_wide());
+      let (mut col_search, mut input_query) = shared::fltk::search_column2(
+        "Input a search term to filter verbs, press enter to confirm"
+      );
+      col_search.end();
+      col.fixed(&col_search, col_search.h());
+      if ! show_all { col_search.hide(); } // if
+      col.fixed(&fltk::frame::Frame::default()
+        .with_label(if show_all { "Winetricks Verbs" } else { "Recommended Libraries" })
+        , dimm::height_text()
+      );
+      scroll!(scroll,
+        hpack!(col_verbs, col_verbs.set_spacing(dimm::border_half()););
+      );
     );
     column!(col_btn,
       fixed!(col_btn, btn_install, shared::fltk::button::rect::install(), dimm::width_button_rec());
@@ -441,50 +974,165 @@ pub fn winetricks(tx: Sender<common::Msg>, title: &str)
   ui.btn_next.clone().emit(tx.clone(), common::Msg::DrawWineRom);
   // Select year
   let mut menu_year = menu_year.clone();
-  for i in 1993..2025 { menu_year.add_choice(&i.to_string()); }
+  for i in catalog.years() { menu_year.add_choice(&i.to_string()); }
   menu_year.set_label(&YEAR.lock().unwrap().to_string());
   menu_year.set_callback(#[clown] |e|
   {
     if let Some(choice) = e.choice()
     {
       *YEAR.lock().unwrap() = choice.parse().unwrap();
-      e.set_label(&choice);
       honk!(tx).send(common::Msg::DrawWineTricks)
     }
   });
-  // Recommend libraries by year
-  let vec_lib = get_recomends_winetricks(*YEAR.lock().unwrap());
-  let mut browser = browser.clone();
-  for lib in vec_lib { browser.add(lib, true); }
+  // Select engine, verbs tagged with other engines are not recommended
+  let mut menu_tag = menu_tag.clone();
+  menu_tag.add_choice("Any");
+  for tag in catalog.tags() { menu_tag.add_choice(&tag); }
+  menu_tag.set_label(TAG.lock().unwrap().as_deref().unwrap_or("Any"));
+  menu_tag.set_callback(#[clown] |e|
+  {
+    if let Some(choice) = e.choice()
+    {
+      *TAG.lock().unwrap() = if choice == "Any" { None } else { Some(choice) };
+      honk!(tx).send(common::Msg::DrawWineTricks)
+    }
+  });
+  // Select architecture of the game
+  let mut menu_arch = menu_arch.clone();
+  menu_arch.add_choice("Any|Win32|Win64");
+  menu_arch.set_label(&format!("{:?}", *ARCH.lock().unwrap()));
+  menu_arch.set_callback(#[clown] |e|
+  {
+    *ARCH.lock().unwrap() = match e.value()
+    {
+      1 => db::winetricks::Arch::Win32,
+      2 => db::winetricks::Arch::Win64,
+      _ => db::winetricks::Arch::Any,
+    }; // match
+    honk!(tx).send(common::Msg::DrawWineTricks)
+  });
+  // Select between the recommended verbs and every verb
+  let mut menu_view = menu_view.clone();
+  menu_view.add_choice("Recommended|All");
+  menu_view.set_label(if show_all { "All" } else { "Recommended" });
+  menu_view.set_callback(#[clown] |e|
+  {
+    *SHOW_ALL.lock().unwrap() = e.value() == 1;
+    honk!(tx).send(common::Msg::DrawWineTricks)
+  });
+  // Filter the verbs on enter
+  input_query.set_value(&QUERY.lock().unwrap().clone());
+  input_query.on_keydown(move |e|
+  {
+    let mut query = QUERY.lock().unwrap();
+    let key = fltk::app::event_key();
+    if key == fltk::enums::Key::Enter || e.value().is_empty()
+    {
+      if key != fltk::enums::Key::Enter && query.is_empty() { return; }
+      *query = e.value();
+      tx.send(common::Msg::DrawWineTricks);
+    } // if
+  });
+  // Resize content to scroll width
+  scroll.set_type(fltk::group::ScrollType::VerticalAlways);
+  scroll.resize_callback({
+    let mut col_verbs = col_verbs.clone();
+    move |_,x,y,w,_|
+    {
+      col_verbs.resize(x,y,w-dimm::border_half()*3,col_verbs.h());
+    }
+  });
+  // Recommend libraries by imports, year, engine and architecture
+  let mut vec_check : Vec<(fltk::button::CheckButton, String)> = vec![];
+  let imports = IMPORTS.lock().unwrap().clone();
+  let groups = match show_all
+  {
+    true => vec![],
+    false => catalog.recommend(*YEAR.lock().unwrap(), TAG.lock().unwrap().as_deref(), *ARCH.lock().unwrap(), &imports),
+  }; // match
+  rescope!(col_verbs,
+    for (group, verbs) in groups
+    {
+      let mut frame_group = Frame::default()
+        .with_size(0, dimm::height_text())
+        .with_align(Align::Left | Align::Inside)
+        .with_label(&group);
+      frame_group.set_label_font(fltk::enums::Font::HelveticaBold);
+      for verb in verbs
+      {
+        // Show which imported libraries require the verb
+        let required_by = verb.required_by(&imports);
+        let description = match required_by.is_empty()
+        {
+          true => verb.description.clone(),
+          false => format!("{} (required by {})", verb.description, required_by.join(", ")),
+        }; // match
+        // Verbs already applied are not installed again unless checked
+        let is_installed = installed.contains(&verb.verb);
+        let (row_verb, mut btn_check) = winetricks_entry(&verb.verb, &description, is_installed);
+        btn_check.set_value(! is_installed);
+        col_verbs.add(&row_verb);
+        vec_check.push((btn_check, verb.verb));
+      } // for
+    } // for
+    // Every verb matching the query, grouped by category
+    if show_all
+    {
+      let available = AVAILABLE.lock().unwrap().clone().unwrap_or_default();
+      let mut category = None;
+      for verb in db::winetricks::search(&available, &QUERY.lock().unwrap())
+      {
+        if category != Some(&verb.category)
+        {
+          let mut frame_group = Frame::default()
+            .with_size(0, dimm::height_text())
+            .with_align(Align::Left | Align::Inside)
+            .with_label(&verb.category);
+          frame_group.set_label_font(fltk::enums::Font::HelveticaBold);
+          category = Some(&verb.category);
+        } // if
+        let (row_verb, mut btn_check) = winetricks_entry(&verb.verb, &verb.description, installed.contains(&verb.verb));
+        btn_check.set_value(SELECTED.lock().unwrap().contains(&verb.verb));
+        let verb = verb.verb.clone();
+        btn_check.set_callback(move |e|
+        {
+          match e.is_checked()
+          {
+            true => SELECTED.lock().unwrap().insert(verb.clone()),
+            false => SELECTED.lock().unwrap().remove(&verb),
+          };
+        });
+        col_verbs.add(&row_verb);
+      } // for
+    } // if
+  );
   // Install button to the right
   btn_install.clone()
     .right_of(&col.as_base_widget(), dimm::border())
     .with_color(Color::Green)
     .with_callback(move |_|
     {
-      // Function to get all checked items
-      tx.send_awake(common::Msg::WindDeactivate);
-      let clone_browser = browser.clone();
-      std::thread::spawn(move ||
+      // Must install one at the time, winetricks exits if at least one verb fails
+      let vec_lib : Vec<String> = match show_all
+      {
+        true => std::mem::take(&mut *SELECTED.lock().unwrap()).into_iter().collect(),
+        false => vec_check.iter().filter(|(btn, _)| btn.is_checked()).map(|(_, verb)| verb.clone()).collect(),
+      }; // match
+      let count = vec_lib.len();
+      if count > 0
       {
-        // Must install one at the time, winetricks exits if at least one verb fails
-        for lib in &mut (1..=clone_browser.size())
-          .filter(|e| clone_browser.checked(*e as i32))
-          .map(|e| clone_browser.text(e as i32).unwrap())
+        let label = format!("Before winetricks '{}'", vec_lib.join(" "));
+        gameimage::queue::push("Snapshot wine prefix", vec![], move || snapshot(&label));
+      } // if
+      for lib in vec_lib
+      {
+        gameimage::queue::push(&format!("Winetricks '{}'", lib), vec![], move ||
         {
-          log_status!("Installing '{}'", lib);
-          let mut json_args = json!({});
-          json_args["op"] = "install".into();
-          json_args["install"]["op"] = "install".into();
-          json_args["install"]["sub_op"] = "winetricks".into();
-          json_args["install"]["args"] = vec!["-f", "-q", lib.as_str()].into();
-          if gameimage::gameimage::gameimage_sync(vec![&json_args.to_string()]) != 0
-          {
-            log_status!("Command exited with non-zero status");
-          } // else
-        } // for
-        tx.send_awake(common::Msg::WindActivate);
-      });
+          gameimage::install::winetricks(vec!["-f".into(), "-q".into(), lib.clone()])
+        });
+      } // for
+      log_status!("Queued {} winetricks verb(s), see the job panel for progress", count);
+      if show_all { tx.send(common::Msg::DrawWineTricks); } // if
     });
 } // fn: winetricks }}}
 
@@ -518,22 +1166,131 @@ fn rom_exec(path_file_item: PathBuf) -> anyhow::Result<()>
 }
 // rom_exec() }}}
 
+// get_path_dir_sources() {{{
+// Discs are extracted in the build directory, outside of the project which is packaged
+fn get_path_dir_sources() -> anyhow::Result<PathBuf>
+{
+  let global = db::global::read()?;
+  Ok(global.path_dir_build.join("sources").join(&global.project))
+} // get_path_dir_sources() }}}
+
+// disc_insert() {{{
+// Maps the disc to the drive 'D:', it is extracted the first time it is inserted
+fn disc_insert(source: &lib::source::Source) -> anyhow::Result<PathBuf>
+{
+  let path_dir_disc = get_path_dir_sources()?.join(source.stem());
+  if ! path_dir_disc.exists()
+  {
+    log_status!("Extracting '{}'...", source.name());
+    // Extract to a temporary directory, so an interrupted extraction is not taken as complete
+    let path_dir_part = PathBuf::from(format!("{}.part", path_dir_disc.string()));
+    let _ = std::fs::remove_dir_all(&path_dir_part);
+    if let Err(e) = source.extract(&path_dir_part)
+    {
+      let _ = std::fs::remove_dir_all(&path_dir_part);
+      return Err(e);
+    } // if
+    std::fs::rename(&path_dir_part, &path_dir_disc)?;
+  } // if
+  let path_dir_dosdevices = get_path_dir_prefix()?.join("dosdevices");
+  std::fs::create_dir_all(&path_dir_dosdevices)?;
+  let path_link_drive = path_dir_dosdevices.join("d:");
+  if path_link_drive.symlink_metadata().is_ok() { std::fs::remove_file(&path_link_drive)?; } // if
+  std::os::unix::fs::symlink(&path_dir_disc, &path_link_drive)?;
+  log_status!("Inserted '{}' in the drive D:", source.name());
+  Ok(path_dir_disc)
+} // disc_insert() }}}
+
+// disc_install() {{{
+// Inserts the first disc and runs its installer. The rom frame waits for the job, so the other discs
+// are inserted from the prompt of the job in the job panel
+fn disc_install(discs: &[lib::source::Source]) -> anyhow::Result<()>
+{
+  let disc = discs.first().ok_or(ah!("No disc in the sources"))?;
+  let path_dir_disc = disc_insert(disc)?;
+  if discs.len() > 1 && let Some(id) = gameimage::queue::current()
+  {
+    let discs = discs.to_vec();
+    gameimage::queue::prompt(id
+      , "Disc in the drive D:"
+      , discs.iter().map(|e| e.name()).collect()
+      , 0
+      , move |index| disc_insert(&discs[index]).map(|_| ())
+    );
+  } // if
+  // Installers check the type of the drive they are started from
+  let mut fragment = lib::registry::Fragment::default();
+  fragment.set("HKEY_LOCAL_MACHINE\\Software\\Wine\\Drives", "d:", lib::registry::Value::Str("cdrom".into()));
+  regedit(&fragment, "drives")?;
+  let path_file_setup = lib::source::autorun(&path_dir_disc)
+    .ok_or(ah!("No installer found in the disc, run it from the drive D: with 'Run a custom command'"))?;
+  let path_file_setup = path_file_setup.strip_prefix(&path_dir_disc)?.to_path_buf();
+  gameimage::install::wine(vec![format!("D:\\{}", path_file_setup.string().replace('/', "\\"))])
+} // disc_install() }}}
+
 // rom_add() {{{
+// Queues the installation of the selected sources, archives are extracted into 'drive_c'
 fn rom_add() -> anyhow::Result<()>
 {
   // Pick files to install
   let mut chooser = dialog::FileChooser::new("."
     , "*"
-    , dialog::FileChooserType::Single
-    , "Pick a file to install with wine");
+    , dialog::FileChooserType::Multi
+    , "Pick the installer, its disc images or an archive to install with wine");
   // Start dialog
   chooser.show();
   // Wait for choice(s)
   while chooser.shown() { std::thread::sleep(std::time::Duration::from_millis(100)) } // while
   // Check if choice is valid
-  let str_choice = chooser.value(1).ok_or(ah!("No file selected"))?;
-  // Execute wine
-  gameimage::install::wine(vec![str_choice])?;
+  let paths : Vec<PathBuf> = (1..=chooser.count()).filter_map(|i| chooser.value(i)).map(PathBuf::from).collect();
+  if paths.is_empty() { return Err(ah!("No file selected")); } // if
+  let sources = lib::source::sources(&paths);
+  // Installers fail late when one of their parts is missing
+  for source in sources.iter()
+  {
+    let missing = source.missing();
+    if ! missing.is_empty() { return Err(ah!("Missing files of '{}': {}", source.name(), missing.join(", "))); } // if
+    let missing = source.tools_missing();
+    if ! missing.is_empty() { return Err(ah!("Install '{}' to extract '{}'", missing.join("', '"), source.name())); } // if
+  } // for
+  let path_dir_drive_c = get_path_dir_prefix()?.join("drive_c");
+  let (discs, others) : (Vec<_>, Vec<_>) = sources.into_iter().partition(|e| e.kind == lib::source::Kind::Disc);
+  let count = others.len() + usize::from(! discs.is_empty());
+  let count_discs = discs.len();
+  if let Some(disc) = discs.first()
+  {
+    let label = format!("Install from '{}'", disc.name());
+    gameimage::queue::push(&label, vec![common::Msg::DrawWineRom], move || disc_install(&discs));
+  } // if
+  for source in others
+  {
+    match source.kind
+    {
+      lib::source::Kind::Archive =>
+      {
+        let path_dir_dst = path_dir_drive_c.join(source.stem());
+        gameimage::queue::push(&format!("Extract '{}'", source.name()), vec![common::Msg::DrawWineRom], move ||
+        {
+          source.extract(&path_dir_dst)
+        });
+      },
+      _ =>
+      {
+        gameimage::queue::push(&format!("Install '{}'", source.name()), vec![common::Msg::DrawWineRom], move ||
+        {
+          // Exported recipes run the installer again, its parts are expected next to it
+          let command = vec![source.path.string()];
+          gameimage::install::wine(command.clone())?;
+          db::recipe::record_wine(&db::global::get_current_project()?.path_dir_project, &command)
+        });
+      },
+    } // match
+  } // for
+  match count_discs
+  {
+    0 | 1 => log_status!("Queued {} installation source(s), see the job panel for progress", count),
+    _ => log_status!("Queued {} installation source(s), swap the discs from the job panel", count),
+  } // match
   Ok(())
 } // rom_add() }}}
 
@@ -571,6 +1328,7 @@ fn rom_entry(tx: Sender<common::Msg>
       row_fst.fixed(&btn_check, dimm::width_button_rec());
       add!(row_fst, output, output::Output::default());
       fixed!(row_fst, btn_folder, shared::fltk::button::rect::folder(), dimm::width_button_rec());
+      fixed!(row_fst, btn_env, shared::fltk::button::rect::configure(), dimm::width_button_rec());
       fixed!(row_fst, btn_run, shared::fltk::button::rect::play(), dimm::width_button_rec());
     );
     col.fixed(&row_fst.clone(), dimm::height_button_wide());
@@ -599,14 +1357,25 @@ fn rom_entry(tx: Sender<common::Msg>
   // Configure buttons
   hover_blink!(btn_run);
   hover_blink!(btn_folder);
+  hover_blink!(btn_env);
   // Checkbutton
   // Include values into shared vector
   vec_radio_path.push((btn_check.clone(), PathBuf::from(item.to_owned())));
   // Label with file name
   let _ = output.clone().insert(&item.string());
+  output.clone().set_tooltip(lib::rank::classify(item).as_str());
   // Button to open file in file manager
   let clone_item = item.clone();
   btn_folder.clone().set_callback(move |_| { let _ = rom_folder(clone_item.clone()); });
+  // Button to configure the environment of the executable
+  let clone_item = item.clone();
+  let mut btn_env = btn_env.clone();
+  btn_env.set_tooltip("Environment and dll overrides of this executable");
+  btn_env.set_callback(move |_|
+  {
+    *ENVIRONMENT_EXECUTABLE.lock().unwrap() = Some(clone_item.string());
+    tx.send_awake(common::Msg::DrawWineEnvironmentExecutable);
+  });
   // Button to run the selected wine binary
   btn_run.clone()
     .with_color(Color::Green)
@@ -654,28 +1423,24 @@ fn rom_entry(tx: Sender<common::Msg>
 } // rom_entry() }}}
 
 // rom_search() {{{
-fn rom_search(query: &str) -> Vec<PathBuf>
+// Executables matching the query, ranked by how likely they are the main binary of the game
+fn rom_search(query: &str, hidden: &[lib::rank::Category]) -> Vec<PathBuf>
 {
-  let mut results: Vec<PathBuf> = gameimage::search::search_local("rom")
+  let results: Vec<PathBuf> = gameimage::search::search_local("rom")
     .unwrap_or_default()
-    .iter()
+    .into_iter()
     .filter(|e| e.string().to_lowercase().contains(&query.to_lowercase()))
-    .map(|e| e.clone())
     .collect();
-  results.sort_by(|a, b|
+  let (name, path_dir_project) = match db::global::read().and_then(|e| Ok((e.project.clone(), e.get_project_dir(&e.project)?)))
   {
-    let a_components = a.components().count();
-    let b_components = b.components().count();
-    if a_components == b_components
-    {
-      a.string().len().cmp(&b.string().len())
-    } // if
-    else
-    {
-      a_components.cmp(&b_components)
-    } // else
-  });
-  results
+    Ok(project) => project,
+    Err(e) => { log!("Could not read project to rank executables: {}", e); (String::new(), PathBuf::new()) },
+  }; // match
+  lib::rank::rank(&name, &path_dir_project, results)
+    .into_iter()
+    .filter(|e| ! hidden.contains(&e.category))
+    .map(|e| e.path)
+    .collect()
 } // rom_search() }}}
 
 // rom_modified() {{{
@@ -684,9 +1449,11 @@ fn rom_modified() -> Vec<PathBuf>
   let hash_executable_arguments = shared::db::kv::read(&get_path_db_args().unwrap_or_default()).unwrap_or_default();
   let hash_executable_alias = shared::db::kv::read(&get_path_db_alias().unwrap_or_default()).unwrap_or_default();
   let hash_executable_use = shared::db::kv::read(&get_path_db_executable().unwrap_or_default()).unwrap_or_default();
+  let hash_executable_env = shared::db::env::read(&get_path_db_env_executable().unwrap_or_default()).unwrap_or_default();
   let mut results: Vec<PathBuf> = hash_executable_arguments.keys()
     .chain(hash_executable_alias.keys())
     .chain(hash_executable_use.keys())
+    .chain(hash_executable_env.keys())
     .map(|e| PathBuf::from(e))
     .collect();
   // Remove duplicate values
@@ -718,10 +1485,15 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
   static PAGE : LazyLock<Mutex<usize>> = LazyLock::new(|| Mutex::new(0));
   static SELECTED : LazyLock<Mutex<PathBuf>> = LazyLock::new(|| Mutex::new(PathBuf::default()));
   static SHOW_SELECTED : LazyLock<Mutex<bool>> = LazyLock::new(|| Mutex::new(false));
+  static HIDDEN : LazyLock<Mutex<Vec<lib::rank::Category>>> = LazyLock::new(|| Mutex::new(vec![
+    lib::rank::Category::Uninstaller,
+    lib::rank::Category::Redist,
+    lib::rank::Category::CrashReporter,
+  ]));
   // Update results if empty
   if ! *SHOW_SELECTED.lock().unwrap()
   {
-    *RESULTS.lock().unwrap() = rom_search(&QUERY.lock().unwrap());
+    *RESULTS.lock().unwrap() = rom_search(&QUERY.lock().unwrap(), &HIDDEN.lock().unwrap());
   } // if
   // UI
   let ui = crate::GUI.lock().unwrap().ui.clone()(title);
@@ -739,6 +1511,19 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
         .with_color(Color::BackGround)
         .with_label(" Only show modified entries");
       col.fixed(&btn_show_selected, dimm::width_checkbutton() + dimm::border());
+      row!(row_hidden,
+        row_hidden.fixed(&Frame::default().with_align(Align::Inside | Align::Left).with_label("Hide"), dimm::width_button_rec());
+        let mut vec_btn_hidden = vec![];
+        for category in lib::rank::Category::noise()
+        {
+          let btn_hidden = shared::fltk::button::rect::checkbutton()
+            .with_align(Align::Inside | Align::Left)
+            .with_color(Color::BackGround)
+            .with_label(&format!(" {}", category.as_str()));
+          vec_btn_hidden.push((btn_hidden, category));
+        } // for
+      );
+      col.fixed(&row_hidden, dimm::width_checkbutton() + dimm::border());
       col.fixed(&shared::fltk::separator::horizontal(col.w()), dimm::height_sep());
       scroll!(scroll,
         hpack!(col_content,);
@@ -792,7 +1577,7 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
       let query = query.clone();
       std::thread::spawn(move ||
       {
-        *RESULTS.lock().unwrap() = rom_search(&query);
+        *RESULTS.lock().unwrap() = rom_search(&query, &HIDDEN.lock().unwrap());
         tx.send_activate(common::Msg::DrawWineRom);
       });
     } // if
@@ -817,11 +1602,30 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
     // Display all items
     else
     {
-      *RESULTS.lock().unwrap() = rom_search(&QUERY.lock().unwrap().clone());
+      *RESULTS.lock().unwrap() = rom_search(&QUERY.lock().unwrap().clone(), &HIDDEN.lock().unwrap());
       *SHOW_SELECTED.lock().unwrap() = false;
       tx.send_activate(common::Msg::DrawWineRom);
     } // else
   });
+  // Configure buttons to hide noise executables, e.g., uninstallers and redistributables
+  for (mut btn_hidden, category) in vec_btn_hidden
+  {
+    btn_hidden.set_value(HIDDEN.lock().unwrap().contains(&category));
+    btn_hidden.set_callback(move |e|
+    {
+      let mut hidden = HIDDEN.lock().unwrap();
+      hidden.retain(|e| *e != category);
+      if e.is_checked() { hidden.push(category); } // if
+      if *SHOW_SELECTED.lock().unwrap() { return; } // if
+      tx.send_awake(common::Msg::WindDeactivate);
+      std::thread::spawn(move ||
+      {
+        *RESULTS.lock().unwrap() = rom_search(&QUERY.lock().unwrap().clone(), &HIDDEN.lock().unwrap());
+        *PAGE.lock().unwrap() = 0;
+        tx.send_activate(common::Msg::DrawWineRom);
+      });
+    });
+  } // for
   log_err!(input_query.take_focus());
   // Insert items in list of currently installed items
   let vec_radio_path = Arc::new(Mutex::new(Vec::<(button::RadioButton, path::PathBuf)>::new()));
@@ -856,6 +1660,11 @@ pub fn rom(tx: Sender<common::Msg>, title: &str)
       for i in honk!(vec_radio_path).lock().unwrap().iter_mut() { i.0.toggle(false); }
       *SELECTED.lock().unwrap() = honk!(path).clone();
       e.toggle(true);
+      // Keep the metadata of the default executable for the previous frames
+      if let Ok(path_dir_project) = get_path_db()
+      {
+        log_err!(executable(&path_dir_project.join(SELECTED.lock().unwrap().as_path())).map(|_| ()));
+      } // if
     });
   } // for
   // Add new item
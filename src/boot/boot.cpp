@@ -142,8 +142,64 @@ void env(fs::path const& path_dir_self)
   , ns_db::Mode::READ);
 } // env() }}}
 
+// env_executable() {{{
+// Environment of the executable, applied over the environment of the project
+void env_executable(fs::path const& path_dir_self, fs::path const& path_file_executable)
+{
+  // Default path for the env var list of each executable
+  fs::path path_file_env = path_dir_self / "gameimage.env.executable.json";
+
+  // Set variables
+  std::ignore = ns_db::from_file(path_file_env, [&](auto&& db)
+  {
+    auto expected_db_executable = db.template value<ns_db::Db>(path_file_executable);
+    if ( not expected_db_executable )
+    {
+      ns_log::write('i', "No environment for ", path_file_executable);
+      return;
+    } // if
+    for(auto&& e : expected_db_executable->keys())
+    {
+      auto value = expected_db_executable->template value<std::string>(e);
+      if ( not value )
+      {
+        ns_log::write('e', "Failed to get value for key '", e, "'");
+        continue;
+      } // if
+      // Dll overrides are appended to the current ones, wine uses the last entry of a library
+      std::string str_value = *value;
+      if ( const char* var = ns_env::get(e.c_str()); e == "WINEDLLOVERRIDES" and var != nullptr and *var != '\0' )
+      {
+        str_value = "{};{}"_fmt(var, *value);
+      } // if
+      ns_env::set(e, str_value, ns_env::Replace::Y);
+      ns_log::write('i', "Set environment variable '", e, "' to '", str_value, "' for ", path_file_executable);
+    } // for
+  }
+  , ns_db::Mode::READ);
+} // env_executable() }}}
+
+// env_launcher() {{{
+// One-off overrides from the launcher command line, as KEY=VAL lines, applied over the databases
+void env_launcher()
+{
+  const char* str_overrides = ns_env::get("GIMG_LAUNCHER_ENV");
+  return_if(str_overrides == nullptr or *str_overrides == '\0',);
+  for(auto&& line : ns_string::split(str_overrides, '\n'))
+  {
+    auto pos = line.find('=');
+    if ( pos == std::string::npos or pos == 0 )
+    {
+      ns_log::write('e', "Invalid environment override '", line, "'");
+      continue;
+    } // if
+    ns_env::set(line.substr(0, pos), line.substr(pos+1), ns_env::Replace::Y);
+    ns_log::write('i', "Override environment variable '", line.substr(0, pos), "'");
+  } // for
+} // env_launcher() }}}
+
 // boot_linux() {{{
-void boot_linux(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self)
+void boot_linux(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self, std::vector<std::string> const& args_extra)
 {
   // Enter application directory
   fs::current_path(path_dir_self);
@@ -159,6 +215,10 @@ void boot_linux(ns_db::ns_project::Project& db_project, fs::path const& path_dir
     path_file_rom = path_dir_self / path_file_rom_relative;
   } // if
 
+  // Environment of the executable and the overrides of the launcher
+  env_executable(path_dir_self, path_file_rom_relative);
+  env_launcher();
+
   // Include exec and read permissions (allow to fail)
   lec(fs::permissions, path_file_rom
     , fs::perms::owner_exec | fs::perms::group_exec | fs::perms::others_exec
@@ -174,13 +234,13 @@ void boot_linux(ns_db::ns_project::Project& db_project, fs::path const& path_dir
   std::ignore = ns_subprocess::Subprocess(*optional_path_file_bash)
     .with_piped_outputs()
     .with_args("-c", R"("{}" "$@")"_fmt(path_file_rom))
-    .with_args("--", args(path_dir_self, path_file_rom_relative))
+    .with_args("--", args(path_dir_self, path_file_rom_relative), args_extra)
     .spawn()
     .wait();
 } // boot_linux() }}}
 
 // boot_wine() {{{
-void boot_wine(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self)
+void boot_wine(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self, std::vector<std::string> const& args_extra)
 {
   // Set wine prefix
   ns_env::set("WINEPREFIX", (path_dir_self / "wine").c_str(), ns_env::Replace::Y);
@@ -196,19 +256,23 @@ void boot_wine(ns_db::ns_project::Project& db_project, fs::path const& path_dir_
     path_file_rom = path_dir_self / path_file_rom_relative;
   } // if
 
+  // Environment of the executable and the overrides of the launcher
+  env_executable(path_dir_self, path_file_rom_relative);
+  env_launcher();
+
   // Enter directory of rom file
   fs::current_path(ns_fs::ns_path::dir_exists<true>(path_file_rom.parent_path())._ret);
 
   // Start application
   std::ignore = ns_subprocess::Subprocess(ns_env::get_or_throw("FIM_BINARY_WINE"))
     .with_piped_outputs()
-    .with_args(path_file_rom, args(path_dir_self, path_file_rom_relative))
+    .with_args(path_file_rom, args(path_dir_self, path_file_rom_relative), args_extra)
     .spawn()
     .wait();
 } // boot_wine() }}}
 
 // boot_retroarch() {{{
-void boot_retroarch(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self)
+void boot_retroarch(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self, std::vector<std::string> const& args_extra)
 {
   // Check if has bios
   db_files_copy(db_project, ns_enum::Op::BIOS, path_dir_self, (get_xdg_config_home() / "retroarch/system"));
@@ -216,13 +280,13 @@ void boot_retroarch(ns_db::ns_project::Project& db_project, fs::path const& path
   // Start application
   std::ignore = ns_subprocess::Subprocess(ns_env::get_or_throw("FIM_BINARY_RETROARCH"))
     .with_piped_outputs()
-    .with_args("-L", path_dir_self / db_project.path_file_core, path_dir_self / db_project.path_file_rom)
+    .with_args("-L", path_dir_self / db_project.path_file_core, path_dir_self / db_project.path_file_rom, args_extra)
     .spawn()
     .wait();
 } // boot_retroarch() }}}
 
 // boot_pcsx2() {{{
-void boot_pcsx2(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self)
+void boot_pcsx2(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self, std::vector<std::string> const& args_extra)
 {
   // Check if has bios
   db_files_copy(db_project, ns_enum::Op::BIOS, path_dir_self, ( get_xdg_config_home() / "PCSX2/bios"));
@@ -230,17 +294,17 @@ void boot_pcsx2(ns_db::ns_project::Project& db_project, fs::path const& path_dir
   // Start application
   std::ignore = ns_subprocess::Subprocess(ns_env::get_or_throw("FIM_BINARY_PCSX2"))
     .with_piped_outputs()
-    .with_args("--", path_dir_self / db_project.path_file_rom)
+    .with_args(args_extra, "--", path_dir_self / db_project.path_file_rom)
     .spawn()
     .wait();
 } // boot_pcsx2() }}}
 
 // boot_rpcs3() {{{
-void boot_rpcs3(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self)
+void boot_rpcs3(ns_db::ns_project::Project& db_project, fs::path const& path_dir_self, std::vector<std::string> const& args_extra)
 {
   std::ignore = ns_subprocess::Subprocess(ns_env::get_or_throw("FIM_BINARY_RPCS3"))
     .with_piped_outputs()
-    .with_args("--allow-any-location", "--no-gui", "--", path_dir_self / db_project.path_file_rom)
+    .with_args("--allow-any-location", "--no-gui", args_extra, "--", path_dir_self / db_project.path_file_rom)
     .spawn()
     .wait();
 } // boot_rpcs3() }}}
@@ -267,6 +331,9 @@ void boot(int argc, char** argv)
     ns_log::write('e', "Could not set environment: ", e.what());
   } // catch
 
+  // Overrides of the launcher take precedence over the environment of the game
+  env_launcher();
+
   // // Adjust environment
   // ns_env::set("LC_ALL", "C", ns_env::Replace::N);
 
@@ -280,13 +347,16 @@ void boot(int argc, char** argv)
   // Database file
   ns_enum::Platform platform = db_project->platform;
 
+  // Extra arguments from the launcher are passed to the game
+  std::vector<std::string> args_extra(argv + std::min(argc, 1), argv + argc);
+
   switch(platform)
   {
-    case ns_enum::Platform::LINUX    : boot_linux(*db_project, path_dir_self)     ; break;
-    case ns_enum::Platform::WINE     : boot_wine(*db_project, path_dir_self)      ; break;
-    case ns_enum::Platform::RETROARCH: boot_retroarch(*db_project, path_dir_self) ; break;
-    case ns_enum::Platform::PCSX2    : boot_pcsx2(*db_project, path_dir_self)     ; break;
-    case ns_enum::Platform::RPCS3    : boot_rpcs3(*db_project, path_dir_self)     ; break;
+    case ns_enum::Platform::LINUX    : boot_linux(*db_project, path_dir_self, args_extra)    ; break;
+    case ns_enum::Platform::WINE     : boot_wine(*db_project, path_dir_self, args_extra)     ; break;
+    case ns_enum::Platform::RETROARCH: boot_retroarch(*db_project, path_dir_self, args_extra) ; break;
+    case ns_enum::Platform::PCSX2    : boot_pcsx2(*db_project, path_dir_self, args_extra)    ; break;
+    case ns_enum::Platform::RPCS3    : boot_rpcs3(*db_project, path_dir_self, args_extra)    ; break;
   } // switch
 } // function: boot }}}
 
@@ -9,6 +9,7 @@
 #include <cpr/cpr.h>
 #include <fmt/ranges.h>
 #include <expected>
+#include <thread>
 
 #include "fetch/check.hpp"
 
@@ -46,6 +47,7 @@ struct sources_layer_ret_t
 {
   fs::path path;
   cpr::Url url;
+  std::string name;
 }; // }}}
 
 // get_path_sources() {{{
@@ -56,54 +58,140 @@ struct sources_layer_ret_t
   return db_build->path_dir_build / "fetch.json";
 } // get_path_sources() }}}
 
+// Attempts to download a file, a failed attempt resumes from the partially downloaded file
+inline constexpr int const FETCH_ATTEMPTS = 5;
+
+// remote_size() {{{
+// Size of the remote file, if the server accepts range requests to resume from
+[[nodiscard]] inline std::optional<uintmax_t> remote_size(cpr::Url const& url)
+{
+  cpr::Response r = cpr::Head(url);
+  qreturn_if(r.status_code != 200, std::nullopt);
+  qreturn_if(r.header["accept-ranges"] != "bytes", std::nullopt);
+  try
+  {
+    return std::stoull(r.header["content-length"]);
+  } // try
+  catch(...)
+  {
+    return std::nullopt;
+  } // catch
+} // remote_size() }}}
+
 // fetch_file_from_url() {{{
 [[nodiscard]] inline std::expected<fs::path, std::string> fetch_file_from_url(fs::path const& path_file
   , cpr::Url const& url
-  , bool send_ipc = true)
+  , bool send_ipc = true
+  , std::string const& name = "")
 {
   ns_log::write('i', "Fetch file '", url.c_str(), "' to '", path_file, "'");
   // Create upper directories
   lec(fs::create_directories, path_file.parent_path());
-  // Try to open destination file
-  auto ofile = std::ofstream{path_file, std::ios::binary};
-  // Check if file is open
-  qreturn_if(not ofile.is_open(), std::unexpected("Failed to open file '{}' for writing"_fmt(path_file)));
-  // fetch_callback
-  auto fetch_callback = [&](cpr::cpr_off_t downloadTotal, cpr::cpr_off_t downloadNow, cpr::cpr_off_t, cpr::cpr_off_t, intptr_t)
+  // Name shown in the progress
+  std::string str_name = name.empty()? path_file.filename().string() : name;
+  // Data is written to a partial file, it is kept on failure to resume from
+  fs::path path_file_part = path_file.string() + ".part";
+  std::string error;
+  bool is_complete = false;
+  for(int attempt = 1; attempt <= FETCH_ATTEMPTS; ++attempt)
   {
-    static std::chrono::steady_clock::time_point prev = std::chrono::steady_clock::now();
-    std::chrono::steady_clock::time_point now = std::chrono::steady_clock::now();
-    std::chrono::milliseconds timeDiff = duration_cast<std::chrono::milliseconds>(now - prev);
-
-    if (downloadTotal > 0 && timeDiff.count() >= 2000)
+    // Wait before retrying
+    if ( attempt > 1 )
     {
-      prev = now;
-      // Update progress
-      int percentage = static_cast<int>((downloadNow * 100) / downloadTotal);
-      // Send progress to watching processes
-      if ( send_ipc ){  ns_ipc::ipc().send(percentage); }
-      // Log
-      ns_log::write('i', "Download progress: ", percentage, "%");
-    }
-    return true; // Return false to cancel the download
-  }; //
-  // Fetch file
-  cpr::Response r = cpr::Download(ofile, url, cpr::ProgressCallback{fetch_callback, reinterpret_cast<intptr_t>(&ofile)});
-  // Check for success
-  if ( r.status_code != 200 )
-  {
-    // Remove partial file
+      ns_log::write('e', error);
+      ns_log::write('i', "Retrying download, attempt ", attempt, " of ", FETCH_ATTEMPTS);
+      std::this_thread::sleep_for(std::chrono::seconds(attempt));
+    } // if
+    // Resume from the partial file if the server supports it
+    std::error_code ec;
+    uintmax_t offset = fs::exists(path_file_part, ec)? fs::file_size(path_file_part, ec) : 0;
+    if ( ec ) { offset = 0; }
+    if ( offset > 0 )
+    {
+      auto opt_size = remote_size(url);
+      if ( opt_size and *opt_size == offset ) { is_complete = true; break; }
+      if ( not opt_size or *opt_size < offset )
+      {
+        ns_log::write('i', "Could not resume download of '", path_file, "', starting over");
+        offset = 0;
+      } // if
+      else
+      {
+        ns_log::write('i', "Resuming download of '", path_file, "' from byte ", offset);
+      } // else
+    } // if
+    // Try to open destination file
+    auto ofile = std::ofstream{path_file_part, std::ios::binary | ((offset > 0)? std::ios::app : std::ios::trunc)};
+    // Check if file is open
+    qreturn_if(not ofile.is_open(), std::unexpected("Failed to open file '{}' for writing"_fmt(path_file_part)));
+    // fetch_callback
+    auto time_prev = std::chrono::steady_clock::now();
+    cpr::cpr_off_t bytes_prev = 0;
+    auto fetch_callback = [&](cpr::cpr_off_t downloadTotal, cpr::cpr_off_t downloadNow, cpr::cpr_off_t, cpr::cpr_off_t, intptr_t)
+    {
+      auto time_now = std::chrono::steady_clock::now();
+      auto ms = duration_cast<std::chrono::milliseconds>(time_now - time_prev).count();
+      if (downloadTotal > 0 && ms >= 1000)
+      {
+        // Transfer rate since the last update
+        double rate = static_cast<double>(downloadNow - bytes_prev) * 1000.0 / static_cast<double>(ms);
+        time_prev = time_now;
+        bytes_prev = downloadNow;
+        // Count the bytes of previous attempts
+        uint64_t bytes = offset + downloadNow;
+        uint64_t total = offset + downloadTotal;
+        int percentage = static_cast<int>((bytes * 100) / total);
+        // Send progress to watching processes
+        if ( send_ipc )
+        {
+          ns_ipc::ipc().progress(percentage);
+          ns_ipc::ipc().download(str_name, bytes, total, rate);
+        } // if
+        // Log
+        ns_log::write('i', "Download progress: ", percentage, "%");
+      }
+      return true; // Return false to cancel the download
+    }; //
+    // Fetch file
+    cpr::Header header;
+    if ( offset > 0 ) { header["Range"] = "bytes={}-"_fmt(offset); }
+    cpr::Response r = cpr::Download(ofile, url, header, cpr::ProgressCallback{fetch_callback});
     ofile.close();
-    fs::remove(path_file);
-    // Return failure
+    // Success, full or remaining part of the file
+    if ( (r.status_code == 200 and offset == 0) or (r.status_code == 206 and offset > 0) )
+    {
+      is_complete = true;
+      break;
+    } // if
+    // Server ignored the range, the partial file is invalid
+    if ( r.status_code == 200 )
+    {
+      error = "Server does not support resuming '{}'"_fmt(path_file);
+      fs::remove(path_file_part, ec);
+      continue;
+    } // if
+    // Network failure or server error, retry
+    if ( r.status_code == 0 or r.status_code >= 500 )
+    {
+      error = "Failure to fetch file '{}' with code '{}': {}"_fmt(path_file, r.status_code, r.error.message);
+      continue;
+    } // if
+    // Request failed, e.g., the file does not exist
+    fs::remove(path_file_part, ec);
     return std::unexpected("Failure to fetch file '{}' with code '{}'"_fmt(path_file, r.status_code));
-  } // if
+  } // for
+  // Check if the download completed
+  qreturn_if(not is_complete
+    , std::unexpected("Failure to fetch file '{}' after {} attempts: {}"_fmt(path_file, FETCH_ATTEMPTS, error))
+  );
+  std::error_code ec;
+  fs::rename(path_file_part, path_file, ec);
+  qreturn_if(ec, std::unexpected("Failed to move '{}' to '{}': {}"_fmt(path_file_part, path_file, ec.message())));
   // Set to progress 100%
-  if ( send_ipc ) { ns_ipc::ipc().send(100); }
+  if ( send_ipc ) { ns_ipc::ipc().progress(100); }
   ns_log::write('i', "Download progress: 100%");
   // Make file executable
   using std::filesystem::perms;
-  std::error_code ec;
   fs::permissions(path_file, perms::owner_all | perms::group_all | perms::others_read, ec);
   elog_if(ec, "Failed to change permissions of file '{}': '{}'"_fmt(path_file, ec.message()));
   // Success
@@ -140,13 +228,14 @@ struct sources_layer_ret_t
 
 // fetch_on_failed_check() {{{
 [[nodiscard]] inline std::error<std::string> fetch_on_failed_check(fs::path const& path_file
-  , cpr::Url const& url)
+  , cpr::Url const& url
+  , std::string const& name)
 {
   qreturn_if(check_file(path_file, url), std::nullopt);
 
   ns_log::write('i', "Failed to check SHA for file ", path_file);
 
-  if(auto expected_path_file = fetch_file_from_url(path_file, url); not expected_path_file )
+  if(auto expected_path_file = fetch_file_from_url(path_file, url, true, name); not expected_path_file )
   {
     return expected_path_file.error();
   } // if
@@ -175,8 +264,12 @@ struct sources_layer_ret_t
   fs::path path_dir_dst = (platform == ns_enum::Platform::LINUX)?
       opt_path_file_sources->parent_path() / "cache/linux.flatimage"
     : opt_path_file_sources->parent_path() / "cache/{}.layer"_fmt(ns_enum::to_string_lower(platform));
+  // Name of the layer shown in the progress
+  std::string str_name = (platform == ns_enum::Platform::WINE)?
+      "wine ({})"_fmt(db_build->dist_wine)
+    : ns_enum::to_string_lower(platform);
   // Create destination / url pair
-  return sources_layer_ret_t { .path = path_dir_dst, .url = cpr::Url(str_url_layer), };
+  return sources_layer_ret_t { .path = path_dir_dst, .url = cpr::Url(str_url_layer), .name = str_name };
 } // sources_layer() }}}
 
 // fetch_layer() {{{
@@ -185,12 +278,12 @@ struct sources_layer_ret_t
   // Resolve URL
   auto expected_path_and_url_layer = sources_layer(platform);
   qreturn_if(not expected_path_and_url_layer, std::unexpected(expected_path_and_url_layer.error()));
-  auto [path_target, url] = *expected_path_and_url_layer;
+  auto [path_target, url, name] = *expected_path_and_url_layer;
   // Fetch
-  auto error_fetch = fetch_on_failed_check(path_target, url);
+  auto error_fetch = fetch_on_failed_check(path_target, url, name);
   qreturn_if(error_fetch, std::unexpected(*error_fetch));
   // Send 100% completion
-  ns_ipc::ipc().send(100);
+  ns_ipc::ipc().progress(100);
   // Return fetched path and url
   return *expected_path_and_url_layer;
 } // fetch() }}}
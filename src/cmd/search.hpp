@@ -6,6 +6,7 @@
 #pragma once
 
 #include <filesystem>
+#include <fstream>
 #include <regex>
 
 #include "fetch.hpp"
@@ -13,9 +14,11 @@
 #include "../common.hpp"
 #include "../enum.hpp"
 
+#include "../std/env.hpp"
 #include "../std/filesystem.hpp"
 
 #include "../lib/log.hpp"
+#include "../lib/subprocess.hpp"
 #include "../lib/db/project.hpp"
 #include "../lib/db/build.hpp"
 #include "../lib/ipc.hpp"
@@ -107,10 +110,43 @@ inline std::vector<std::string> search_remote()
   return ret;
 } // search_remote() }}}
 
+// search_winetricks() {{{
+// Lines of 'winetricks list-all', the categories are kept as '===== name =====' headers
+inline std::vector<std::string> search_winetricks(ns_db::ns_build::Build const& db_build
+  , ns_db::ns_build::Metadata const& db_metadata)
+{
+  std::vector<std::string> ret;
+  fs::path path_file_list = db_build.path_dir_cache / "winetricks.list";
+  // Do not read the list of a previous run if this one fails
+  lec(fs::remove, path_file_list);
+  // Winetricks reports the verbs installed in the prefix of the project
+  ns_env::set("WINEPREFIX", (db_metadata.path_dir_project / "wine").c_str(), ns_env::Replace::Y);
+  // The output handlers run in a child process, so the list is written to a file
+  auto opt_code = ns_subprocess::Subprocess("/fim/static/fim_portal")
+    .with_piped_outputs()
+    .with_args(db_build.path_file_image
+      , "fim-exec"
+      , "bash"
+      , "-c"
+      , R"(/opt/wine/bin/wine.sh winetricks list-all > "$1")"
+      , "--"
+      , path_file_list)
+    .spawn()
+    .wait();
+  ethrow_if(opt_code != 0, "Could not list winetricks verbs, exit code '{}'"_fmt(opt_code.value_or(-1)));
+  std::ifstream file_list(path_file_list);
+  ethrow_if(not file_list.is_open(), "Could not open winetricks list '{}'"_fmt(path_file_list));
+  for(std::string line; std::getline(file_list, line);)
+  {
+    if ( not line.empty() ) { ret.push_back(line); } // if
+  } // for
+  return ret;
+} // search_winetricks() }}}
+
 // send() {{{
 auto send(auto&& vec_paths, ns_ipc::Ipc& ipc)
 {
-  std::ranges::for_each(vec_paths, [&](auto&& e){ ipc.send(e); });
+  std::ranges::for_each(vec_paths, [&](auto&& e){ ipc.item(e); });
 } // send() }}}
 
 } // anonymous namespace
@@ -142,8 +178,14 @@ inline void search_remote(std::optional<std::string> opt_query)
   {
     case ns_enum::Platform::RETROARCH: send(search_remote(), ns_ipc::ipc());
     break;
-    case ns_enum::Platform::LINUX:
     case ns_enum::Platform::WINE:
+    {
+      // Check if is winetricks
+      "Only winetricks operation is available for wine"_throw_if([&]{ return op != Op::WINETRICKS; });
+      send(search_winetricks(*db_build, db_metadata), ns_ipc::ipc());
+    } // case
+    break;
+    case ns_enum::Platform::LINUX:
     case ns_enum::Platform::PCSX2:
     case ns_enum::Platform::RPCS3: "Not implemented"_throw();
   } // switch
@@ -196,8 +196,8 @@ void init_impl(fs::path const& path_dir_project, ns_enum::Platform const& platfo
     db_project("path_file_icon")  = db_project.template value_or_default<std::string>("path_file_icon");
     db_project("path_file_rom")   = db_project.template value_or_default<std::string>("path_file_rom");
     db_project("paths_file_bios") = db_project.template value_or_default<std::vector<fs::path>>("paths_file_bios");
-    db_project("paths_file_core") = db_project.template value_or_default<std::vector<fs::path>>("paths_file_bios");
-    db_project("paths_file_rom")  = db_project.template value_or_default<std::vector<fs::path>>("paths_file_bios");
+    db_project("paths_file_core") = db_project.template value_or_default<std::vector<fs::path>>("paths_file_core");
+    db_project("paths_file_rom")  = db_project.template value_or_default<std::vector<fs::path>>("paths_file_rom");
   }
   , fs::exists(path_file_database)? ns_db::Mode::UPDATE : ns_db::Mode::CREATE);
 } // init_impl() }}}
@@ -7,7 +7,11 @@
 
 #include <sys/ipc.h>
 #include <sys/msg.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
 #include <cstring>
+#include <nlohmann/json.hpp>
 
 #include "../common.hpp"
 #include "../std/concepts.hpp"
@@ -27,23 +31,91 @@ struct message_buffer
   char message_text[1024];
 };
 
+// Maximum size of the event data in a single frame, the json escaping of the data can at most
+// double its size and the frame header must fit in the remaining space of message_text
+constexpr size_t SIZE_FRAME_DATA = 448;
+
 // class Ipc {{{
+// Events are json objects with a 'type' field, they are split in frames of the form
+// { "id": <event id>, "part": <index>, "parts": <count>, "data": <slice of the event json> }
+// Frames are written to the unix socket in GIMG_IPC_SOCKET, one per line, when it is set,
+// otherwise they are sent through the message queue keyed by the path of the binary
 class Ipc
 {
   private:
     key_t m_key;
     int m_message_queue_id;
+    int m_socket;
     message_buffer m_buffer;
+    uint64_t m_id;
     Ipc();
+    ~Ipc();
+    void connect_socket(std::string const& str_path_socket);
+    void connect_queue();
+    void send_raw(std::string const& data);
+    void send_event(nlohmann::json const& json_event);
   public:
+    void progress(double value);
+    void download(std::string const& name, uint64_t bytes, uint64_t total, double rate);
+    template<ns_concept::AsString T>
+    void item(T&& t);
+    template<ns_concept::AsString T>
+    void log(T&& t);
     template<ns_concept::AsString T>
-    void send(T&& t);
+    void warning(T&& t);
+    template<ns_concept::AsString T>
+    void error(int code, T&& t);
   friend Ipc& ipc();
 }; // class Ipc }}}
 
 // Ipc::Ipc() {{{
 inline Ipc::Ipc()
-  : m_buffer({ .message_type = 1, .message_text = "" })
+  : m_key(-1)
+  , m_message_queue_id(-1)
+  , m_socket(-1)
+  , m_buffer({ .message_type = 1, .message_text = "" })
+  , m_id(0)
+{
+  if ( const char* str_path_socket = std::getenv("GIMG_IPC_SOCKET"); str_path_socket != nullptr )
+  {
+    connect_socket(str_path_socket);
+  } // if
+  else
+  {
+    connect_queue();
+  } // else
+} // Ipc::Ipc() }}}
+
+// Ipc::~Ipc() {{{
+inline Ipc::~Ipc()
+{
+  if ( m_socket != -1 ) { ::close(m_socket); }
+} // Ipc::~Ipc() }}}
+
+// Ipc::connect_socket() {{{
+inline void Ipc::connect_socket(std::string const& str_path_socket)
+{
+  ns_log::write('i', "Starting IPC on socket ", str_path_socket);
+  struct sockaddr_un addr{};
+  addr.sun_family = AF_UNIX;
+  if ( str_path_socket.size() >= sizeof(addr.sun_path) )
+  {
+    "Socket path '{}' is too long"_throw(str_path_socket);
+  } // if
+  strncpy(addr.sun_path, str_path_socket.c_str(), sizeof(addr.sun_path) - 1);
+  // Do not leak the socket to child processes, e.g., wine, the frontend waits for it to close
+  if ( m_socket = socket(AF_UNIX, SOCK_STREAM | SOCK_CLOEXEC, 0); m_socket == -1 )
+  {
+    "Could not create socket: {}"_throw(strerror(errno));
+  } // if
+  if ( connect(m_socket, reinterpret_cast<struct sockaddr*>(&addr), sizeof(addr)) == -1 )
+  {
+    "Could not connect to socket '{}': {}"_throw(str_path_socket, strerror(errno));
+  } // if
+} // Ipc::connect_socket() }}}
+
+// Ipc::connect_queue() {{{
+inline void Ipc::connect_queue()
 {
   fs::path path_file_self = ns_fs::ns_path::file_self<true>()._ret;
   ns_log::write('i', "Starting IPC for ", path_file_self);
@@ -66,15 +138,27 @@ inline Ipc::Ipc()
     "msgget failed, could not create message queue for identifier '{}': {}"_throw(identifier, strerror(errno));
   } // if
   ns_log::write('i', "Message queue id: ", m_message_queue_id);
-} // Ipc::Ipc() }}}
+} // Ipc::connect_queue() }}}
 
-// Ipc::send() {{{
-template<ns_concept::AsString T>
-void Ipc::send(T&& t)
+// Ipc::send_raw() {{{
+inline void Ipc::send_raw(std::string const& data)
 {
-  std::string data = ns_string::to_string(t);
-  // Limit data size
-  size_t data_length = std::min(data.size(), sizeof(m_buffer));
+  // Socket, write the whole line
+  if ( m_socket != -1 )
+  {
+    std::string line = data + "\n";
+    for(size_t sent = 0; sent < line.size();)
+    {
+      ssize_t ret = send(m_socket, line.data() + sent, line.size() - sent, MSG_NOSIGNAL);
+      if ( ret == -1 and errno == EINTR ) { continue; }
+      if ( ret == -1 ) { perror("Failure to send message"); return; }
+      sent += static_cast<size_t>(ret);
+    } // for
+    return;
+  } // if
+  // Message queue
+  // Limit data size, keep space for the null terminator
+  size_t data_length = std::min(data.size(), sizeof(m_buffer.message_text) - 1);
   // Copy the contents of std::string to the message_text buffer
   strncpy(m_buffer.message_text, data.c_str(), data_length);
   // Ensure null termination
@@ -84,7 +168,89 @@ void Ipc::send(T&& t)
   {
     perror("Failure to send message");
   } // if
-} // Ipc::send() }}}
+} // Ipc::send_raw() }}}
+
+// Ipc::send_event() {{{
+inline void Ipc::send_event(nlohmann::json const& json_event)
+{
+  // Strings which are not valid utf-8, e.g., file names, are sent with the replacement character
+  // instead of failing the event
+  std::string data = json_event.dump(-1, ' ', false, nlohmann::json::error_handler_t::replace);
+  // Split the data in parts, without breaking utf-8 sequences
+  std::vector<std::string> parts;
+  for(size_t begin = 0; begin < data.size();)
+  {
+    size_t length = std::min(SIZE_FRAME_DATA, data.size() - begin);
+    while ( begin + length < data.size() and (data[begin + length] & 0xC0) == 0x80 ) { --length; }
+    parts.push_back(data.substr(begin, length));
+    begin += length;
+  } // for
+  // Send each part in a frame
+  ++m_id;
+  for(size_t i = 0; i < parts.size(); ++i)
+  {
+    nlohmann::json json_frame;
+    json_frame["id"] = m_id;
+    json_frame["part"] = i;
+    json_frame["parts"] = parts.size();
+    json_frame["data"] = parts[i];
+    send_raw(json_frame.dump(-1, ' ', false, nlohmann::json::error_handler_t::replace));
+  } // for
+} // Ipc::send_event() }}}
+
+// Ipc::progress() {{{
+inline void Ipc::progress(double value)
+{
+  send_event({{"type", "progress"}, {"value", value}});
+} // Ipc::progress() }}}
+
+// Ipc::download() {{{
+// Progress of a download, rate is in bytes per second and the eta in seconds, if known
+inline void Ipc::download(std::string const& name, uint64_t bytes, uint64_t total, double rate)
+{
+  nlohmann::json json_event =
+  {
+      {"type", "download"}
+    , {"name", name}
+    , {"bytes", bytes}
+    , {"total", total}
+    , {"rate", rate}
+    , {"eta", nullptr}
+  };
+  if ( rate > 0 and total >= bytes )
+  {
+    json_event["eta"] = static_cast<uint64_t>(static_cast<double>(total - bytes) / rate);
+  } // if
+  send_event(json_event);
+} // Ipc::download() }}}
+
+// Ipc::item() {{{
+template<ns_concept::AsString T>
+void Ipc::item(T&& t)
+{
+  send_event({{"type", "item"}, {"value", ns_string::to_string(t)}});
+} // Ipc::item() }}}
+
+// Ipc::log() {{{
+template<ns_concept::AsString T>
+void Ipc::log(T&& t)
+{
+  send_event({{"type", "log"}, {"line", ns_string::to_string(t)}});
+} // Ipc::log() }}}
+
+// Ipc::warning() {{{
+template<ns_concept::AsString T>
+void Ipc::warning(T&& t)
+{
+  send_event({{"type", "warning"}, {"message", ns_string::to_string(t)}});
+} // Ipc::warning() }}}
+
+// Ipc::error() {{{
+template<ns_concept::AsString T>
+void Ipc::error(int code, T&& t)
+{
+  send_event({{"type", "error"}, {"code", code}, {"message", ns_string::to_string(t)}});
+} // Ipc::error() }}}
 
 // ipc() {{{
 inline Ipc& ipc()
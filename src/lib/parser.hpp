@@ -24,8 +24,12 @@ namespace fs = std::filesystem;
 
 } // namespace
 
+// Version of the json protocol spoken with the gui
+constexpr int PROTOCOL_VERSION = 3;
+
 enum class Op
 {
+  VERSION,
   FETCH,
   INIT,
   PROJECT,
@@ -261,7 +265,17 @@ struct Package
   return package;
 } // parse_package() }}}
 
-using Command = std::variant<Init,Fetch,Project,Install,Compress,Search,Select,Test,Desktop,Package>;
+// parse_version() {{{
+struct Version
+{
+};
+
+[[nodiscard]] inline std::expected<Version, std::string> parse_version([[maybe_unused]] auto&)
+{
+  return Version{};
+} // parse_version() }}}
+
+using Command = std::variant<Version,Init,Fetch,Project,Install,Compress,Search,Select,Test,Desktop,Package>;
 
 // parse() {{{
 [[nodiscard]] inline std::expected<Command, std::string> parse(int argc, char** argv)
@@ -273,9 +287,15 @@ using Command = std::variant<Init,Fetch,Project,Install,Compress,Search,Select,T
   return ns_db::from_string<Ret>(argv[1], [](ns_db::Db& db) -> Ret
   {
     Command command;
+    // Refuse requests from a gui that speaks another protocol version
+    if ( auto version = db.template value<int>("version"); version and *version != PROTOCOL_VERSION )
+    {
+      return std::unexpected("Protocol version mismatch, expected {} got {}"_fmt(PROTOCOL_VERSION, *version));
+    } // if
     auto op = ehope(db.template value<std::string>("op"));
     switch(ns_enum::from_string<Op>(op))
     {
+      case Op::VERSION: command = ehope(parse_version(db)); break;
       case Op::FETCH: command = ehope(parse_fetch(db)); break;
       case Op::INIT: command = ehope(parse_init(db)); break;
       case Op::PROJECT: command = ehope(parse_project(db)); break;
@@ -33,6 +33,12 @@
 // Start logging
 INITIALIZE_EASYLOGGINGPP
 
+// version() {{{
+void version()
+{
+  ns_ipc::ipc().item(std::to_string(ns_parser::PROTOCOL_VERSION));
+} // version() }}}
+
 // init() {{{
 void init(ns_parser::Init const& parser)
 {
@@ -55,7 +61,7 @@ void fetch(ns_parser::Fetch const& parser)
       auto vec_platform = ns_fetch::installed();
       // Send platforms
       std::ranges::for_each(vec_platform | std::views::transform([](auto&& e){ return ns_enum::to_string_lower(e); })
-        , [&](auto&& e) { ns_ipc::ipc().send(e); }
+        , [&](auto&& e) { ns_ipc::ipc().item(e); }
       );
     } // if
     break;
@@ -141,12 +147,20 @@ int parse(int argc, char** argv)
 {
   // Parse arguments
   auto parsed = ns_parser::parse(argc, argv);
+  if ( not parsed )
+  {
+    try { ns_ipc::ipc().error(EXIT_FAILURE, parsed.error()); } catch(...) {}
+  } // if
   ereturn_if(not parsed, parsed.error(), EXIT_FAILURE);
   // Call functions
-  if ( auto* cmd = std::get_if<ns_parser::Fetch>(&parsed.value()) )
+  if ( std::get_if<ns_parser::Version>(&parsed.value()) )
   {
-    fetch(*cmd);
+    version();
   } // if
+  else if ( auto* cmd = std::get_if<ns_parser::Fetch>(&parsed.value()) )
+  {
+    fetch(*cmd);
+  } // else if
   else if ( auto* cmd = std::get_if<ns_parser::Init>(&parsed.value()) )
   {
     init(*cmd);
@@ -204,6 +218,8 @@ int main(int argc, char** argv)
   catch(std::exception const& e)
   {
     std::cerr << "Exception: " << e.what() << '\n';
+    // Report the failure to the gui, the ipc transport might not be available
+    try { ns_ipc::ipc().error(EXIT_FAILURE, e.what()); } catch(...) {}
     return EXIT_FAILURE;
   } // catch
 }